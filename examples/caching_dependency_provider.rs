@@ -3,19 +3,25 @@
 use std::cell::RefCell;
 use std::error::Error;
 
+use pubgrub::incompatibility::Incompatibility;
 use pubgrub::package::Package;
 use pubgrub::range::Range;
 use pubgrub::solver::{resolve, Dependencies, DependencyProvider, OfflineDependencyProvider};
-use pubgrub::version::{NumberVersion, Version};
+use pubgrub::version::NumberVersion;
+use pubgrub::version_set::VersionSet;
+
+type NumVS = Range<NumberVersion>;
 
 // An example implementing caching dependency provider that will
 // store queried dependencies in memory and check them before querying more from remote.
-struct CachingDependencyProvider<P: Package, V: Version, DP: DependencyProvider<P, V>> {
+struct CachingDependencyProvider<P: Package, VS: VersionSet, DP: DependencyProvider<P, VS>> {
     remote_dependencies: DP,
-    cached_dependencies: RefCell<OfflineDependencyProvider<P, V>>,
+    cached_dependencies: RefCell<OfflineDependencyProvider<P, VS>>,
 }
 
-impl<P: Package, V: Version, DP: DependencyProvider<P, V>> CachingDependencyProvider<P, V, DP> {
+impl<P: Package, VS: VersionSet, DP: DependencyProvider<P, VS>>
+    CachingDependencyProvider<P, VS, DP>
+{
     pub fn new(remote_dependencies_provider: DP) -> Self {
         CachingDependencyProvider {
             remote_dependencies: remote_dependencies_provider,
@@ -24,48 +30,45 @@ impl<P: Package, V: Version, DP: DependencyProvider<P, V>> CachingDependencyProv
     }
 }
 
-impl<P: Package, V: Version, DP: DependencyProvider<P, V>> DependencyProvider<P, V>
-    for CachingDependencyProvider<P, V, DP>
+impl<P: Package, VS: VersionSet, DP: DependencyProvider<P, VS>> DependencyProvider<P, VS>
+    for CachingDependencyProvider<P, VS, DP>
 {
-    fn choose_package_version<T: std::borrow::Borrow<P>, U: std::borrow::Borrow<Range<V>>>(
+    fn choose_package_version<T: std::borrow::Borrow<P>, U: std::borrow::Borrow<VS>>(
         &self,
         packages: impl Iterator<Item = (T, U)>,
-    ) -> Result<(T, Option<V>), Box<dyn Error>> {
+    ) -> Result<(T, Option<VS::V>), Box<dyn Error>> {
         self.remote_dependencies.choose_package_version(packages)
     }
 
+    fn prioritize(&self, package: &P, range: &VS) -> u32 {
+        self.remote_dependencies.prioritize(package, range)
+    }
+
+    fn known_incompatibilities(&self) -> Vec<Incompatibility<P, VS>> {
+        self.remote_dependencies.known_incompatibilities()
+    }
+
     // Caches dependencies if they were already queried
     fn get_dependencies(
         &self,
         package: &P,
-        version: &V,
-    ) -> Result<Dependencies<P, V>, Box<dyn Error>> {
+        version: &VS::V,
+    ) -> Result<Dependencies<P, VS>, Box<dyn Error>> {
         let mut cache = self.cached_dependencies.borrow_mut();
-        match cache.get_dependencies(package, version) {
-            Ok(Dependencies::Unknown) => {
-                let dependencies = self.remote_dependencies.get_dependencies(package, version);
-                match dependencies {
-                    Ok(Dependencies::Known(dependencies)) => {
-                        cache.add_dependencies(
-                            package.clone(),
-                            version.clone(),
-                            dependencies.clone().into_iter(),
-                        );
-                        Ok(Dependencies::Known(dependencies))
-                    }
-                    Ok(Dependencies::Unknown) => Ok(Dependencies::Unknown),
-                    error @ Err(_) => error,
-                }
-            }
-            dependencies @ Ok(_) => dependencies,
-            error @ Err(_) => error,
+        if let Some(dependencies) = cache.dependencies(package, version) {
+            return Ok(Dependencies::Known(dependencies.clone()));
+        }
+        let dependencies = self.remote_dependencies.get_dependencies(package, version);
+        if let Ok(Dependencies::Known(dependencies)) = &dependencies {
+            cache.add_dependencies(package.clone(), version.clone(), dependencies.clone());
         }
+        dependencies
     }
 }
 
 fn main() {
     // Simulating remote provider locally.
-    let mut remote_dependencies_provider = OfflineDependencyProvider::<&str, NumberVersion>::new();
+    let mut remote_dependencies_provider = OfflineDependencyProvider::<&str, NumVS>::new();
 
     // Add dependencies as needed. Here only root package is added.
     remote_dependencies_provider.add_dependencies("root", 1, Vec::new());
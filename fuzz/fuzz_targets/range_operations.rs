@@ -0,0 +1,31 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Check the invariants of set operations on ranges.
+//! Run with `cargo fuzz run range_operations` from the root of the repository.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pubgrub::range::Range;
+use pubgrub::version::NumberVersion;
+
+fuzz_target!(
+    |input: (Range<NumberVersion>, Range<NumberVersion>, NumberVersion)| {
+        let (r1, r2, v) = input;
+        assert_eq!(r1.negate().negate(), r1);
+        assert_eq!(
+            r1.intersection(&r2).contains(&v),
+            r1.contains(&v) && r2.contains(&v)
+        );
+        assert_eq!(
+            r1.union(&r2).contains(&v),
+            r1.contains(&v) || r2.contains(&v)
+        );
+        assert_eq!(r1.negate().contains(&v), !r1.contains(&v));
+        assert_eq!(
+            r1.union(&r2).negate(),
+            r1.negate().intersection(&r2.negate())
+        );
+        assert!(r1.intersection(&r2).subset_of(&r1));
+    }
+);
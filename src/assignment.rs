@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Assignments of the partial solution built by the solver.
+//!
+//! Every assignment is either a decision, picking a version of a package,
+//! or a derivation, a term deduced from an incompatibility
+//! and the previous assignments.
+//! They are listed in the order in which they were made
+//! by [resolve_with_assignments](crate::solver::resolve_with_assignments),
+//! which is useful to audit why each package was chosen.
+//! Assignments can be inspected, but not constructed outside of the solver.
+//!
+//! ```
+//! # use pubgrub::assignment::Assignment;
+//! # use pubgrub::range::Range;
+//! # use pubgrub::solver::{resolve_with_assignments, OfflineDependencyProvider};
+//! # use pubgrub::version::NumberVersion;
+//! #
+//! let mut dependency_provider = OfflineDependencyProvider::<&str, Range<NumberVersion>>::new();
+//! dependency_provider.add_dependencies("root", 1, vec![("menu", Range::any())]);
+//! dependency_provider.add_dependencies("menu", 1, vec![]);
+//!
+//! let (solution, assignments) = resolve_with_assignments(&dependency_provider, "root", 1);
+//! assert!(solution.is_ok());
+//! for assignment in &assignments {
+//!     match assignment {
+//!         Assignment::Decision(decision) => {
+//!             println!("decided {} {}", decision.package(), decision.version())
+//!         }
+//!         Assignment::Derivation(derivation) => println!(
+//!             "derived {} {} because {}",
+//!             derivation.package(),
+//!             derivation.term(),
+//!             derivation.cause()
+//!         ),
+//!     }
+//! }
+//! ```
+
+use crate::incompatibility::Incompatibility;
+use crate::package::Package;
+use crate::term::Term;
+use crate::version_set::VersionSet;
+
+/// A decision or a derivation of the partial solution.
+#[derive(Debug, Clone)]
+pub enum Assignment<P: Package, VS: VersionSet> {
+    /// A version was picked for a package.
+    Decision(Decision<P, VS>),
+    /// A term was derived for a package.
+    Derivation(Derivation<P, VS>),
+}
+
+/// A version picked for a package.
+#[derive(Debug, Clone)]
+pub struct Decision<P: Package, VS: VersionSet> {
+    package: P,
+    version: VS::V,
+    decision_level: u32,
+}
+
+/// A term derived for a package from an incompatibility,
+/// since all the other terms of that incompatibility were satisfied.
+#[derive(Debug, Clone)]
+pub struct Derivation<P: Package, VS: VersionSet> {
+    package: P,
+    term: Term<VS>,
+    cause: Incompatibility<P, VS>,
+    decision_level: u32,
+}
+
+impl<P: Package, VS: VersionSet> Assignment<P, VS> {
+    /// Package of this assignment.
+    pub fn package(&self) -> &P {
+        match self {
+            Self::Decision(decision) => decision.package(),
+            Self::Derivation(derivation) => derivation.package(),
+        }
+    }
+
+    /// Decision level of this assignment,
+    /// which is the number of decisions made up to and including this one.
+    pub fn decision_level(&self) -> u32 {
+        match self {
+            Self::Decision(decision) => decision.decision_level(),
+            Self::Derivation(derivation) => derivation.decision_level(),
+        }
+    }
+
+    /// Term of this assignment, exactly the picked version for decisions.
+    pub fn as_term(&self) -> Term<VS> {
+        match self {
+            Self::Decision(decision) => Term::Positive(VS::singleton(decision.version.clone())),
+            Self::Derivation(derivation) => derivation.term.clone(),
+        }
+    }
+}
+
+impl<P: Package, VS: VersionSet> Decision<P, VS> {
+    pub(crate) fn new(package: P, version: VS::V, decision_level: u32) -> Self {
+        Self {
+            package,
+            version,
+            decision_level,
+        }
+    }
+
+    /// Package of this decision.
+    pub fn package(&self) -> &P {
+        &self.package
+    }
+
+    /// Version picked for the package.
+    pub fn version(&self) -> &VS::V {
+        &self.version
+    }
+
+    /// Decision level of this decision.
+    pub fn decision_level(&self) -> u32 {
+        self.decision_level
+    }
+}
+
+impl<P: Package, VS: VersionSet> Derivation<P, VS> {
+    pub(crate) fn new(
+        package: P,
+        term: Term<VS>,
+        cause: Incompatibility<P, VS>,
+        decision_level: u32,
+    ) -> Self {
+        Self {
+            package,
+            term,
+            cause,
+            decision_level,
+        }
+    }
+
+    /// Package of this derivation.
+    pub fn package(&self) -> &P {
+        &self.package
+    }
+
+    /// Term derived for the package.
+    pub fn term(&self) -> &Term<VS> {
+        &self.term
+    }
+
+    /// Incompatibility from which the term was derived.
+    pub fn cause(&self) -> &Incompatibility<P, VS> {
+        &self.cause
+    }
+
+    /// Decision level of this derivation.
+    pub fn decision_level(&self) -> u32 {
+        self.decision_level
+    }
+}
@@ -2,32 +2,40 @@
 
 //! Handling pubgrub errors.
 
-use thiserror::Error;
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::fmt;
 
 use crate::package::Package;
-use crate::report::DerivationTree;
-use crate::version::Version;
+use crate::report::{DerivationTree, External};
+use crate::version_set::VersionSet;
+
+// Errors of dependency providers are boxed trait objects of the `Error` trait of `std`,
+// only taken from `core`, stable since Rust 1.81, without the `std` feature.
+#[cfg(feature = "std")]
+pub(crate) use std::error::Error;
+
+#[cfg(not(feature = "std"))]
+pub(crate) use core::error::Error;
 
 /// Errors that may occur while solving dependencies.
-#[derive(Error, Debug)]
-pub enum PubGrubError<P: Package, V: Version> {
+#[derive(Debug)]
+pub enum PubGrubError<P: Package, VS: VersionSet> {
     /// There is no solution for this set of dependencies.
-    #[error("No solution")]
-    NoSolution(DerivationTree<P, V>),
+    NoSolution(DerivationTree<P, VS>),
 
     /// Error arising when the implementer of
     /// [DependencyProvider](crate::solver::DependencyProvider)
     /// returned an error in the method
     /// [get_dependencies](crate::solver::DependencyProvider::get_dependencies).
-    #[error("Retrieving dependencies of {package} {version} failed")]
     ErrorRetrievingDependencies {
         /// Package whose dependencies we want.
         package: P,
         /// Version of the package for which we want the dependencies.
-        version: V,
+        version: VS::V,
         /// Error raised by the implementer of
         /// [DependencyProvider](crate::solver::DependencyProvider).
-        source: Box<dyn std::error::Error>,
+        source: Box<dyn Error>,
     },
 
     /// Error arising when the implementer of
@@ -35,12 +43,11 @@ pub enum PubGrubError<P: Package, V: Version> {
     /// returned a dependency on an empty range.
     /// This technically means that the package can not be selected,
     /// but is clearly some kind of mistake.
-    #[error("Package {dependent} required by {package} {version} depends on the empty set")]
     DependencyOnTheEmptySet {
         /// Package whose dependencies we want.
         package: P,
         /// Version of the package for which we want the dependencies.
-        version: V,
+        version: VS::V,
         /// The dependent package that requires us to pick from the empty set.
         dependent: P,
     },
@@ -50,27 +57,236 @@ pub enum PubGrubError<P: Package, V: Version> {
     /// returned a dependency on the requested package.
     /// This technically means that the package directly depends on itself,
     /// and is clearly some kind of mistake.
-    #[error("{package} {version} depends on itself")]
     SelfDependency {
         /// Package whose dependencies we want.
         package: P,
         /// Version of the package for which we want the dependencies.
-        version: V,
+        version: VS::V,
+    },
+
+    /// Error arising when a required package does not exist according to
+    /// [package_exists](crate::solver::DependencyProvider::package_exists).
+    PackageNotFound {
+        /// Package that does not exist.
+        package: P,
+    },
+
+    /// Error arising when [verify_lock](crate::solver::verify_lock)
+    /// found a dependency that is not satisfied by the lock.
+    LockViolation {
+        /// Package whose dependency is not satisfied.
+        package: P,
+        /// Version of that package.
+        version: VS::V,
+        /// The dependency that is not satisfied.
+        dependency: P,
+        /// Locked version of the dependency, outside of the required range,
+        /// or [None] if the dependency is missing from the lock.
+        locked: Option<VS::V>,
+    },
+
+    /// Error arising when [verify_lock](crate::solver::verify_lock)
+    /// reached a package version whose dependencies are
+    /// [unknown](crate::solver::Dependencies::Unknown).
+    UnknownDependencies {
+        /// Package whose dependencies are unknown.
+        package: P,
+        /// Version of that package.
+        version: VS::V,
+    },
+
+    /// Error arising when [verify_lock](crate::solver::verify_lock)
+    /// found locked versions matching one of the
+    /// [known incompatibilities](crate::solver::DependencyProvider::known_incompatibilities).
+    LockIncompatibility(External<P, VS>),
+
+    /// Error arising when the implementer of
+    /// [DependencyProvider](crate::solver::DependencyProvider)
+    /// has no version to offer for the root package and version to solve,
+    /// typically because that version was never registered.
+    RootVersionNotAvailable {
+        /// Root package of the resolution.
+        package: P,
+        /// Requested version of the root package.
+        version: VS::V,
     },
 
     /// Error arising when the implementer of
     /// [DependencyProvider](crate::solver::DependencyProvider)
     /// returned an error in the method
     /// [choose_package_version](crate::solver::DependencyProvider::choose_package_version).
-    #[error("Decision making failed")]
-    ErrorChoosingPackageVersion(Box<dyn std::error::Error>),
+    ErrorChoosingPackageVersion(Box<dyn Error>),
 
     /// Error arising when the implementer of [DependencyProvider](crate::solver::DependencyProvider)
     /// returned an error in the method [should_cancel](crate::solver::DependencyProvider::should_cancel).
-    #[error("We should cancel")]
-    ErrorInShouldCancel(Box<dyn std::error::Error>),
+    ErrorInShouldCancel(Box<dyn Error>),
+
+    /// Error arising when [replay](crate::solver::replay) of a recorded resolution
+    /// did not go through the same steps as the recorded ones.
+    ReplayDivergence {
+        /// Index of the first step that differs from the recorded trace.
+        step: usize,
+    },
 
     /// Something unexpected happened.
-    #[error("{0}")]
     Failure(String),
 }
+
+impl<P: Package, VS: VersionSet> PubGrubError<P, VS> {
+    /// Replace every package of this error.
+    pub(crate) fn map_packages<Q: Package>(
+        self,
+        f: &mut impl FnMut(P) -> Q,
+    ) -> PubGrubError<Q, VS> {
+        match self {
+            Self::NoSolution(tree) => PubGrubError::NoSolution(tree.map_packages(f)),
+            Self::ErrorRetrievingDependencies {
+                package,
+                version,
+                source,
+            } => PubGrubError::ErrorRetrievingDependencies {
+                package: f(package),
+                version,
+                source,
+            },
+            Self::DependencyOnTheEmptySet {
+                package,
+                version,
+                dependent,
+            } => PubGrubError::DependencyOnTheEmptySet {
+                package: f(package),
+                version,
+                dependent: f(dependent),
+            },
+            Self::SelfDependency { package, version } => PubGrubError::SelfDependency {
+                package: f(package),
+                version,
+            },
+            Self::PackageNotFound { package } => PubGrubError::PackageNotFound {
+                package: f(package),
+            },
+            Self::LockViolation {
+                package,
+                version,
+                dependency,
+                locked,
+            } => PubGrubError::LockViolation {
+                package: f(package),
+                version,
+                dependency: f(dependency),
+                locked,
+            },
+            Self::UnknownDependencies { package, version } => PubGrubError::UnknownDependencies {
+                package: f(package),
+                version,
+            },
+            Self::LockIncompatibility(external) => {
+                PubGrubError::LockIncompatibility(external.map_packages(f))
+            }
+            Self::RootVersionNotAvailable { package, version } => {
+                PubGrubError::RootVersionNotAvailable {
+                    package: f(package),
+                    version,
+                }
+            }
+            Self::ErrorChoosingPackageVersion(source) => {
+                PubGrubError::ErrorChoosingPackageVersion(source)
+            }
+            Self::ErrorInShouldCancel(source) => PubGrubError::ErrorInShouldCancel(source),
+            Self::ReplayDivergence { step } => PubGrubError::ReplayDivergence { step },
+            Self::Failure(msg) => PubGrubError::Failure(msg),
+        }
+    }
+}
+
+impl<P: Package, VS: VersionSet> fmt::Display for PubGrubError<P, VS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSolution(_) => write!(f, "no solution satisfies the dependencies"),
+            Self::ErrorRetrievingDependencies {
+                package,
+                version,
+                source,
+            } => write!(
+                f,
+                "error retrieving dependencies of package '{}' at version {}: {}",
+                package, version, source
+            ),
+            Self::DependencyOnTheEmptySet {
+                package,
+                version,
+                dependent,
+            } => write!(
+                f,
+                "package '{}' at version {} depends on package '{}' with an empty set of versions",
+                package, version, dependent
+            ),
+            Self::SelfDependency { package, version } => write!(
+                f,
+                "package '{}' at version {} depends on itself",
+                package, version
+            ),
+            Self::PackageNotFound { package } => {
+                write!(f, "package '{}' does not exist", package)
+            }
+            Self::LockViolation {
+                package,
+                version,
+                dependency,
+                locked: Some(locked),
+            } => write!(
+                f,
+                "package '{}' at version {} depends on package '{}', \
+                 which is locked at the incompatible version {}",
+                package, version, dependency, locked
+            ),
+            Self::LockViolation {
+                package,
+                version,
+                dependency,
+                locked: None,
+            } => write!(
+                f,
+                "package '{}' at version {} depends on package '{}', \
+                 which is missing from the lock",
+                package, version, dependency
+            ),
+            Self::UnknownDependencies { package, version } => write!(
+                f,
+                "dependencies of package '{}' at version {} are unknown",
+                package, version
+            ),
+            Self::LockIncompatibility(external) => {
+                write!(f, "the lock is incompatible: {}", external)
+            }
+            Self::RootVersionNotAvailable { package, version } => write!(
+                f,
+                "root package '{}' is not available at version {}",
+                package, version
+            ),
+            Self::ErrorChoosingPackageVersion(source) => {
+                write!(f, "error choosing a package version: {}", source)
+            }
+            Self::ErrorInShouldCancel(source) => {
+                write!(f, "resolution was cancelled: {}", source)
+            }
+            Self::ReplayDivergence { step } => write!(
+                f,
+                "replay diverged from the recorded trace at step {}",
+                step
+            ),
+            Self::Failure(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl<P: Package, VS: VersionSet> Error for PubGrubError<P, VS> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::ErrorRetrievingDependencies { source, .. }
+            | Self::ErrorChoosingPackageVersion(source)
+            | Self::ErrorInShouldCancel(source) => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
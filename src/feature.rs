@@ -0,0 +1,177 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Optional features of packages, modeled as pseudo-packages.
+//!
+//! A package may declare additional dependencies that only apply
+//! when one of its features is enabled.
+//! Each feature of a package is represented by its own [FeaturePackage::Feature],
+//! which depends on the exact same version of the [FeaturePackage::Base] package,
+//! in addition to the dependencies of that feature.
+//! Depending on a feature of a package thus enables it,
+//! while the dependencies of features that nobody requires
+//! are never seen by the solver.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt::{self, Display};
+
+use crate::error::{Error, PubGrubError};
+use crate::incompatibility::Incompatibility;
+use crate::package::Package;
+use crate::solver::{resolve, Dependencies, DependencyProvider, OfflineDependencyProvider};
+use crate::type_aliases::SelectedDependencies;
+use crate::version_set::VersionSet;
+
+/// A package, or one of its features.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FeaturePackage<P> {
+    /// The package itself, with its unconditional dependencies.
+    Base(P),
+    /// A feature of the package, with the dependencies it adds.
+    Feature(P, String),
+}
+
+impl<P: Display> Display for FeaturePackage<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Base(package) => write!(f, "{}", package),
+            Self::Feature(package, feature) => write!(f, "{}[{}]", package, feature),
+        }
+    }
+}
+
+impl<P: Package, VS: VersionSet> OfflineDependencyProvider<FeaturePackage<P>, VS> {
+    /// Registers the dependencies added by a feature of a package and version pair.
+    /// The feature also depends on that exact version of the base package,
+    /// so that enabling it cannot select another version of the package.
+    /// Like [add_dependencies](OfflineDependencyProvider::add_dependencies),
+    /// subsequent calls for the same feature replace its dependencies.
+    pub fn add_feature_dependencies<I: IntoIterator<Item = (FeaturePackage<P>, VS)>>(
+        &mut self,
+        package: P,
+        version: impl Into<VS::V>,
+        feature: impl Into<String>,
+        dependencies: I,
+    ) {
+        let version = version.into();
+        let base = (
+            FeaturePackage::Base(package.clone()),
+            VS::singleton(version.clone()),
+        );
+        self.add_dependencies(
+            FeaturePackage::Feature(package, feature.into()),
+            version,
+            core::iter::once(base).chain(dependencies),
+        );
+    }
+}
+
+/// Finds a set of packages satisfying the dependencies of a package and version pair,
+/// with the given features of that package enabled.
+/// Enabled features of all packages appear in the solution
+/// as [FeaturePackage::Feature], at the version of their base package.
+#[allow(clippy::type_complexity)]
+pub fn resolve_with_features<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<FeaturePackage<P>, VS>,
+    package: P,
+    version: impl Into<VS::V>,
+    features: impl IntoIterator<Item = String>,
+) -> Result<SelectedDependencies<FeaturePackage<P>, VS::V>, PubGrubError<FeaturePackage<P>, VS>> {
+    let root_version = version.into();
+    let root_features = features
+        .into_iter()
+        .map(|feature| FeaturePackage::Feature(package.clone(), feature))
+        .collect();
+    let root = FeaturePackage::Base(package);
+    let feature_provider = RootFeaturesDependencyProvider {
+        dependency_provider,
+        root: root.clone(),
+        root_version: root_version.clone(),
+        root_features,
+    };
+    resolve(&feature_provider, root, root_version)
+}
+
+/// Dependency provider used by [resolve_with_features],
+/// adding the enabled features to the dependencies of the root package
+/// and delegating everything else.
+struct RootFeaturesDependencyProvider<'a, P: Package, V, DP> {
+    dependency_provider: &'a DP,
+    root: FeaturePackage<P>,
+    root_version: V,
+    root_features: Vec<FeaturePackage<P>>,
+}
+
+impl<P: Package, VS: VersionSet, DP: DependencyProvider<FeaturePackage<P>, VS>>
+    DependencyProvider<FeaturePackage<P>, VS> for RootFeaturesDependencyProvider<'_, P, VS::V, DP>
+{
+    fn choose_package_version<T: Borrow<FeaturePackage<P>>, U: Borrow<VS>>(
+        &self,
+        potential_packages: impl Iterator<Item = (T, U)>,
+    ) -> Result<(T, Option<VS::V>), Box<dyn Error>> {
+        self.dependency_provider
+            .choose_package_version(potential_packages)
+    }
+
+    fn prioritize(&self, package: &FeaturePackage<P>, range: &VS) -> u32 {
+        self.dependency_provider.prioritize(package, range)
+    }
+
+    fn score_version(&self, package: &FeaturePackage<P>, version: &VS::V) -> i64 {
+        self.dependency_provider.score_version(package, version)
+    }
+
+    fn get_dependencies(
+        &self,
+        package: &FeaturePackage<P>,
+        version: &VS::V,
+    ) -> Result<Dependencies<FeaturePackage<P>, VS>, Box<dyn Error>> {
+        let dependencies = self
+            .dependency_provider
+            .get_dependencies(package, version)?;
+        if package != &self.root || version != &self.root_version {
+            return Ok(dependencies);
+        }
+        Ok(match dependencies {
+            Dependencies::Unknown => Dependencies::Unknown,
+            Dependencies::Known(mut constraints) => {
+                for feature in &self.root_features {
+                    constraints.insert(feature.clone(), VS::singleton(version.clone()));
+                }
+                Dependencies::Known(constraints)
+            }
+        })
+    }
+
+    fn get_dev_dependencies(
+        &self,
+        package: &FeaturePackage<P>,
+        version: &VS::V,
+    ) -> Result<Dependencies<FeaturePackage<P>, VS>, Box<dyn Error>> {
+        self.dependency_provider
+            .get_dev_dependencies(package, version)
+    }
+
+    fn package_exists(&self, package: &FeaturePackage<P>) -> bool {
+        self.dependency_provider.package_exists(package)
+    }
+
+    fn accept_version(&self, package: &FeaturePackage<P>, version: &VS::V) -> bool {
+        self.dependency_provider.accept_version(package, version)
+    }
+
+    fn is_virtual(&self, package: &FeaturePackage<P>) -> bool {
+        self.dependency_provider.is_virtual(package)
+    }
+
+    fn known_incompatibilities(&self) -> Vec<Incompatibility<FeaturePackage<P>, VS>> {
+        self.dependency_provider.known_incompatibilities()
+    }
+
+    fn should_cancel(&self) -> Result<(), Box<dyn Error>> {
+        self.dependency_provider.should_cancel()
+    }
+}
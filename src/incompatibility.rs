@@ -0,0 +1,10 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! An incompatibility is a set of terms for different packages
+//! that should never be satisfied all together.
+//!
+//! Incompatibilities are usually created by the solver from package dependencies,
+//! but externally known ones can also be provided before solving with
+//! [DependencyProvider::known_incompatibilities](crate::solver::DependencyProvider::known_incompatibilities).
+
+pub use crate::internal::incompatibility::Incompatibility;
@@ -1,4 +1,6 @@
-use std::{
+use alloc::vec::Vec;
+use core::{
+    cmp::Ordering,
     fmt,
     hash::{Hash, Hasher},
     marker::PhantomData,
@@ -33,6 +35,18 @@ impl<T> PartialEq for Id<T> {
 
 impl<T> Eq for Id<T> {}
 
+impl<T> PartialOrd for Id<T> {
+    fn partial_cmp(&self, other: &Id<T>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Id<T> {
+    fn cmp(&self, other: &Id<T>) -> Ordering {
+        self.raw.cmp(&other.raw)
+    }
+}
+
 impl<T> Hash for Id<T> {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.raw.hash(state)
@@ -41,7 +55,7 @@ impl<T> Hash for Id<T> {
 
 impl<T> fmt::Debug for Id<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut type_name = std::any::type_name::<T>();
+        let mut type_name = core::any::type_name::<T>();
         if let Some(id) = type_name.rfind(':') {
             type_name = &type_name[id + 1..]
         }
@@ -55,7 +69,7 @@ impl<T> Id<T> {
     }
     fn from(n: u32) -> Self {
         Self {
-            raw: n as u32,
+            raw: n,
             _ty: PhantomData,
         }
     }
@@ -97,6 +111,16 @@ impl<T> Arena<T> {
         Id::from(raw as u32)
     }
 
+    /// Check that this id was allocated in this arena.
+    #[allow(dead_code)]
+    pub fn contains(&self, id: Id<T>) -> bool {
+        id.into_raw() < self.data.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.data.iter()
+    }
+
     pub fn alloc_iter<I: Iterator<Item = T>>(&mut self, values: I) -> Range<Id<T>> {
         let start = Id::from(self.data.len() as u32);
         values.for_each(|v| {
@@ -3,7 +3,8 @@
 //! Core model and functions
 //! to write a functional PubGrub algorithm.
 
-use std::collections::HashSet as Set;
+use alloc::vec;
+use alloc::vec::Vec;
 
 use crate::error::PubGrubError;
 use crate::internal::arena::Arena;
@@ -14,39 +15,46 @@ use crate::internal::partial_solution::SatisfierSearch::{
 use crate::internal::partial_solution::{DecisionLevel, PartialSolution};
 use crate::internal::small_vec::SmallVec;
 use crate::package::Package;
-use crate::report::DerivationTree;
-use crate::solver::DependencyConstraints;
-use crate::type_aliases::Map;
-use crate::version::Version;
+use crate::report::{DerivationTree, External};
+use crate::solver::{BacktrackStrategy, DependencyConstraints, SolverStats};
+use crate::type_aliases::{Map, Set};
+use crate::version_set::VersionSet;
 
 /// Current state of the PubGrub algorithm.
 #[derive(Clone)]
-pub struct State<P: Package, V: Version> {
+pub struct State<P: Package, VS: VersionSet> {
     root_package: P,
-    root_version: V,
+    root_version: VS::V,
 
-    incompatibilities: Map<P, Vec<IncompId<P, V>>>,
+    incompatibilities: Map<P, Vec<IncompId<P, VS>>>,
 
-    /// Store the ids of incompatibilities that are already contradicted
-    /// and will stay that way until the next conflict and backtrack is operated.
-    contradicted_incompatibilities: rustc_hash::FxHashSet<IncompId<P, V>>,
+    /// Store the ids of incompatibilities that are already contradicted,
+    /// with the decision level at which they were found to be.
+    /// They stay contradicted until a backtrack below that level,
+    /// so their relation with the partial solution does not need to be computed again.
+    contradicted_incompatibilities: Map<IncompId<P, VS>, DecisionLevel>,
 
     /// Partial solution.
     /// TODO: remove pub.
-    pub partial_solution: PartialSolution<P, V>,
+    pub partial_solution: PartialSolution<P, VS>,
 
     /// The store is the reference storage for all incompatibilities.
-    pub incompatibility_store: Arena<Incompatibility<P, V>>,
+    pub incompatibility_store: Arena<Incompatibility<P, VS>>,
 
     /// This is a stack of work to be done in `unit_propagation`.
     /// It can definitely be a local variable to that method, but
     /// this way we can reuse the same allocation for better performance.
     unit_propagation_buffer: SmallVec<P>,
+
+    /// Whether the derivation tree explaining a failure is built.
+    /// Without it, a failure is reported with a tree made
+    /// of the single root incompatibility.
+    pub report_failures: bool,
 }
 
-impl<P: Package, V: Version> State<P, V> {
+impl<P: Package, VS: VersionSet> State<P, VS> {
     /// Initialization of PubGrub state.
-    pub fn init(root_package: P, root_version: V) -> Self {
+    pub fn init(root_package: P, root_version: VS::V) -> Self {
         let mut incompatibility_store = Arena::new();
         let not_root_id = incompatibility_store.alloc(Incompatibility::not_root(
             root_package.clone(),
@@ -58,15 +66,16 @@ impl<P: Package, V: Version> State<P, V> {
             root_package,
             root_version,
             incompatibilities,
-            contradicted_incompatibilities: rustc_hash::FxHashSet::default(),
+            contradicted_incompatibilities: Map::default(),
             partial_solution: PartialSolution::empty(),
             incompatibility_store,
             unit_propagation_buffer: SmallVec::Empty,
+            report_failures: true,
         }
     }
 
     /// Add an incompatibility to the state.
-    pub fn add_incompatibility(&mut self, incompat: Incompatibility<P, V>) {
+    pub fn add_incompatibility(&mut self, incompat: Incompatibility<P, VS>) {
         let id = self.incompatibility_store.alloc(incompat);
         self.merge_incompatibility(id);
     }
@@ -75,15 +84,20 @@ impl<P: Package, V: Version> State<P, V> {
     pub fn add_incompatibility_from_dependencies(
         &mut self,
         package: P,
-        version: V,
-        deps: &DependencyConstraints<P, V>,
-    ) -> std::ops::Range<IncompId<P, V>> {
+        version: VS::V,
+        deps: &DependencyConstraints<P, VS>,
+    ) -> core::ops::Range<IncompId<P, VS>> {
         // Create incompatibilities and allocate them in the store.
-        let new_incompats_id_range = self
-            .incompatibility_store
-            .alloc_iter(deps.iter().map(|dep| {
-                Incompatibility::from_dependency(package.clone(), version.clone(), dep)
-            }));
+        let new_incompats_id_range = self.incompatibility_store.alloc_iter(deps.iter().map(
+            |(dep_package, dep_versions)| {
+                Incompatibility::from_dependency(
+                    package.clone(),
+                    VS::singleton(version.clone()),
+                    dep_package.clone(),
+                    dep_versions.clone(),
+                )
+            },
+        ));
         // Merge the newly created incompatibilities with the older ones.
         for id in IncompId::range_to_iter(new_incompats_id_range.clone()) {
             self.merge_incompatibility(id);
@@ -92,13 +106,20 @@ impl<P: Package, V: Version> State<P, V> {
     }
 
     /// Check if an incompatibility is terminal.
-    pub fn is_terminal(&self, incompatibility: &Incompatibility<P, V>) -> bool {
+    pub fn is_terminal(&self, incompatibility: &Incompatibility<P, VS>) -> bool {
         incompatibility.is_terminal(&self.root_package, &self.root_version)
     }
 
     /// Unit propagation is the core mechanism of the solving algorithm.
     /// CF <https://github.com/dart-lang/pub/blob/master/doc/solver.md#unit-propagation>
-    pub fn unit_propagation(&mut self, package: P) -> Result<(), PubGrubError<P, V>> {
+    /// Conflicts are resolved by backtracking to the level chosen by `backtrack_strategy`,
+    /// and derivations, conflicts and backtracks are counted in `stats`.
+    pub fn unit_propagation(
+        &mut self,
+        package: P,
+        backtrack_strategy: &impl BacktrackStrategy,
+        stats: &mut SolverStats,
+    ) -> Result<(), PubGrubError<P, VS>> {
         self.unit_propagation_buffer.clear();
         self.unit_propagation_buffer.push(package);
         while let Some(current_package) = self.unit_propagation_buffer.pop() {
@@ -107,9 +128,13 @@ impl<P: Package, V: Version> State<P, V> {
             let mut conflict_id = None;
             // We only care about incompatibilities if it contains the current package.
             for &incompat_id in self.incompatibilities[&current_package].iter().rev() {
-                if self.contradicted_incompatibilities.contains(&incompat_id) {
+                if self
+                    .contradicted_incompatibilities
+                    .contains_key(&incompat_id)
+                {
                     continue;
                 }
+                stats.relations += 1;
                 let current_incompat = &self.incompatibility_store[incompat_id];
                 match self.partial_solution.relation(current_incompat) {
                     // If the partial solution satisfies the incompatibility
@@ -121,25 +146,31 @@ impl<P: Package, V: Version> State<P, V> {
                     Relation::AlmostSatisfied(package_almost) => {
                         self.unit_propagation_buffer.push(package_almost.clone());
                         // Add (not term) to the partial solution with incompat as cause.
+                        stats.derivations += 1;
                         self.partial_solution.add_derivation(
                             package_almost,
                             incompat_id,
                             &self.incompatibility_store,
                         );
                         // With the partial solution updated, the incompatibility is now contradicted.
-                        self.contradicted_incompatibilities.insert(incompat_id);
+                        self.contradicted_incompatibilities
+                            .insert(incompat_id, self.partial_solution.current_decision_level());
                     }
                     Relation::Contradicted(_) => {
-                        self.contradicted_incompatibilities.insert(incompat_id);
+                        self.contradicted_incompatibilities
+                            .insert(incompat_id, self.partial_solution.current_decision_level());
                     }
                     _ => {}
                 }
             }
             if let Some(incompat_id) = conflict_id {
-                let (package_almost, root_cause) = self.conflict_resolution(incompat_id)?;
+                let (package_almost, root_cause) =
+                    self.conflict_resolution(incompat_id, backtrack_strategy, stats)?;
                 self.unit_propagation_buffer.clear();
                 self.unit_propagation_buffer.push(package_almost.clone());
                 // Add to the partial solution with incompat as cause.
+                stats.conflicts += 1;
+                stats.derivations += 1;
                 self.partial_solution.add_derivation(
                     package_almost,
                     root_cause,
@@ -147,28 +178,57 @@ impl<P: Package, V: Version> State<P, V> {
                 );
                 // After conflict resolution and the partial solution update,
                 // the root cause incompatibility is now contradicted.
-                self.contradicted_incompatibilities.insert(root_cause);
+                self.contradicted_incompatibilities
+                    .insert(root_cause, self.partial_solution.current_decision_level());
             }
         }
         // If there are no more changed packages, unit propagation is done.
         Ok(())
     }
 
+    /// Restart the search right after the decision of the root package,
+    /// at decision level 1, keeping all known incompatibilities.
+    /// Since learned incompatibilities may now be almost satisfied,
+    /// unit propagation is run for all packages.
+    pub fn restart(
+        &mut self,
+        backtrack_strategy: &impl BacktrackStrategy,
+        stats: &mut SolverStats,
+    ) -> Result<(), PubGrubError<P, VS>> {
+        let root_level = DecisionLevel(1);
+        self.partial_solution.backtrack(root_level);
+        self.contradicted_incompatibilities
+            .retain(|_, level| *level <= root_level);
+        let packages: Vec<P> = self.incompatibilities.keys().cloned().collect();
+        for package in packages {
+            self.unit_propagation(package, backtrack_strategy, stats)?;
+        }
+        Ok(())
+    }
+
     /// Return the root cause and the backtracked model.
     /// CF <https://github.com/dart-lang/pub/blob/master/doc/solver.md#unit-propagation>
     fn conflict_resolution(
         &mut self,
-        incompatibility: IncompId<P, V>,
-    ) -> Result<(P, IncompId<P, V>), PubGrubError<P, V>> {
+        incompatibility: IncompId<P, VS>,
+        backtrack_strategy: &impl BacktrackStrategy,
+        stats: &mut SolverStats,
+    ) -> Result<(P, IncompId<P, VS>), PubGrubError<P, VS>> {
         let mut current_incompat_id = incompatibility;
         let mut current_incompat_changed = false;
         loop {
             if self.incompatibility_store[current_incompat_id]
                 .is_terminal(&self.root_package, &self.root_version)
             {
-                return Err(PubGrubError::NoSolution(
-                    self.build_derivation_tree(current_incompat_id),
-                ));
+                let derivation_tree = if self.report_failures {
+                    self.build_derivation_tree(current_incompat_id)
+                } else {
+                    DerivationTree::External(External::NotRoot(
+                        self.root_package.clone(),
+                        self.root_version.clone(),
+                    ))
+                };
+                return Err(PubGrubError::NoSolution(derivation_tree));
             } else {
                 let (package, satisfier_search_result) = self.partial_solution.satisfier_search(
                     &self.incompatibility_store[current_incompat_id],
@@ -177,11 +237,18 @@ impl<P: Package, V: Version> State<P, V> {
                 match satisfier_search_result {
                     DifferentDecisionLevels {
                         previous_satisfier_level,
+                        satisfier_level,
                     } => {
+                        // The learned incompatibility is only almost satisfied
+                        // between the previous satisfier and the satisfier levels.
+                        let level = backtrack_strategy
+                            .backtrack_level(previous_satisfier_level.0, satisfier_level.0)
+                            .clamp(previous_satisfier_level.0, satisfier_level.decrement().0);
+                        stats.backtracks += 1;
                         self.backtrack(
                             current_incompat_id,
                             current_incompat_changed,
-                            previous_satisfier_level,
+                            DecisionLevel(level),
                         );
                         return Ok((package, current_incompat_id));
                     }
@@ -203,13 +270,13 @@ impl<P: Package, V: Version> State<P, V> {
     /// Backtracking.
     fn backtrack(
         &mut self,
-        incompat: IncompId<P, V>,
+        incompat: IncompId<P, VS>,
         incompat_changed: bool,
         decision_level: DecisionLevel,
     ) {
-        self.partial_solution
-            .backtrack(decision_level, &self.incompatibility_store);
-        self.contradicted_incompatibilities.clear();
+        self.partial_solution.backtrack(decision_level);
+        self.contradicted_incompatibilities
+            .retain(|_, level| *level <= decision_level);
         if incompat_changed {
             self.merge_incompatibility(incompat);
         }
@@ -231,10 +298,30 @@ impl<P: Package, V: Version> State<P, V> {
     /// We could collapse them into { foo (1.0.0 ∪ 1.1.0), not bar ^1.0.0 }
     /// without having to check the existence of other versions though.
     ///
-    /// Here we do the simple stupid thing of just growing the Vec.
-    /// It may not be trivial since those incompatibilities
-    /// may already have derived others.
-    fn merge_incompatibility(&mut self, id: IncompId<P, V>) {
+    /// When the new incompatibility can be [merged](Incompatibility::merge)
+    /// with a previous one, the merged incompatibility replaces the previous one.
+    /// The previous one stays in the store since it may already have derived others.
+    fn merge_incompatibility(&mut self, mut id: IncompId<P, VS>) {
+        let incompat = &self.incompatibility_store[id];
+        let mergeable = incompat.iter().next().and_then(|(package, _)| {
+            self.incompatibilities
+                .get(package)?
+                .iter()
+                .rev()
+                .find_map(|&previous| {
+                    incompat
+                        .merge(&self.incompatibility_store[previous])
+                        .map(|merged| (previous, merged))
+                })
+        });
+        if let Some((previous, merged)) = mergeable {
+            for (pkg, _term) in self.incompatibility_store[previous].iter() {
+                if let Some(ids) = self.incompatibilities.get_mut(pkg) {
+                    ids.retain(|&other| other != previous);
+                }
+            }
+            id = self.incompatibility_store.alloc(merged);
+        }
         for (pkg, _term) in self.incompatibility_store[id].iter() {
             self.incompatibilities
                 .entry(pkg.clone())
@@ -245,14 +332,14 @@ impl<P: Package, V: Version> State<P, V> {
 
     // Error reporting #########################################################
 
-    fn build_derivation_tree(&self, incompat: IncompId<P, V>) -> DerivationTree<P, V> {
+    fn build_derivation_tree(&self, incompat: IncompId<P, VS>) -> DerivationTree<P, VS> {
         let shared_ids = self.find_shared_ids(incompat);
         Incompatibility::build_derivation_tree(incompat, &shared_ids, &self.incompatibility_store)
     }
 
-    fn find_shared_ids(&self, incompat: IncompId<P, V>) -> Set<IncompId<P, V>> {
-        let mut all_ids = Set::new();
-        let mut shared_ids = Set::new();
+    fn find_shared_ids(&self, incompat: IncompId<P, VS>) -> Set<IncompId<P, VS>> {
+        let mut all_ids = Set::default();
+        let mut shared_ids = Set::default();
         let mut stack = vec![incompat];
         while let Some(i) = stack.pop() {
             if let Some((id1, id2)) = self.incompatibility_store[i].causes() {
@@ -268,3 +355,68 @@ impl<P: Package, V: Version> State<P, V> {
         shared_ids
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::range::Range;
+    use crate::solver::PreviousSatisfierLevel;
+    use crate::version::NumberVersion;
+
+    #[test]
+    fn contradicted_incompatibilities_are_not_recomputed() {
+        let mut state = State::<&str, Range<NumberVersion>>::init("root", NumberVersion(1));
+        let mut stats = SolverStats::default();
+        state
+            .unit_propagation("root", &PreviousSatisfierLevel, &mut stats)
+            .unwrap();
+        assert_eq!(stats.relations, 1);
+
+        // Many incompatibilities about packages that never change.
+        let others = ["o0", "o1", "o2", "o3", "o4", "o5", "o6", "o7", "o8", "o9"];
+        for pair in others.windows(2) {
+            state.add_incompatibility(Incompatibility::forbidden_combination(
+                pair[0],
+                Range::any(),
+                pair[1],
+                Range::any(),
+            ));
+        }
+        let mut deps = Map::default();
+        deps.insert("a", Range::any());
+        deps.insert("b", Range::any());
+        let root_deps =
+            state.add_incompatibility_from_dependencies("root", NumberVersion(1), &deps);
+        state.partial_solution.add_version(
+            "root",
+            NumberVersion(1),
+            root_deps,
+            &state.incompatibility_store,
+        );
+
+        // Only the dependencies of root are computed, not_root is already contradicted.
+        stats = SolverStats::default();
+        state
+            .unit_propagation("root", &PreviousSatisfierLevel, &mut stats)
+            .unwrap();
+        assert_eq!(stats.relations, 2);
+
+        // Backtracking a later decision keeps the earlier contradictions.
+        state.partial_solution.add_decision("a", NumberVersion(1));
+        let not_root = state.incompatibilities[&"root"][0];
+        state.backtrack(not_root, false, DecisionLevel(1));
+        stats = SolverStats::default();
+        state
+            .unit_propagation("root", &PreviousSatisfierLevel, &mut stats)
+            .unwrap();
+        assert_eq!(stats.relations, 0);
+
+        // Backtracking below them forgets them.
+        state.backtrack(not_root, false, DecisionLevel(0));
+        stats = SolverStats::default();
+        state
+            .unit_propagation("root", &PreviousSatisfierLevel, &mut stats)
+            .unwrap();
+        assert_eq!(stats.relations, 2);
+    }
+}
@@ -3,16 +3,16 @@
 //! An incompatibility is a set of terms for different packages
 //! that should never be satisfied all together.
 
-use std::collections::HashSet as Set;
-use std::fmt;
+use alloc::boxed::Box;
+use core::fmt;
 
 use crate::internal::arena::{Arena, Id};
 use crate::internal::small_map::SmallMap;
 use crate::package::Package;
-use crate::range::Range;
 use crate::report::{DefaultStringReporter, DerivationTree, Derived, External};
 use crate::term::{self, Term};
-use crate::version::Version;
+use crate::type_aliases::Set;
+use crate::version_set::VersionSet;
 
 /// An incompatibility is a set of terms for different packages
 /// that should never be satisfied all together.
@@ -30,26 +30,51 @@ use crate::version::Version;
 /// during conflict resolution. More about all this in
 /// [PubGrub documentation](https://github.com/dart-lang/pub/blob/master/doc/solver.md#incompatibility).
 #[derive(Debug, Clone)]
-pub struct Incompatibility<P: Package, V: Version> {
-    package_terms: SmallMap<P, Term<V>>,
-    kind: Kind<P, V>,
+pub struct Incompatibility<P: Package, VS: VersionSet> {
+    package_terms: SmallMap<P, Term<VS>>,
+    kind: Kind<P, VS>,
 }
 
 /// Type alias of unique identifiers for incompatibilities.
-pub type IncompId<P, V> = Id<Incompatibility<P, V>>;
+pub type IncompId<P, VS> = Id<Incompatibility<P, VS>>;
 
 #[derive(Debug, Clone)]
-enum Kind<P: Package, V: Version> {
+enum Kind<P: Package, VS: VersionSet> {
     /// Initial incompatibility aiming at picking the root package for the first decision.
-    NotRoot(P, V),
+    NotRoot(P, VS::V),
     /// There are no versions in the given range for this package.
-    NoVersions(P, Range<V>),
+    NoVersions(P, VS),
     /// Dependencies of the package are unavailable for versions in that range.
-    UnavailableDependencies(P, Range<V>),
+    UnavailableDependencies(P, VS),
     /// Incompatibility coming from the dependencies of a given package.
-    FromDependencyOf(P, Range<V>, P, Range<V>),
+    FromDependencyOf(P, VS, P, VS),
+    /// Two packages in the given ranges that can never be selected together.
+    ForbiddenCombination(P, VS, P, VS),
+    /// The package can only be selected at that version.
+    Pinned(P, VS::V),
+    /// The package at that version forbids the selection of another package.
+    Forbidden(P, VS::V, P),
+    /// The package at that version depends on a package
+    /// only when another one is selected in the given range.
+    ConditionalDependency(P, VS::V, P, VS, P, VS),
+    /// The package at that version is in a lockstep group with another package,
+    /// which can only be selected at the same version.
+    Lockstep(P, VS::V, P),
     /// Derived from two causes. Stores cause ids.
-    DerivedFrom(IncompId<P, V>, IncompId<P, V>),
+    DerivedFrom(IncompId<P, VS>, IncompId<P, VS>),
+}
+
+/// Terms of an incompatibility on two packages,
+/// intersected into a single term if both are the same package.
+fn two_terms<P: Package, VS: VersionSet>(
+    (package1, term1): (P, Term<VS>),
+    (package2, term2): (P, Term<VS>),
+) -> SmallMap<P, Term<VS>> {
+    if package1 == package2 {
+        SmallMap::One([(package1, term1.intersection(&term2))])
+    } else {
+        SmallMap::Two([(package1, term1), (package2, term2)])
+    }
 }
 
 /// A Relation describes how a set of terms can be compared to an incompatibility.
@@ -69,13 +94,13 @@ pub enum Relation<P: Package> {
     Inconclusive,
 }
 
-impl<P: Package, V: Version> Incompatibility<P, V> {
+impl<P: Package, VS: VersionSet> Incompatibility<P, VS> {
     /// Create the initial "not Root" incompatibility.
-    pub fn not_root(package: P, version: V) -> Self {
+    pub fn not_root(package: P, version: VS::V) -> Self {
         Self {
             package_terms: SmallMap::One([(
                 package.clone(),
-                Term::Negative(Range::exact(version.clone())),
+                Term::Negative(VS::singleton(version.clone())),
             )]),
             kind: Kind::NotRoot(package, version),
         }
@@ -83,7 +108,7 @@ impl<P: Package, V: Version> Incompatibility<P, V> {
 
     /// Create an incompatibility to remember
     /// that a given range does not contain any version.
-    pub fn no_versions(package: P, term: Term<V>) -> Self {
+    pub(crate) fn no_versions(package: P, term: Term<VS>) -> Self {
         let range = match &term {
             Term::Positive(r) => r.clone(),
             Term::Negative(_) => panic!("No version should have a positive term"),
@@ -97,29 +122,161 @@ impl<P: Package, V: Version> Incompatibility<P, V> {
     /// Create an incompatibility to remember
     /// that a package version is not selectable
     /// because its list of dependencies is unavailable.
-    pub fn unavailable_dependencies(package: P, version: V) -> Self {
-        let range = Range::exact(version);
+    pub fn unavailable_dependencies(package: P, version: VS::V) -> Self {
+        let range = VS::singleton(version);
         Self {
             package_terms: SmallMap::One([(package.clone(), Term::Positive(range.clone()))]),
             kind: Kind::UnavailableDependencies(package, range),
         }
     }
 
-    /// Build an incompatibility from a given dependency.
-    pub fn from_dependency(package: P, version: V, dep: (&P, &Range<V>)) -> Self {
-        let range1 = Range::exact(version);
-        let (p2, range2) = dep;
+    /// Build an incompatibility from a dependency of all versions of `package`
+    /// in `versions` on `dep_package` with versions in `dep_versions`.
+    /// A dependency on the empty set simply forbids `package` in `versions`.
+    pub fn from_dependency(package: P, versions: VS, dep_package: P, dep_versions: VS) -> Self {
+        let package_terms = if dep_versions.is_empty() {
+            SmallMap::One([(package.clone(), Term::Positive(versions.clone()))])
+        } else {
+            two_terms(
+                (package.clone(), Term::Positive(versions.clone())),
+                (dep_package.clone(), Term::Negative(dep_versions.clone())),
+            )
+        };
+        Self {
+            package_terms,
+            kind: Kind::FromDependencyOf(package, versions, dep_package, dep_versions),
+        }
+    }
+
+    /// Build an incompatibility forbidding the selection of both
+    /// `package1` with a version in `versions1`
+    /// and `package2` with a version in `versions2`.
+    pub fn forbidden_combination(package1: P, versions1: VS, package2: P, versions2: VS) -> Self {
+        Self {
+            package_terms: two_terms(
+                (package1.clone(), Term::Positive(versions1.clone())),
+                (package2.clone(), Term::Positive(versions2.clone())),
+            ),
+            kind: Kind::ForbiddenCombination(package1, versions1, package2, versions2),
+        }
+    }
+
+    /// Build an incompatibility forbidding the selection of `package`
+    /// at any other version than `version`.
+    /// It does not force the selection of `package`.
+    pub fn pinned(package: P, version: VS::V) -> Self {
+        Self {
+            package_terms: SmallMap::One([(
+                package.clone(),
+                Term::Positive(VS::singleton(version.clone()).complement()),
+            )]),
+            kind: Kind::Pinned(package, version),
+        }
+    }
+
+    /// Build an incompatibility forbidding the selection of any version of `forbidden`
+    /// when `package` is selected at `version`.
+    pub fn forbidden_package(package: P, version: VS::V, forbidden: P) -> Self {
+        Self {
+            package_terms: two_terms(
+                (package.clone(), Term::exact(version.clone())),
+                (forbidden.clone(), Term::Positive(VS::full())),
+            ),
+            kind: Kind::Forbidden(package, version, forbidden),
+        }
+    }
+
+    /// Build an incompatibility stating that `package` at `version`
+    /// cannot be selected together with `other` at any other version.
+    pub fn lockstep(package: P, version: VS::V, other: P) -> Self {
         Self {
-            package_terms: SmallMap::Two([
-                (package.clone(), Term::Positive(range1.clone())),
-                (p2.clone(), Term::Negative(range2.clone())),
-            ]),
-            kind: Kind::FromDependencyOf(package, range1, p2.clone(), range2.clone()),
+            package_terms: two_terms(
+                (package.clone(), Term::exact(version.clone())),
+                (
+                    other.clone(),
+                    Term::Positive(VS::singleton(version.clone()).complement()),
+                ),
+            ),
+            kind: Kind::Lockstep(package, version, other),
+        }
+    }
+
+    /// Rebuild an incompatibility from its external reason.
+    pub(crate) fn from_external(external: External<P, VS>) -> Self {
+        match external {
+            External::NotRoot(package, version) => Self::not_root(package, version),
+            External::NoVersions(package, range) => {
+                Self::no_versions(package, Term::Positive(range))
+            }
+            External::UnavailableDependencies(package, range) => Self {
+                package_terms: SmallMap::One([(package.clone(), Term::Positive(range.clone()))]),
+                kind: Kind::UnavailableDependencies(package, range),
+            },
+            External::FromDependencyOf(package, range, dep_package, dep_range) => {
+                Self::from_dependency(package, range, dep_package, dep_range)
+            }
+            External::ForbiddenCombination(package1, range1, package2, range2) => {
+                Self::forbidden_combination(package1, range1, package2, range2)
+            }
+            External::Pinned(package, version) => Self::pinned(package, version),
+            External::Forbidden(package, version, forbidden) => {
+                Self::forbidden_package(package, version, forbidden)
+            }
+            External::ConditionalDependency(package, version, condition, range, dep, dep_range) => {
+                Self::conditional_dependency(package, version, (condition, range), (dep, dep_range))
+            }
+            External::Lockstep(package, version, other) => Self::lockstep(package, version, other),
+        }
+    }
+
+    /// Merge two incompatibilities into one forbidding exactly what either of them forbids.
+    /// This is only possible if they have the same terms for all their packages but one,
+    /// which happens for the dependencies of different versions of a package
+    /// on the same versions of another package,
+    /// or for ranges of the same package without versions or without dependencies.
+    /// The terms of that package are then united.
+    /// Return [None] if the two incompatibilities cannot be merged.
+    pub fn merge(&self, other: &Self) -> Option<Self> {
+        match (&self.kind, &other.kind) {
+            (
+                Kind::FromDependencyOf(package, versions, dep_package, dep_versions),
+                Kind::FromDependencyOf(
+                    other_package,
+                    other_versions,
+                    other_dep,
+                    other_dep_versions,
+                ),
+            ) if package == other_package
+                && dep_package == other_dep
+                && dep_versions == other_dep_versions =>
+            {
+                Some(Self::from_dependency(
+                    package.clone(),
+                    versions.union(other_versions),
+                    dep_package.clone(),
+                    dep_versions.clone(),
+                ))
+            }
+            (Kind::NoVersions(package, range), Kind::NoVersions(other_package, other_range))
+                if package == other_package =>
+            {
+                Some(Self::no_versions(
+                    package.clone(),
+                    Term::Positive(range.union(other_range)),
+                ))
+            }
+            (
+                Kind::UnavailableDependencies(package, range),
+                Kind::UnavailableDependencies(other_package, other_range),
+            ) if package == other_package => Some(Self::from_external(
+                External::UnavailableDependencies(package.clone(), range.union(other_range)),
+            )),
+            _ => None,
         }
     }
 
     /// Prior cause of two incompatibilities using the rule of resolution.
-    pub fn prior_cause(
+    pub(crate) fn prior_cause(
         incompat: Id<Self>,
         satisfier_cause: Id<Self>,
         package: &P,
@@ -145,31 +302,120 @@ impl<P: Package, V: Version> Incompatibility<P, V> {
 
     /// Check if an incompatibility should mark the end of the algorithm
     /// because it satisfies the root package.
-    pub fn is_terminal(&self, root_package: &P, root_version: &V) -> bool {
+    pub(crate) fn is_terminal(&self, root_package: &P, root_version: &VS::V) -> bool {
         if self.package_terms.len() == 0 {
             true
         } else if self.package_terms.len() > 1 {
             false
         } else {
             let (package, term) = self.package_terms.iter().next().unwrap();
-            (package == root_package) && term.contains(&root_version)
+            (package == root_package) && term.contains(root_version)
         }
     }
 
     /// Get the term related to a given package (if it exists).
-    pub fn get(&self, package: &P) -> Option<&Term<V>> {
+    pub fn get(&self, package: &P) -> Option<&Term<VS>> {
         self.package_terms.get(package)
     }
 
+    /// Build an incompatibility stating that `package` at `version`
+    /// depends on `dependency` in `dependency_range`,
+    /// but only when `condition` is selected in `condition_range`.
+    pub fn conditional_dependency(
+        package: P,
+        version: VS::V,
+        (condition, condition_range): (P, VS),
+        (dependency, dependency_range): (P, VS),
+    ) -> Self {
+        let mut package_terms = SmallMap::One([(package.clone(), Term::exact(version.clone()))]);
+        let mut add_term = |p: &P, term: Term<VS>| match package_terms.get_mut(p) {
+            Some(existing) => *existing = existing.intersection(&term),
+            None => package_terms.insert(p.clone(), term),
+        };
+        add_term(&condition, Term::Positive(condition_range.clone()));
+        // Like for regular dependencies, depending on the empty set forbids the condition.
+        if !dependency_range.is_empty() {
+            add_term(&dependency, Term::Negative(dependency_range.clone()));
+        }
+        Self {
+            package_terms,
+            kind: Kind::ConditionalDependency(
+                package,
+                version,
+                condition,
+                condition_range,
+                dependency,
+                dependency_range,
+            ),
+        }
+    }
+
     /// Iterate over packages.
-    pub fn iter(&self) -> impl Iterator<Item = (&P, &Term<V>)> {
+    pub fn iter(&self) -> impl Iterator<Item = (&P, &Term<VS>)> {
         self.package_terms.iter()
     }
 
+    /// Retrieve the external reason of this incompatibility.
+    /// Returns [None] if it was derived from two other incompatibilities
+    /// during conflict resolution.
+    pub fn external(&self) -> Option<External<P, VS>> {
+        match &self.kind {
+            Kind::NotRoot(package, version) => {
+                Some(External::NotRoot(package.clone(), version.clone()))
+            }
+            Kind::NoVersions(package, range) => {
+                Some(External::NoVersions(package.clone(), range.clone()))
+            }
+            Kind::UnavailableDependencies(package, range) => Some(
+                External::UnavailableDependencies(package.clone(), range.clone()),
+            ),
+            Kind::FromDependencyOf(package, range, dep_package, dep_range) => {
+                Some(External::FromDependencyOf(
+                    package.clone(),
+                    range.clone(),
+                    dep_package.clone(),
+                    dep_range.clone(),
+                ))
+            }
+            Kind::ForbiddenCombination(package1, range1, package2, range2) => {
+                Some(External::ForbiddenCombination(
+                    package1.clone(),
+                    range1.clone(),
+                    package2.clone(),
+                    range2.clone(),
+                ))
+            }
+            Kind::Pinned(package, version) => {
+                Some(External::Pinned(package.clone(), version.clone()))
+            }
+            Kind::Forbidden(package, version, forbidden) => Some(External::Forbidden(
+                package.clone(),
+                version.clone(),
+                forbidden.clone(),
+            )),
+            Kind::ConditionalDependency(package, version, condition, range, dep, dep_range) => {
+                Some(External::ConditionalDependency(
+                    package.clone(),
+                    version.clone(),
+                    condition.clone(),
+                    range.clone(),
+                    dep.clone(),
+                    dep_range.clone(),
+                ))
+            }
+            Kind::Lockstep(package, version, other) => Some(External::Lockstep(
+                package.clone(),
+                version.clone(),
+                other.clone(),
+            )),
+            Kind::DerivedFrom(_, _) => None,
+        }
+    }
+
     // Reporting ###############################################################
 
     /// Retrieve parent causes if of type DerivedFrom.
-    pub fn causes(&self) -> Option<(Id<Self>, Id<Self>)> {
+    pub(crate) fn causes(&self) -> Option<(Id<Self>, Id<Self>)> {
         match self.kind {
             Kind::DerivedFrom(id1, id2) => Some((id1, id2)),
             _ => None,
@@ -177,11 +423,11 @@ impl<P: Package, V: Version> Incompatibility<P, V> {
     }
 
     /// Build a derivation tree for error reporting.
-    pub fn build_derivation_tree(
+    pub(crate) fn build_derivation_tree(
         self_id: Id<Self>,
         shared_ids: &Set<Id<Self>>,
         store: &Arena<Self>,
-    ) -> DerivationTree<P, V> {
+    ) -> DerivationTree<P, VS> {
         match &store[self_id].kind {
             Kind::DerivedFrom(id1, id2) => {
                 let cause1 = Self::build_derivation_tree(*id1, shared_ids, store);
@@ -194,33 +440,21 @@ impl<P: Package, V: Version> Incompatibility<P, V> {
                 };
                 DerivationTree::Derived(derived)
             }
-            Kind::NotRoot(package, version) => {
-                DerivationTree::External(External::NotRoot(package.clone(), version.clone()))
-            }
-            Kind::NoVersions(package, range) => {
-                DerivationTree::External(External::NoVersions(package.clone(), range.clone()))
-            }
-            Kind::UnavailableDependencies(package, range) => DerivationTree::External(
-                External::UnavailableDependencies(package.clone(), range.clone()),
+            _ => DerivationTree::External(
+                store[self_id]
+                    .external()
+                    .expect("Only derived incompatibilities have no external reason"),
             ),
-            Kind::FromDependencyOf(package, range, dep_package, dep_range) => {
-                DerivationTree::External(External::FromDependencyOf(
-                    package.clone(),
-                    range.clone(),
-                    dep_package.clone(),
-                    dep_range.clone(),
-                ))
-            }
         }
     }
 }
 
-impl<'a, P: Package, V: Version + 'a> Incompatibility<P, V> {
+impl<'a, P: Package, VS: VersionSet + 'a> Incompatibility<P, VS> {
     /// CF definition of Relation enum.
-    pub fn relation(&self, terms: impl Fn(&P) -> Option<&'a Term<V>>) -> Relation<P> {
+    pub(crate) fn relation(&self, terms: impl Fn(&P) -> Option<&'a Term<VS>>) -> Relation<P> {
         let mut relation = Relation::Satisfied;
         for (package, incompat_term) in self.package_terms.iter() {
-            match terms(package).map(|term| incompat_term.relation_with(&term)) {
+            match terms(package).map(|term| incompat_term.relation_with(term)) {
                 Some(term::Relation::Satisfied) => {}
                 Some(term::Relation::Contradicted) => {
                     return Relation::Contradicted(package.clone());
@@ -243,7 +477,7 @@ impl<'a, P: Package, V: Version + 'a> Incompatibility<P, V> {
     }
 }
 
-impl<P: Package, V: Version> fmt::Display for Incompatibility<P, V> {
+impl<P: Package, VS: VersionSet> fmt::Display for Incompatibility<P, VS> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
@@ -258,10 +492,68 @@ impl<P: Package, V: Version> fmt::Display for Incompatibility<P, V> {
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use crate::range::Range;
     use crate::term::tests::strategy as term_strat;
     use crate::type_aliases::Map;
+    use crate::version::NumberVersion;
     use proptest::prelude::*;
 
+    #[test]
+    fn merge_dependencies_of_different_versions() {
+        let dependency = |version: u32, dep_versions: Range<NumberVersion>| {
+            Incompatibility::from_dependency("a", Range::exact(version), "b", dep_versions)
+        };
+        let merged = dependency(1, Range::higher_than(2))
+            .merge(&dependency(2, Range::higher_than(2)))
+            .unwrap();
+        assert_eq!(
+            merged.get(&"a"),
+            Some(&Term::Positive(Range::between(1, 3)))
+        );
+        assert_eq!(
+            merged.get(&"b"),
+            Some(&Term::Negative(Range::higher_than(2)))
+        );
+        assert!(matches!(
+            merged.external(),
+            Some(External::FromDependencyOf("a", _, "b", _))
+        ));
+
+        let no_versions =
+            |range: Range<NumberVersion>| Incompatibility::no_versions("a", Term::Positive(range));
+        let merged = no_versions(Range::exact(1))
+            .merge(&no_versions(Range::higher_than(5)))
+            .unwrap();
+        assert_eq!(
+            merged.get(&"a"),
+            Some(&Term::Positive(
+                Range::exact(1).union(&Range::higher_than(5))
+            ))
+        );
+    }
+
+    #[test]
+    fn merge_incompatible_kinds_or_packages() {
+        let dependency = |version: u32, dep_package, dep_versions: Range<NumberVersion>| {
+            Incompatibility::from_dependency("a", Range::exact(version), dep_package, dep_versions)
+        };
+        // Different versions of the dependency.
+        assert!(dependency(1, "b", Range::any())
+            .merge(&dependency(2, "b", Range::exact(1)))
+            .is_none());
+        // Different dependencies.
+        assert!(dependency(1, "b", Range::any())
+            .merge(&dependency(2, "c", Range::any()))
+            .is_none());
+        // Different kinds.
+        assert!(dependency(1, "b", Range::any())
+            .merge(&Incompatibility::unavailable_dependencies(
+                "a",
+                NumberVersion(2)
+            ))
+            .is_none());
+    }
+
     proptest! {
 
         /// For any three different packages p1, p2 and p3,
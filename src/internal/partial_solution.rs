@@ -3,14 +3,17 @@
 //! A Memory acts like a structured partial solution
 //! where terms are regrouped by package in a [Map](crate::type_aliases::Map).
 
+use alloc::vec::Vec;
+
+use crate::assignment::{Assignment, Decision, Derivation};
 use crate::internal::arena::Arena;
 use crate::internal::incompatibility::{IncompId, Incompatibility, Relation};
 use crate::internal::small_map::SmallMap;
 use crate::package::Package;
-use crate::range::Range;
+use crate::solver::rank_packages_by_fewest_versions;
 use crate::term::Term;
 use crate::type_aliases::{Map, SelectedDependencies};
-use crate::version::Version;
+use crate::version_set::VersionSet;
 
 use super::small_vec::SmallVec;
 
@@ -18,55 +21,68 @@ use super::small_vec::SmallVec;
 pub struct DecisionLevel(pub u32);
 
 impl DecisionLevel {
+    /// Level of the next decision.
+    /// Panics instead of wrapping around if the level overflows.
     pub fn increment(self) -> Self {
-        Self(self.0 + 1)
+        Self(self.0.checked_add(1).expect("decision level overflow"))
+    }
+
+    /// Level right before this one.
+    /// Only levels reached by a decision can be decremented,
+    /// so this panics instead of wrapping around at level zero.
+    pub fn decrement(self) -> Self {
+        Self(self.0.checked_sub(1).expect("no decision level below zero"))
     }
 }
 
 /// The partial solution contains all package assignments,
 /// organized by package and historically ordered.
 #[derive(Clone, Debug)]
-pub struct PartialSolution<P: Package, V: Version> {
+pub struct PartialSolution<P: Package, VS: VersionSet> {
     next_global_index: u32,
     current_decision_level: DecisionLevel,
-    package_assignments: Map<P, PackageAssignments<P, V>>,
+    package_assignments: Map<P, PackageAssignments<P, VS>>,
 }
 
 /// Package assignments contain the potential decision and derivations
 /// that have already been made for a given package,
 /// as well as the intersection of terms by all of these.
 #[derive(Clone, Debug)]
-struct PackageAssignments<P: Package, V: Version> {
+struct PackageAssignments<P: Package, VS: VersionSet> {
     smallest_decision_level: DecisionLevel,
     highest_decision_level: DecisionLevel,
-    dated_derivations: SmallVec<DatedDerivation<P, V>>,
-    assignments_intersection: AssignmentsIntersection<V>,
+    dated_derivations: SmallVec<DatedDerivation<P, VS>>,
+    assignments_intersection: AssignmentsIntersection<VS>,
 }
 
 #[derive(Clone, Debug)]
-pub struct DatedDerivation<P: Package, V: Version> {
+pub struct DatedDerivation<P: Package, VS: VersionSet> {
     global_index: u32,
     decision_level: DecisionLevel,
-    cause: IncompId<P, V>,
+    cause: IncompId<P, VS>,
+    /// Intersection of the terms of this derivation and of all previous ones
+    /// of the same package, restored as is when backtracking to this derivation.
+    accumulated_intersection: Term<VS>,
 }
 
 #[derive(Clone, Debug)]
-enum AssignmentsIntersection<V: Version> {
-    Decision((u32, V, Term<V>)),
-    Derivations(Term<V>),
+enum AssignmentsIntersection<VS: VersionSet> {
+    Decision((u32, VS::V, Term<VS>)),
+    Derivations(Term<VS>),
 }
 
 #[derive(Clone, Debug)]
-pub enum SatisfierSearch<P: Package, V: Version> {
+pub enum SatisfierSearch<P: Package, VS: VersionSet> {
     DifferentDecisionLevels {
         previous_satisfier_level: DecisionLevel,
+        satisfier_level: DecisionLevel,
     },
     SameDecisionLevels {
-        satisfier_cause: IncompId<P, V>,
+        satisfier_cause: IncompId<P, VS>,
     },
 }
 
-impl<P: Package, V: Version> PartialSolution<P, V> {
+impl<P: Package, VS: VersionSet> PartialSolution<P, VS> {
     /// Initialize an empty PartialSolution.
     pub fn empty() -> Self {
         Self {
@@ -76,8 +92,13 @@ impl<P: Package, V: Version> PartialSolution<P, V> {
         }
     }
 
+    /// Current decision level, the number of decisions in the partial solution.
+    pub fn current_decision_level(&self) -> DecisionLevel {
+        self.current_decision_level
+    }
+
     /// Add a decision.
-    pub fn add_decision(&mut self, package: P, version: V) {
+    pub fn add_decision(&mut self, package: P, version: VS::V) {
         // Check that add_decision is never used in the wrong context.
         if cfg!(debug_assertions) {
             match self.package_assignments.get_mut(&package) {
@@ -93,7 +114,7 @@ impl<P: Package, V: Version> PartialSolution<P, V> {
             }
         }
         self.current_decision_level = self.current_decision_level.increment();
-        let mut pa = self
+        let pa = self
             .package_assignments
             .get_mut(&package)
             .expect("Derivations must already exist");
@@ -110,20 +131,24 @@ impl<P: Package, V: Version> PartialSolution<P, V> {
     pub fn add_derivation(
         &mut self,
         package: P,
-        cause: IncompId<P, V>,
-        store: &Arena<Incompatibility<P, V>>,
+        cause: IncompId<P, VS>,
+        store: &Arena<Incompatibility<P, VS>>,
     ) {
+        #[cfg(not(feature = "std"))]
+        use hashbrown::hash_map::Entry;
+        #[cfg(feature = "std")]
         use std::collections::hash_map::Entry;
         let term = store[cause].get(&package).unwrap().negate();
-        let dated_derivation = DatedDerivation {
+        let mut dated_derivation = DatedDerivation {
             global_index: self.next_global_index,
             decision_level: self.current_decision_level,
             cause,
+            accumulated_intersection: term,
         };
         self.next_global_index += 1;
         match self.package_assignments.entry(package) {
             Entry::Occupied(mut occupied) => {
-                let mut pa = occupied.get_mut();
+                let pa = occupied.get_mut();
                 pa.highest_decision_level = self.current_decision_level;
                 match &mut pa.assignments_intersection {
                     // Check that add_derivation is never called in the wrong context.
@@ -131,12 +156,14 @@ impl<P: Package, V: Version> PartialSolution<P, V> {
                         panic!("add_derivation should not be called after a decision")
                     }
                     AssignmentsIntersection::Derivations(t) => {
-                        *t = t.intersection(&term);
+                        *t = t.intersection(&dated_derivation.accumulated_intersection);
+                        dated_derivation.accumulated_intersection = t.clone();
                     }
                 }
                 pa.dated_derivations.push(dated_derivation);
             }
             Entry::Vacant(v) => {
+                let term = dated_derivation.accumulated_intersection.clone();
                 v.insert(PackageAssignments {
                     smallest_decision_level: self.current_decision_level,
                     highest_decision_level: self.current_decision_level,
@@ -153,7 +180,7 @@ impl<P: Package, V: Version> PartialSolution<P, V> {
     /// selected version (no "decision")
     /// and if it contains at least one positive derivation term
     /// in the partial solution.
-    pub fn potential_packages(&self) -> Option<impl Iterator<Item = (&P, &Range<V>)>> {
+    pub fn potential_packages(&self) -> Option<impl Iterator<Item = (&P, &VS)>> {
         let mut iter = self
             .package_assignments
             .iter()
@@ -166,10 +193,35 @@ impl<P: Package, V: Version> PartialSolution<P, V> {
         }
     }
 
+    /// Potential packages ranked by the heuristic of
+    /// [choose_package_with_fewest_versions](crate::solver::choose_package_with_fewest_versions),
+    /// with their term and the number of versions from `list_available_versions` in it.
+    /// The first one is the package that would be picked for the next decision.
+    pub fn ranked_potential_packages<I, F>(
+        &self,
+        list_available_versions: F,
+    ) -> Vec<(P, Term<VS>, usize)>
+    where
+        I: Iterator<Item = VS::V>,
+        F: Fn(&P) -> I,
+    {
+        let potential_packages = self
+            .package_assignments
+            .iter()
+            .filter_map(|(p, pa)| pa.assignments_intersection.potential_package_filter(p));
+        rank_packages_by_fewest_versions::<P, VS, _, _, _, _>(
+            &list_available_versions,
+            potential_packages,
+        )
+        .into_iter()
+        .map(|(p, range, count)| (p.clone(), Term::Positive(range.clone()), count))
+        .collect()
+    }
+
     /// If a partial solution has, for every positive derivation,
     /// a corresponding decision that satisfies that assignment,
     /// it's a total solution and version solving has succeeded.
-    pub fn extract_solution(&self) -> Option<SelectedDependencies<P, V>> {
+    pub fn extract_solution(&self) -> Option<SelectedDependencies<P, VS::V>> {
         let mut solution = Map::default();
         for (p, pa) in &self.package_assignments {
             match &pa.assignments_intersection {
@@ -186,14 +238,32 @@ impl<P: Package, V: Version> PartialSolution<P, V> {
         Some(solution)
     }
 
+    /// Packages with a positive derivation but no decision yet,
+    /// with their term intersection.
+    /// They are the reason why [extract_solution](Self::extract_solution) returns [None].
+    pub fn unsatisfied_derivations(&self) -> Vec<(P, Term<VS>)> {
+        self.package_assignments
+            .iter()
+            .filter_map(|(p, pa)| match &pa.assignments_intersection {
+                AssignmentsIntersection::Derivations(term) if term.is_positive() => {
+                    Some((p.clone(), term.clone()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
     /// Backtrack the partial solution to a given decision level.
-    pub fn backtrack(
-        &mut self,
-        decision_level: DecisionLevel,
-        store: &Arena<Incompatibility<P, V>>,
-    ) {
+    /// Backtracking to the current decision level or above is a no-op,
+    /// which also covers the case of an empty partial solution.
+    /// Assignments above that level are popped, and the intersection of terms of a package
+    /// is restored from its last remaining derivation instead of being recomputed.
+    pub fn backtrack(&mut self, decision_level: DecisionLevel) {
+        if decision_level >= self.current_decision_level {
+            return;
+        }
         self.current_decision_level = decision_level;
-        self.package_assignments.retain(|p, pa| {
+        self.package_assignments.retain(|_, pa| {
             if pa.smallest_decision_level > decision_level {
                 // Remove all entries that have a smallest decision level higher than the backtrack target.
                 false
@@ -213,20 +283,19 @@ impl<P: Package, V: Version> PartialSolution<P, V> {
                 {
                     pa.dated_derivations.pop();
                 }
-                debug_assert!(!pa.dated_derivations.is_empty());
-
-                // Update highest_decision_level.
-                pa.highest_decision_level = pa.dated_derivations.last().unwrap().decision_level;
-
-                // Recompute the assignments intersection.
-                pa.assignments_intersection = AssignmentsIntersection::Derivations(
-                    pa.dated_derivations
-                        .iter()
-                        .fold(Term::any(), |acc, dated_derivation| {
-                            let term = store[dated_derivation.cause].get(&p).unwrap().negate();
-                            acc.intersection(&term)
-                        }),
-                );
+
+                // Update highest_decision_level and restore the assignments intersection
+                // accumulated up to the last remaining derivation.
+                // An assignment without any remaining derivation has nothing left to keep.
+                match pa.dated_derivations.last() {
+                    None => return false,
+                    Some(dd) => {
+                        pa.highest_decision_level = dd.decision_level;
+                        pa.assignments_intersection = AssignmentsIntersection::Derivations(
+                            dd.accumulated_intersection.clone(),
+                        );
+                    }
+                }
                 true
             }
         });
@@ -237,15 +306,16 @@ impl<P: Package, V: Version> PartialSolution<P, V> {
     /// In practice I think it can only produce a conflict if one of the dependencies
     /// (which are used to make the new incompatibilities)
     /// is already in the partial solution with an incompatible version.
+    /// Return whether the version was added as a decision.
     pub fn add_version(
         &mut self,
         package: P,
-        version: V,
-        new_incompatibilities: std::ops::Range<IncompId<P, V>>,
-        store: &Arena<Incompatibility<P, V>>,
-    ) {
+        version: VS::V,
+        new_incompatibilities: core::ops::Range<IncompId<P, VS>>,
+        store: &Arena<Incompatibility<P, VS>>,
+    ) -> bool {
         let exact = Term::exact(version.clone());
-        let not_satisfied = |incompat: &Incompatibility<P, V>| {
+        let not_satisfied = |incompat: &Incompatibility<P, VS>| {
             incompat.relation(|p| {
                 if p == &package {
                     Some(&exact)
@@ -259,27 +329,173 @@ impl<P: Package, V: Version> PartialSolution<P, V> {
         // would create a conflict (be satisfied).
         if store[new_incompatibilities].iter().all(not_satisfied) {
             self.add_decision(package, version);
+            true
+        } else {
+            false
         }
     }
 
+    /// Check the invariants of the partial solution, panicking if one is broken:
+    /// there is exactly one decision per decision level,
+    /// decision levels are monotonic in the history of assignments,
+    /// and the cause of every derivation is a stored incompatibility.
+    /// The checks are `debug_assert!`, so they do nothing in release builds.
+    #[cfg(feature = "debug-checks")]
+    pub fn check_invariants(&self, store: &Arena<Incompatibility<P, VS>>) {
+        let mut decision_levels = Vec::new();
+        for (package, pa) in &self.package_assignments {
+            let mut previous: Option<&DatedDerivation<P, VS>> = None;
+            for dd in pa.dated_derivations.iter() {
+                debug_assert!(
+                    store.contains(dd.cause),
+                    "cause of a derivation of {} is not stored",
+                    package
+                );
+                if let Some(previous) = previous {
+                    debug_assert!(
+                        previous.global_index < dd.global_index
+                            && previous.decision_level <= dd.decision_level,
+                        "derivations of {} are not monotonic",
+                        package
+                    );
+                }
+                previous = Some(dd);
+            }
+            debug_assert!(
+                pa.smallest_decision_level <= pa.highest_decision_level
+                    && pa.highest_decision_level <= self.current_decision_level,
+                "decision levels of {} are out of bounds",
+                package
+            );
+            if let AssignmentsIntersection::Decision((global_index, version, _)) =
+                &pa.assignments_intersection
+            {
+                if let Some(dd) = previous {
+                    debug_assert!(
+                        dd.global_index < *global_index
+                            && dd.decision_level <= pa.highest_decision_level,
+                        "decision of {} is not its last assignment",
+                        package
+                    );
+                }
+                debug_assert!(
+                    pa.dated_derivations.iter().all(|dd| store[dd.cause]
+                        .get(package)
+                        .unwrap()
+                        .negate()
+                        .contains(version)),
+                    "decision of {} contradicts its derivations",
+                    package
+                );
+                decision_levels.push((*global_index, pa.highest_decision_level));
+            }
+        }
+        decision_levels.sort_unstable_by_key(|(global_index, _)| *global_index);
+        debug_assert!(
+            decision_levels
+                .iter()
+                .enumerate()
+                .all(|(i, (_, level))| level.0 as usize == i + 1),
+            "decisions are not one per decision level in increasing order"
+        );
+        debug_assert_eq!(
+            decision_levels.len(),
+            self.current_decision_level.0 as usize,
+            "some decision level has no decision"
+        );
+    }
+
     /// Check if the terms in the partial solution satisfy the incompatibility.
-    pub fn relation(&self, incompat: &Incompatibility<P, V>) -> Relation<P> {
+    pub fn relation(&self, incompat: &Incompatibility<P, VS>) -> Relation<P> {
         incompat.relation(|package| self.term_intersection_for_package(package))
     }
 
     /// Retrieve intersection of terms related to package.
-    pub fn term_intersection_for_package(&self, package: &P) -> Option<&Term<V>> {
+    pub fn term_intersection_for_package(&self, package: &P) -> Option<&Term<VS>> {
         self.package_assignments
             .get(package)
             .map(|pa| pa.assignments_intersection.term())
     }
 
+    /// Iterate over the intersection of terms of every package
+    /// having assignments in the partial solution.
+    /// This is a cheap read-only view, useful to inspect how constraints evolve,
+    /// exposed by [PackageTerms](crate::solver::PackageTerms).
+    pub fn package_terms(&self) -> impl Iterator<Item = (&P, &Term<VS>)> {
+        self.package_assignments
+            .iter()
+            .map(|(p, pa)| (p, pa.assignments_intersection.term()))
+    }
+
+    /// List all the decisions and derivations of the partial solution,
+    /// in the order in which they were made.
+    pub fn assignments(&self, store: &Arena<Incompatibility<P, VS>>) -> Vec<Assignment<P, VS>> {
+        let mut assignments = Vec::new();
+        for (package, pa) in &self.package_assignments {
+            for dd in pa.dated_derivations.iter() {
+                let cause = &store[dd.cause];
+                let term = cause.get(package).expect("cause of a derivation").negate();
+                let derivation =
+                    Derivation::new(package.clone(), term, cause.clone(), dd.decision_level.0);
+                assignments.push((dd.global_index, Assignment::Derivation(derivation)));
+            }
+            if let AssignmentsIntersection::Decision((global_index, version, _)) =
+                &pa.assignments_intersection
+            {
+                let decision = Decision::new(
+                    package.clone(),
+                    version.clone(),
+                    pa.highest_decision_level.0,
+                );
+                assignments.push((*global_index, Assignment::Decision(decision)));
+            }
+        }
+        assignments.sort_by_key(|(global_index, _)| *global_index);
+        assignments.into_iter().map(|(_, a)| a).collect()
+    }
+
+    /// Find the satisfier of an incompatibility, the earliest assignment
+    /// such that the incompatibility is satisfied by the partial solution
+    /// up to and including that assignment.
+    /// Return `None` if the incompatibility is not satisfied.
+    /// It is not used by the solver itself,
+    /// but exposed by [PackageTerms](crate::solver::PackageTerms).
+    pub fn satisfier_of(
+        &self,
+        incompat: &Incompatibility<P, VS>,
+        store: &Arena<Incompatibility<P, VS>>,
+    ) -> Option<Assignment<P, VS>> {
+        if self.relation(incompat) != Relation::Satisfied {
+            return None;
+        }
+        let satisfied_map = Self::find_satisfier(incompat, &self.package_assignments, store);
+        let (package, &(index, _, decision_level)) = satisfied_map
+            .iter()
+            .max_by_key(|(_p, (_, global_index, _))| global_index)?;
+        let pa = self.package_assignments.get(package)?;
+        match pa.dated_derivations.get(index) {
+            Some(dd) => {
+                let cause = &store[dd.cause];
+                let term = cause.get(package).expect("cause of a derivation").negate();
+                let derivation =
+                    Derivation::new(package.clone(), term, cause.clone(), decision_level.0);
+                Some(Assignment::Derivation(derivation))
+            }
+            None => match &pa.assignments_intersection {
+                AssignmentsIntersection::Decision((_, version, _)) => Some(Assignment::Decision(
+                    Decision::new(package.clone(), version.clone(), decision_level.0),
+                )),
+                AssignmentsIntersection::Derivations(_) => None,
+            },
+        }
+    }
+
     /// Figure out if the satisfier and previous satisfier are of different decision levels.
     pub fn satisfier_search(
         &self,
-        incompat: &Incompatibility<P, V>,
-        store: &Arena<Incompatibility<P, V>>,
-    ) -> (P, SatisfierSearch<P, V>) {
+        incompat: &Incompatibility<P, VS>,
+        store: &Arena<Incompatibility<P, VS>>,
+    ) -> (P, SatisfierSearch<P, VS>) {
         let satisfied_map = Self::find_satisfier(incompat, &self.package_assignments, store);
         let (satisfier_package, &(satisfier_index, _, satisfier_decision_level)) = satisfied_map
             .iter()
@@ -296,6 +512,7 @@ impl<P: Package, V: Version> PartialSolution<P, V> {
         if previous_satisfier_level < satisfier_decision_level {
             let search_result = SatisfierSearch::DifferentDecisionLevels {
                 previous_satisfier_level,
+                satisfier_level: satisfier_decision_level,
             };
             (satisfier_package, search_result)
         } else {
@@ -318,9 +535,9 @@ impl<P: Package, V: Version> PartialSolution<P, V> {
     /// It would be nice if we could get rid of it, but I don't know if then it will be possible
     /// to return a coherent previous_satisfier_level.
     fn find_satisfier(
-        incompat: &Incompatibility<P, V>,
-        package_assignments: &Map<P, PackageAssignments<P, V>>,
-        store: &Arena<Incompatibility<P, V>>,
+        incompat: &Incompatibility<P, VS>,
+        package_assignments: &Map<P, PackageAssignments<P, VS>>,
+        store: &Arena<Incompatibility<P, VS>>,
     ) -> SmallMap<P, (usize, u32, DecisionLevel)> {
         let mut satisfied = SmallMap::Empty;
         for (package, incompat_term) in incompat.iter() {
@@ -337,11 +554,11 @@ impl<P: Package, V: Version> PartialSolution<P, V> {
     /// such that incompatibility is satisfied by the partial solution up to
     /// and including that assignment plus satisfier.
     fn find_previous_satisfier(
-        incompat: &Incompatibility<P, V>,
+        incompat: &Incompatibility<P, VS>,
         satisfier_package: &P,
         mut satisfied_map: SmallMap<P, (usize, u32, DecisionLevel)>,
-        package_assignments: &Map<P, PackageAssignments<P, V>>,
-        store: &Arena<Incompatibility<P, V>>,
+        package_assignments: &Map<P, PackageAssignments<P, VS>>,
+        store: &Arena<Incompatibility<P, VS>>,
     ) -> DecisionLevel {
         // First, let's retrieve the previous derivations and the initial accum_term.
         let satisfier_pa = package_assignments.get(satisfier_package).unwrap();
@@ -375,13 +592,13 @@ impl<P: Package, V: Version> PartialSolution<P, V> {
     }
 }
 
-impl<P: Package, V: Version> PackageAssignments<P, V> {
+impl<P: Package, VS: VersionSet> PackageAssignments<P, VS> {
     fn satisfier(
         &self,
         package: &P,
-        incompat_term: &Term<V>,
-        start_term: Term<V>,
-        store: &Arena<Incompatibility<P, V>>,
+        incompat_term: &Term<VS>,
+        start_term: Term<VS>,
+        store: &Arena<Incompatibility<P, VS>>,
     ) -> (usize, u32, DecisionLevel) {
         // Term where we accumulate intersections until incompat_term is satisfied.
         let mut accum_term = start_term;
@@ -413,9 +630,9 @@ impl<P: Package, V: Version> PackageAssignments<P, V> {
     }
 }
 
-impl<V: Version> AssignmentsIntersection<V> {
+impl<VS: VersionSet> AssignmentsIntersection<VS> {
     /// Returns the term intersection of all assignments (decision included).
-    fn term(&self) -> &Term<V> {
+    fn term(&self) -> &Term<VS> {
         match self {
             Self::Decision((_, _, term)) => term,
             Self::Derivations(term) => term,
@@ -429,7 +646,7 @@ impl<V: Version> AssignmentsIntersection<V> {
     fn potential_package_filter<'a, P: Package>(
         &'a self,
         package: &'a P,
-    ) -> Option<(&'a P, &'a Range<V>)> {
+    ) -> Option<(&'a P, &'a VS)> {
         match self {
             Self::Decision(_) => None,
             Self::Derivations(term_intersection) => {
@@ -442,3 +659,293 @@ impl<V: Version> AssignmentsIntersection<V> {
         }
     }
 }
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+pub mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::range::Range;
+    use crate::version::NumberVersion;
+
+    #[test]
+    fn decision_level_increment_and_decrement() {
+        assert_eq!(DecisionLevel(0).increment(), DecisionLevel(1));
+        assert_eq!(DecisionLevel(1).decrement(), DecisionLevel(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "no decision level below zero")]
+    fn decision_level_decrement_at_zero() {
+        DecisionLevel(0).decrement();
+    }
+
+    #[test]
+    #[should_panic(expected = "decision level overflow")]
+    fn decision_level_increment_overflow() {
+        DecisionLevel(u32::MAX).increment();
+    }
+
+    #[test]
+    fn backtrack_empty_partial_solution() {
+        let mut partial_solution = PartialSolution::<&str, Range<NumberVersion>>::empty();
+        partial_solution.backtrack(DecisionLevel(0));
+        partial_solution.backtrack(DecisionLevel(3));
+        assert_eq!(partial_solution.current_decision_level, DecisionLevel(0));
+        assert!(partial_solution.package_assignments.is_empty());
+    }
+
+    #[test]
+    fn backtrack_above_current_decision_level() {
+        let mut store = Arena::new();
+        let mut partial_solution = PartialSolution::<&str, Range<NumberVersion>>::empty();
+        let not_root = store.alloc(Incompatibility::not_root("root", NumberVersion(1)));
+        partial_solution.add_derivation("root", not_root, &store);
+        partial_solution.add_decision("root", NumberVersion(1));
+        partial_solution.backtrack(DecisionLevel(5));
+        assert_eq!(partial_solution.current_decision_level, DecisionLevel(1));
+        assert_eq!(
+            partial_solution.term_intersection_for_package(&"root"),
+            Some(&Term::exact(NumberVersion(1)))
+        );
+    }
+
+    #[test]
+    fn package_terms_after_decisions() {
+        // Beginning of the resolution of the example in the crate documentation.
+        let mut store = Arena::new();
+        let mut partial_solution = PartialSolution::<&str, Range<NumberVersion>>::empty();
+        let not_root = store.alloc(Incompatibility::not_root("root", NumberVersion(1)));
+        partial_solution.add_derivation("root", not_root, &store);
+        partial_solution.add_decision("root", NumberVersion(1));
+        let root_exact = Range::exact(1);
+        let menu = store.alloc(Incompatibility::from_dependency(
+            "root",
+            root_exact.clone(),
+            "menu",
+            Range::any(),
+        ));
+        let icons = store.alloc(Incompatibility::from_dependency(
+            "root",
+            root_exact,
+            "icons",
+            Range::any(),
+        ));
+        partial_solution.add_derivation("menu", menu, &store);
+        partial_solution.add_derivation("icons", icons, &store);
+        partial_solution.add_decision("menu", NumberVersion(1));
+
+        let mut terms: Vec<_> = partial_solution
+            .package_terms()
+            .map(|(p, t)| (*p, t.clone()))
+            .collect();
+        terms.sort_by_key(|(p, _)| *p);
+        assert_eq!(
+            terms,
+            vec![
+                ("icons", Term::Positive(Range::any())),
+                ("menu", Term::exact(NumberVersion(1))),
+                ("root", Term::exact(NumberVersion(1))),
+            ]
+        );
+    }
+
+    #[test]
+    fn ranked_potential_packages_by_version_count() {
+        let mut store = Arena::new();
+        let mut partial_solution = PartialSolution::<&str, Range<NumberVersion>>::empty();
+        let not_root = store.alloc(Incompatibility::not_root("root", NumberVersion(1)));
+        partial_solution.add_derivation("root", not_root, &store);
+        partial_solution.add_decision("root", NumberVersion(1));
+        for (dependency, range) in [
+            ("menu", Range::any()),
+            ("icons", Range::higher_than(2)),
+            ("dropdown", Range::any()),
+        ] {
+            let incompat = store.alloc(Incompatibility::from_dependency(
+                "root",
+                Range::exact(1),
+                dependency,
+                range,
+            ));
+            partial_solution.add_derivation(dependency, incompat, &store);
+        }
+
+        let available = |_: &&str| (1..=3).map(NumberVersion);
+        let ranking = partial_solution.ranked_potential_packages(available);
+        assert!(ranking.windows(2).all(|w| w[0].2 <= w[1].2));
+        assert_eq!(
+            ranking[0],
+            ("icons", Term::Positive(Range::higher_than(2)), 2)
+        );
+        let mut packages: Vec<_> = ranking.iter().map(|(p, _, _)| *p).collect();
+        packages.sort_unstable();
+        assert_eq!(packages, vec!["dropdown", "icons", "menu"]);
+    }
+
+    #[test]
+    fn unsatisfied_derivations_lists_undecided_packages() {
+        let mut store = Arena::new();
+        let mut partial_solution = PartialSolution::<&str, Range<NumberVersion>>::empty();
+        let not_root = store.alloc(Incompatibility::not_root("root", NumberVersion(1)));
+        partial_solution.add_derivation("root", not_root, &store);
+        partial_solution.add_decision("root", NumberVersion(1));
+        let menu = store.alloc(Incompatibility::from_dependency(
+            "root",
+            Range::exact(1),
+            "menu",
+            Range::between(1, 3),
+        ));
+        partial_solution.add_derivation("menu", menu, &store);
+
+        assert!(partial_solution.extract_solution().is_none());
+        assert_eq!(
+            partial_solution.unsatisfied_derivations(),
+            vec![("menu", Term::Positive(Range::between(1, 3)))]
+        );
+
+        partial_solution.add_decision("menu", NumberVersion(2));
+        assert!(partial_solution.unsatisfied_derivations().is_empty());
+        assert!(partial_solution.extract_solution().is_some());
+    }
+
+    #[test]
+    fn satisfier_of_incompatibility() {
+        let mut store = Arena::new();
+        let mut partial_solution = PartialSolution::<&str, Range<NumberVersion>>::empty();
+        let not_root = store.alloc(Incompatibility::not_root("root", NumberVersion(1)));
+        partial_solution.add_derivation("root", not_root, &store);
+        partial_solution.add_decision("root", NumberVersion(1));
+        let menu = store.alloc(Incompatibility::from_dependency(
+            "root",
+            Range::exact(1),
+            "menu",
+            Range::any(),
+        ));
+        partial_solution.add_derivation("menu", menu, &store);
+
+        // Satisfied by the derivation of "menu", at the decision level of "root".
+        let any_menu =
+            Incompatibility::from_dependency("menu", Range::any(), "dropdown", Range::none());
+        let satisfier = partial_solution.satisfier_of(&any_menu, &store).unwrap();
+        assert!(matches!(satisfier, Assignment::Derivation(_)));
+        assert_eq!(
+            (satisfier.package(), satisfier.decision_level()),
+            (&"menu", 1)
+        );
+
+        // Not satisfied until a version of "menu" is decided.
+        let root_menu = Incompatibility::forbidden_combination(
+            "root",
+            Range::exact(1),
+            "menu",
+            Range::exact(1),
+        );
+        assert!(partial_solution.satisfier_of(&root_menu, &store).is_none());
+        partial_solution.add_decision("menu", NumberVersion(1));
+        let satisfier = partial_solution.satisfier_of(&root_menu, &store).unwrap();
+        assert!(matches!(satisfier, Assignment::Decision(_)));
+        assert_eq!(
+            (satisfier.package(), satisfier.decision_level()),
+            (&"menu", 2)
+        );
+
+        // Never satisfied since "icons" has no assignment.
+        let icons = Incompatibility::from_dependency("icons", Range::any(), "menu", Range::none());
+        assert!(partial_solution.satisfier_of(&icons, &store).is_none());
+    }
+
+    /// Step of a random history of the partial solution.
+    #[derive(Debug, Clone)]
+    enum Step {
+        /// Derive a positive or negative term of one of a few packages.
+        Derive(u32, Range<NumberVersion>, bool),
+        /// Decide a new package, starting a new decision level.
+        Decide,
+    }
+
+    fn step_strategy() -> impl Strategy<Value = Step> {
+        prop_oneof![
+            3 => (
+                0..3u32,
+                Range::arbitrary_with(2, 10).prop_filter("non empty", |r| !r.is_empty()),
+                any::<bool>(),
+            )
+                .prop_map(|(p, r, positive)| Step::Derive(p, r, positive)),
+            1 => Just(Step::Decide),
+        ]
+    }
+
+    /// Apply the steps of a history, stopping before the decision
+    /// that would start a level above `max_level`.
+    fn replay(
+        steps: &[Step],
+        max_level: u32,
+        store: &mut Arena<Incompatibility<u32, Range<NumberVersion>>>,
+    ) -> PartialSolution<u32, Range<NumberVersion>> {
+        let mut partial_solution = PartialSolution::empty();
+        let mut decisions = 0;
+        for step in steps {
+            match step {
+                Step::Derive(p, range, positive) => {
+                    let cause = if *positive {
+                        Incompatibility::from_dependency(100, Range::any(), *p, range.clone())
+                    } else {
+                        Incompatibility::no_versions(*p, Term::Positive(range.clone()))
+                    };
+                    let cause = store.alloc(cause);
+                    partial_solution.add_derivation(*p, cause, store);
+                }
+                Step::Decide => {
+                    // A decided package must have been derived first,
+                    // at the level preceding its decision.
+                    let package = 100 + decisions + 1;
+                    let cause = store.alloc(Incompatibility::from_dependency(
+                        100,
+                        Range::any(),
+                        package,
+                        Range::any(),
+                    ));
+                    partial_solution.add_derivation(package, cause, store);
+                    if decisions == max_level {
+                        break;
+                    }
+                    decisions += 1;
+                    partial_solution.add_decision(package, NumberVersion(0));
+                }
+            }
+        }
+        partial_solution
+    }
+
+    fn sorted_terms(
+        partial_solution: &PartialSolution<u32, Range<NumberVersion>>,
+    ) -> Vec<(u32, Term<Range<NumberVersion>>)> {
+        let mut terms: Vec<_> = partial_solution
+            .package_terms()
+            .map(|(p, t)| (*p, t.clone()))
+            .collect();
+        terms.sort_by_key(|(p, _)| *p);
+        terms
+    }
+
+    proptest! {
+        #[test]
+        fn backtrack_is_same_as_replay(
+            steps in prop::collection::vec(step_strategy(), 0..30),
+            level in 0..6u32,
+        ) {
+            let mut store = Arena::new();
+            let mut partial_solution = replay(&steps, u32::MAX, &mut store);
+            partial_solution.backtrack(DecisionLevel(level));
+            let replayed = replay(&steps, level, &mut store);
+            prop_assert_eq!(
+                partial_solution.current_decision_level,
+                replayed.current_decision_level
+            );
+            prop_assert_eq!(sorted_terms(&partial_solution), sorted_terms(&replayed));
+        }
+    }
+}
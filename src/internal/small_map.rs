@@ -1,15 +1,21 @@
 use crate::type_aliases::Map;
-use std::hash::Hash;
+use core::hash::Hash;
 
-#[derive(Debug, Clone)]
+#[cfg(not(feature = "std"))]
+use hashbrown::hash_map::Iter as MapIter;
+#[cfg(feature = "std")]
+use std::collections::hash_map::Iter as MapIter;
+
+#[derive(Debug, Clone, Default)]
 pub enum SmallMap<K, V> {
+    #[default]
     Empty,
     One([(K, V); 1]),
     Two([(K, V); 2]),
     Flexible(Map<K, V>),
 }
 
-impl<K: PartialEq + Eq + Hash, V> SmallMap<K, V> {
+impl<K: Eq + Hash, V> SmallMap<K, V> {
     pub fn get(&self, key: &K) -> Option<&V> {
         match self {
             Self::Empty => None,
@@ -36,7 +42,7 @@ impl<K: PartialEq + Eq + Hash, V> SmallMap<K, V> {
 
     pub fn remove(&mut self, key: &K) -> Option<V> {
         let out;
-        *self = match std::mem::take(self) {
+        *self = match core::mem::take(self) {
             Self::Empty => {
                 out = None;
                 Self::Empty
@@ -71,7 +77,7 @@ impl<K: PartialEq + Eq + Hash, V> SmallMap<K, V> {
     }
 
     pub fn insert(&mut self, key: K, value: V) {
-        *self = match std::mem::take(self) {
+        *self = match core::mem::take(self) {
             Self::Empty => Self::One([(key, value)]),
             Self::One([(k, v)]) => {
                 if key == k {
@@ -86,7 +92,7 @@ impl<K: PartialEq + Eq + Hash, V> SmallMap<K, V> {
                 } else if key == k2 {
                     Self::Two([(k1, v1), (k2, value)])
                 } else {
-                    let mut data: Map<K, V> = Map::with_capacity_and_hasher(3, Default::default());
+                    let mut data: Map<K, V> = Map::default();
                     data.insert(key, value);
                     data.insert(k1, v1);
                     data.insert(k2, v2);
@@ -101,7 +107,7 @@ impl<K: PartialEq + Eq + Hash, V> SmallMap<K, V> {
     }
 }
 
-impl<K: Clone + PartialEq + Eq + Hash, V: Clone> SmallMap<K, V> {
+impl<K: Clone + Eq + Hash, V: Clone> SmallMap<K, V> {
     /// Merge two hash maps.
     ///
     /// When a key is common to both,
@@ -129,12 +135,6 @@ impl<K: Clone + PartialEq + Eq + Hash, V: Clone> SmallMap<K, V> {
     }
 }
 
-impl<K, V> Default for SmallMap<K, V> {
-    fn default() -> Self {
-        Self::Empty
-    }
-}
-
 impl<K, V> SmallMap<K, V> {
     pub fn len(&self) -> usize {
         match self {
@@ -151,12 +151,12 @@ impl<K: Eq + Hash + Clone, V: Clone> SmallMap<K, V> {
         match self {
             Self::Empty => Map::default(),
             Self::One([(k, v)]) => {
-                let mut map = Map::with_capacity_and_hasher(1, Default::default());
+                let mut map = Map::default();
                 map.insert(k.clone(), v.clone());
                 map
             }
             Self::Two(data) => {
-                let mut map = Map::with_capacity_and_hasher(2, Default::default());
+                let mut map = Map::default();
                 for (k, v) in data {
                     map.insert(k.clone(), v.clone());
                 }
@@ -168,8 +168,8 @@ impl<K: Eq + Hash + Clone, V: Clone> SmallMap<K, V> {
 }
 
 enum IterSmallMap<'a, K, V> {
-    Inline(std::slice::Iter<'a, (K, V)>),
-    Map(std::collections::hash_map::Iter<'a, K, V>),
+    Inline(core::slice::Iter<'a, (K, V)>),
+    Map(MapIter<'a, K, V>),
 }
 
 impl<'a, K: 'a, V: 'a> Iterator for IterSmallMap<'a, K, V> {
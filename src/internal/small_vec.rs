@@ -1,8 +1,12 @@
-use std::fmt;
-use std::ops::Deref;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub enum SmallVec<T> {
+    #[default]
     Empty,
     One([T; 1]),
     Two([T; 2]),
@@ -28,7 +32,7 @@ impl<T> SmallVec<T> {
     }
 
     pub fn push(&mut self, new: T) {
-        *self = match std::mem::take(self) {
+        *self = match core::mem::take(self) {
             Self::Empty => Self::One([new]),
             Self::One([v1]) => Self::Two([v1, new]),
             Self::Two([v1, v2]) => Self::Flexible(vec![v1, v2, new]),
@@ -40,7 +44,7 @@ impl<T> SmallVec<T> {
     }
 
     pub fn pop(&mut self) -> Option<T> {
-        match std::mem::take(self) {
+        match core::mem::take(self) {
             Self::Empty => None,
             Self::One([v1]) => {
                 *self = Self::Empty;
@@ -59,23 +63,17 @@ impl<T> SmallVec<T> {
     }
 
     pub fn clear(&mut self) {
-        if let Self::Flexible(mut v) = std::mem::take(self) {
+        if let Self::Flexible(mut v) = core::mem::take(self) {
             v.clear();
             *self = Self::Flexible(v);
         } // else: self already eq Empty from the take
     }
 
-    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+    pub fn iter(&self) -> core::slice::Iter<'_, T> {
         self.as_slice().iter()
     }
 }
 
-impl<T> Default for SmallVec<T> {
-    fn default() -> Self {
-        Self::Empty
-    }
-}
-
 impl<T> Deref for SmallVec<T> {
     type Target = [T];
 
@@ -87,7 +85,7 @@ impl<T> Deref for SmallVec<T> {
 impl<'a, T> IntoIterator for &'a SmallVec<T> {
     type Item = &'a T;
 
-    type IntoIter = std::slice::Iter<'a, T>;
+    type IntoIter = core::slice::Iter<'a, T>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.iter()
@@ -102,6 +100,12 @@ impl<T: PartialEq> PartialEq for SmallVec<T> {
     }
 }
 
+impl<T: Hash> Hash for SmallVec<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state)
+    }
+}
+
 impl<T: fmt::Debug> fmt::Debug for SmallVec<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.as_slice().fmt(f)
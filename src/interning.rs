@@ -0,0 +1,260 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Interning of packages, to solve with cheap package identifiers.
+//!
+//! The solver hashes, compares and clones packages a lot.
+//! For packages that are expensive to handle, like long strings,
+//! [resolve_interned] gives each package a compact [PackageId]
+//! when it is first seen, and the solver only works with those identifiers.
+//! Packages are translated back to the ones of the dependency provider
+//! in the solution and the errors, so callers never see the identifiers.
+//!
+//! ```
+//! # use pubgrub::interning::resolve_interned;
+//! # use pubgrub::range::Range;
+//! # use pubgrub::solver::{resolve, OfflineDependencyProvider};
+//! # use pubgrub::version::NumberVersion;
+//! #
+//! let mut dependency_provider = OfflineDependencyProvider::<String, Range<NumberVersion>>::new();
+//! let root = "org.example.application".to_string();
+//! let menu = "org.example.widgets.menu".to_string();
+//! dependency_provider.add_dependencies(root.clone(), 1, vec![(menu.clone(), Range::any())]);
+//! dependency_provider.add_dependencies(menu, 1, vec![]);
+//!
+//! let solution = resolve_interned(&dependency_provider, root.clone(), 1).unwrap();
+//! assert_eq!(solution, resolve(&dependency_provider, root, 1).unwrap());
+//! ```
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cell::RefCell;
+use core::fmt::{self, Display};
+
+use crate::error::{Error, PubGrubError};
+use crate::incompatibility::Incompatibility;
+use crate::package::Package;
+use crate::solver::{resolve, Dependencies, DependencyProvider};
+use crate::type_aliases::{Map, SelectedDependencies};
+use crate::version_set::VersionSet;
+
+/// Compact identifier of an interned package.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PackageId(u32);
+
+impl Display for PackageId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// Table of interned packages, giving each distinct package its own [PackageId].
+#[derive(Debug, Clone)]
+pub struct Interner<P: Package> {
+    packages: Vec<P>,
+    ids: Map<P, PackageId>,
+}
+
+impl<P: Package> Interner<P> {
+    /// Creates an empty table.
+    pub fn new() -> Self {
+        Self {
+            packages: Vec::new(),
+            ids: Map::default(),
+        }
+    }
+
+    /// Identifier of a package, interning it if it was never seen before.
+    pub fn intern(&mut self, package: P) -> PackageId {
+        if let Some(id) = self.ids.get(&package) {
+            return *id;
+        }
+        let id = PackageId(self.packages.len() as u32);
+        self.packages.push(package.clone());
+        self.ids.insert(package, id);
+        id
+    }
+
+    /// Package interned with that identifier.
+    /// Panics if the identifier comes from another table.
+    pub fn package(&self, id: PackageId) -> &P {
+        &self.packages[id.0 as usize]
+    }
+
+    /// Number of interned packages.
+    pub fn len(&self) -> usize {
+        self.packages.len()
+    }
+
+    /// Check if no package was interned.
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty()
+    }
+}
+
+impl<P: Package> Default for Interner<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Same as [resolve], except that packages are interned,
+/// so that the solver only hashes, compares and clones [PackageId]s.
+/// The solution and errors refer to the packages of the dependency provider.
+pub fn resolve_interned<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<P, VS>,
+    package: P,
+    version: impl Into<VS::V>,
+) -> Result<SelectedDependencies<P, VS::V>, PubGrubError<P, VS>> {
+    let mut interner = Interner::new();
+    let root = interner.intern(package);
+    let interning_provider = InterningDependencyProvider {
+        dependency_provider,
+        interner: RefCell::new(interner),
+    };
+    let result = resolve(&interning_provider, root, version);
+    let interner = interning_provider.interner.into_inner();
+    let mut package = |id| interner.package(id).clone();
+    match result {
+        Ok(solution) => Ok(solution
+            .into_iter()
+            .map(|(id, v)| (package(id), v))
+            .collect()),
+        Err(error) => Err(error.map_packages(&mut package)),
+    }
+}
+
+/// Dependency provider used by [resolve_interned],
+/// translating between packages and their identifiers
+/// and delegating everything else.
+struct InterningDependencyProvider<'a, P: Package, DP> {
+    dependency_provider: &'a DP,
+    interner: RefCell<Interner<P>>,
+}
+
+/// Package passed to [DependencyProvider::choose_package_version],
+/// remembering its position among the potential packages.
+struct IndexedPackage<'a, P> {
+    index: usize,
+    package: &'a P,
+}
+
+impl<P> Borrow<P> for IndexedPackage<'_, P> {
+    fn borrow(&self) -> &P {
+        self.package
+    }
+}
+
+impl<P: Package, DP> InterningDependencyProvider<'_, P, DP> {
+    fn intern_dependencies<VS: VersionSet>(
+        &self,
+        dependencies: Dependencies<P, VS>,
+    ) -> Dependencies<PackageId, VS> {
+        let mut interner = self.interner.borrow_mut();
+        match dependencies {
+            Dependencies::Unknown => Dependencies::Unknown,
+            Dependencies::Known(constraints) => Dependencies::Known(
+                constraints
+                    .into_iter()
+                    .map(|(p, range)| (interner.intern(p), range))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl<P: Package, VS: VersionSet, DP: DependencyProvider<P, VS>> DependencyProvider<PackageId, VS>
+    for InterningDependencyProvider<'_, P, DP>
+{
+    fn choose_package_version<T: Borrow<PackageId>, U: Borrow<VS>>(
+        &self,
+        potential_packages: impl Iterator<Item = (T, U)>,
+    ) -> Result<(T, Option<VS::V>), Box<dyn Error>> {
+        let mut potential_packages: Vec<_> = potential_packages.collect();
+        let interner = self.interner.borrow();
+        let (chosen, version) = self.dependency_provider.choose_package_version(
+            potential_packages
+                .iter()
+                .enumerate()
+                .map(|(index, (id, range))| {
+                    let package = interner.package(*id.borrow());
+                    (IndexedPackage { index, package }, range.borrow())
+                }),
+        )?;
+        let index = chosen.index;
+        Ok((potential_packages.swap_remove(index).0, version))
+    }
+
+    fn prioritize(&self, package: &PackageId, range: &VS) -> u32 {
+        let interner = self.interner.borrow();
+        self.dependency_provider
+            .prioritize(interner.package(*package), range)
+    }
+
+    fn score_version(&self, package: &PackageId, version: &VS::V) -> i64 {
+        let interner = self.interner.borrow();
+        self.dependency_provider
+            .score_version(interner.package(*package), version)
+    }
+
+    fn get_dependencies(
+        &self,
+        package: &PackageId,
+        version: &VS::V,
+    ) -> Result<Dependencies<PackageId, VS>, Box<dyn Error>> {
+        let dependencies = {
+            let interner = self.interner.borrow();
+            self.dependency_provider
+                .get_dependencies(interner.package(*package), version)?
+        };
+        Ok(self.intern_dependencies(dependencies))
+    }
+
+    fn get_dev_dependencies(
+        &self,
+        package: &PackageId,
+        version: &VS::V,
+    ) -> Result<Dependencies<PackageId, VS>, Box<dyn Error>> {
+        let dev_dependencies = {
+            let interner = self.interner.borrow();
+            self.dependency_provider
+                .get_dev_dependencies(interner.package(*package), version)?
+        };
+        Ok(self.intern_dependencies(dev_dependencies))
+    }
+
+    fn package_exists(&self, package: &PackageId) -> bool {
+        let interner = self.interner.borrow();
+        self.dependency_provider
+            .package_exists(interner.package(*package))
+    }
+
+    fn accept_version(&self, package: &PackageId, version: &VS::V) -> bool {
+        let interner = self.interner.borrow();
+        self.dependency_provider
+            .accept_version(interner.package(*package), version)
+    }
+
+    fn is_virtual(&self, package: &PackageId) -> bool {
+        let interner = self.interner.borrow();
+        self.dependency_provider
+            .is_virtual(interner.package(*package))
+    }
+
+    fn known_incompatibilities(&self) -> Vec<Incompatibility<PackageId, VS>> {
+        let mut interner = self.interner.borrow_mut();
+        self.dependency_provider
+            .known_incompatibilities()
+            .into_iter()
+            // Incompatibilities known before solving are never derived from others.
+            .filter_map(|incompat| incompat.external())
+            .map(|external| {
+                Incompatibility::from_external(external.map_packages(&mut |p| interner.intern(p)))
+            })
+            .collect()
+    }
+
+    fn should_cancel(&self) -> Result<(), Box<dyn Error>> {
+        self.dependency_provider.should_cancel()
+    }
+}
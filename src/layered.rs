@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Dependency provider composed of several layers of packages with a precedence order,
+//! like local overrides, then a private mirror, then a public registry.
+//!
+//! When choosing a version, the versions in range of the layer with the highest precedence
+//! are preferred, even if other layers have higher versions.
+//! A version present in several layers comes from the one with the highest precedence,
+//! and all layers must agree on its dependencies and dev dependencies.
+//!
+//! ```
+//! # use pubgrub::layered::LayeredDependencyProvider;
+//! # use pubgrub::range::Range;
+//! # use pubgrub::solver::{resolve, OfflineDependencyProvider};
+//! # use pubgrub::version::NumberVersion;
+//! #
+//! let mut local = OfflineDependencyProvider::<&str, Range<NumberVersion>>::new();
+//! local.add_dependencies("menu", 1, vec![]);
+//! let mut registry = OfflineDependencyProvider::new();
+//! registry.add_dependencies("root", 1, vec![("menu", Range::any())]);
+//! registry.add_dependencies("menu", 2, vec![]);
+//!
+//! let layered = LayeredDependencyProvider::new(vec![local, registry]);
+//! let solution = resolve(&layered, "root", 1).unwrap();
+//! assert_eq!(solution["menu"], NumberVersion(1));
+//! assert_eq!(layered.source(&"menu", &NumberVersion(1)), Some(0));
+//! ```
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+
+use crate::error::Error;
+use crate::incompatibility::Incompatibility;
+use crate::package::Package;
+use crate::solver::{
+    choose_package_with_fewest_versions_by_package_order, Dependencies, DependencyConstraints,
+    DependencyProvider, OfflineDependencyProvider,
+};
+use crate::version_set::VersionSet;
+
+/// Dependency provider made of layers of [OfflineDependencyProvider],
+/// from the highest to the lowest precedence.
+#[derive(Debug, Clone)]
+pub struct LayeredDependencyProvider<P: Package, VS: VersionSet> {
+    layers: Vec<OfflineDependencyProvider<P, VS>>,
+}
+
+impl<P: Package, VS: VersionSet> LayeredDependencyProvider<P, VS> {
+    /// Creates a provider from layers ordered from the highest to the lowest precedence.
+    pub fn new(layers: Vec<OfflineDependencyProvider<P, VS>>) -> Self {
+        Self { layers }
+    }
+
+    /// Index of the layer with the highest precedence providing that package version,
+    /// or [None] if no layer has it.
+    pub fn source(&self, package: &P, version: &VS::V) -> Option<usize> {
+        self.layers
+            .iter()
+            .position(|layer| Self::provides(layer, package, version))
+    }
+
+    /// Check if that layer has that package version.
+    fn provides(layer: &OfflineDependencyProvider<P, VS>, package: &P, version: &VS::V) -> bool {
+        layer
+            .versions(package)
+            .is_some_and(|mut versions| versions.any(|v| v == version))
+    }
+
+    /// Dependencies declared by the first layer,
+    /// or an error if another layer declares different ones.
+    fn agreed_dependencies(
+        package: &P,
+        version: &VS::V,
+        kind: &str,
+        declarations: Vec<(usize, DependencyConstraints<P, VS>)>,
+    ) -> Result<Option<DependencyConstraints<P, VS>>, Box<dyn Error>> {
+        let mut declarations = declarations.into_iter();
+        let Some((source, dependencies)) = declarations.next() else {
+            return Ok(None);
+        };
+        if let Some((conflicting, _)) = declarations.find(|(_, other)| other != &dependencies) {
+            return Err(format!(
+                "layers {} and {} declare different {} for {} {}",
+                source, conflicting, kind, package, version
+            )
+            .into());
+        }
+        Ok(Some(dependencies))
+    }
+
+    /// Versions of a package, grouped by layer from the highest to the lowest precedence,
+    /// and from the newest to the oldest within each layer.
+    /// Versions already provided by a layer of higher precedence are skipped.
+    fn versions_by_precedence(&self, package: &P) -> Vec<VS::V> {
+        let mut versions: Vec<VS::V> = Vec::new();
+        for layer in &self.layers {
+            let mut layer_versions: Vec<VS::V> = layer
+                .versions(package)
+                .into_iter()
+                .flatten()
+                .filter(|v| !versions.contains(v))
+                .cloned()
+                .collect();
+            layer_versions.reverse();
+            versions.extend(layer_versions);
+        }
+        versions
+    }
+}
+
+/// Packages are picked with the fewest versions contained in the constraints first.
+/// Versions are picked from the layer with the highest precedence having one in range,
+/// with the newest versions first.
+/// Packages with the same number of versions are picked in [Ord] order.
+impl<P: Package + Ord, VS: VersionSet> DependencyProvider<P, VS>
+    for LayeredDependencyProvider<P, VS>
+{
+    fn choose_package_version<T: Borrow<P>, U: Borrow<VS>>(
+        &self,
+        potential_packages: impl Iterator<Item = (T, U)>,
+    ) -> Result<(T, Option<VS::V>), Box<dyn Error>> {
+        Ok(choose_package_with_fewest_versions_by_package_order(
+            |p| self.versions_by_precedence(p).into_iter(),
+            potential_packages,
+        ))
+    }
+
+    fn get_dependencies(
+        &self,
+        package: &P,
+        version: &VS::V,
+    ) -> Result<Dependencies<P, VS>, Box<dyn Error>> {
+        let mut declarations = Vec::new();
+        for (index, layer) in self.layers.iter().enumerate() {
+            if let Dependencies::Known(dependencies) = layer.get_dependencies(package, version)? {
+                declarations.push((index, dependencies));
+            }
+        }
+        Ok(
+            match Self::agreed_dependencies(package, version, "dependencies", declarations)? {
+                Some(dependencies) => Dependencies::Known(dependencies),
+                None => Dependencies::Unknown,
+            },
+        )
+    }
+
+    fn get_dev_dependencies(
+        &self,
+        package: &P,
+        version: &VS::V,
+    ) -> Result<Dependencies<P, VS>, Box<dyn Error>> {
+        let mut declarations = Vec::new();
+        for (index, layer) in self.layers.iter().enumerate() {
+            if !Self::provides(layer, package, version) {
+                continue;
+            }
+            match layer.get_dev_dependencies(package, version)? {
+                Dependencies::Known(dev_dependencies) => {
+                    declarations.push((index, dev_dependencies))
+                }
+                Dependencies::Unknown => return Ok(Dependencies::Unknown),
+            }
+        }
+        let dev_dependencies =
+            Self::agreed_dependencies(package, version, "dev dependencies", declarations)?;
+        Ok(Dependencies::Known(dev_dependencies.unwrap_or_default()))
+    }
+
+    fn package_exists(&self, package: &P) -> bool {
+        self.layers
+            .iter()
+            .any(|layer| layer.package_exists(package))
+    }
+
+    fn is_virtual(&self, package: &P) -> bool {
+        self.layers.iter().any(|layer| layer.is_virtual(package))
+    }
+
+    fn known_incompatibilities(&self) -> Vec<Incompatibility<P, VS>> {
+        self.layers
+            .iter()
+            .flat_map(|layer| layer.known_incompatibilities())
+            .collect()
+    }
+}
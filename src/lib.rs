@@ -13,14 +13,20 @@
 //! All the code in this crate is manipulating packages and versions, and for this to work
 //! we defined a [Package](package::Package) and [Version](version::Version) traits
 //! that are used as bounds on most of the exposed types and functions.
+//! The solver itself only manipulates sets of versions through the
+//! [VersionSet](version_set::VersionSet) trait,
+//! and [Range](range::Range) is the implementation of that trait
+//! for any type implementing [Version](version::Version).
+//! Non-interval domains can be supported by implementing
+//! [VersionSet](version_set::VersionSet) directly.
 //!
 //! Package identifiers needs to implement our [Package](package::Package) trait,
 //! which is automatic if the type already implements
-//! [Clone] + [Eq] + [Hash] + [Debug] + [Display](std::fmt::Display).
+//! [Clone] + [Eq] + [Hash] + [Debug] + [Display](core::fmt::Display).
 //! So things like [String] will work out of the box.
 //!
 //! Our [Version](version::Version) trait requires
-//! [Clone] + [Ord] + [Debug] + [Display](std::fmt::Display)
+//! [Clone] + [Ord] + [Debug] + [Display](core::fmt::Display)
 //! and also the definition of two methods,
 //! [lowest() -> Self](version::Version::lowest) which returns the lowest version existing,
 //! and [bump(&self) -> Self](version::Version::bump) which returns the next smallest version
@@ -50,7 +56,9 @@
 //! # use pubgrub::version::NumberVersion;
 //! # use pubgrub::range::Range;
 //! #
-//! let mut dependency_provider = OfflineDependencyProvider::<&str, NumberVersion>::new();
+//! type NumVS = Range<NumberVersion>;
+//!
+//! let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
 //!
 //! dependency_provider.add_dependencies(
 //!     "root", 1, vec![("menu", Range::any()), ("icons", Range::any())],
@@ -80,12 +88,14 @@
 //! # use pubgrub::range::Range;
 //! # use pubgrub::type_aliases::Map;
 //! # use std::error::Error;
-//! # use std::borrow::Borrow;
+//! # use core::borrow::Borrow;
 //! #
 //! # struct MyDependencyProvider;
 //! #
-//! impl DependencyProvider<String, SemanticVersion> for MyDependencyProvider {
-//!     fn choose_package_version<T: Borrow<String>, U: Borrow<Range<SemanticVersion>>>(&self,packages: impl Iterator<Item=(T, U)>) -> Result<(T, Option<SemanticVersion>), Box<dyn Error>> {
+//! type SemVS = Range<SemanticVersion>;
+//!
+//! impl DependencyProvider<String, SemVS> for MyDependencyProvider {
+//!     fn choose_package_version<T: Borrow<String>, U: Borrow<SemVS>>(&self,packages: impl Iterator<Item=(T, U)>) -> Result<(T, Option<SemanticVersion>), Box<dyn Error>> {
 //!         unimplemented!()
 //!     }
 //!
@@ -93,7 +103,7 @@
 //!         &self,
 //!         package: &String,
 //!         version: &SemanticVersion,
-//!     ) -> Result<Dependencies<String, SemanticVersion>, Box<dyn Error>> {
+//!     ) -> Result<Dependencies<String, SemVS>, Box<dyn Error>> {
 //!         unimplemented!()
 //!     }
 //! }
@@ -153,13 +163,13 @@
 //! [Output](crate::report::Reporter::Output) type and a single method.
 //! ```
 //! # use pubgrub::package::Package;
-//! # use pubgrub::version::Version;
+//! # use pubgrub::version_set::VersionSet;
 //! # use pubgrub::report::DerivationTree;
 //! #
-//! pub trait Reporter<P: Package, V: Version> {
+//! pub trait Reporter<P: Package, VS: VersionSet> {
 //!     type Output;
 //!
-//!     fn report(derivation_tree: &DerivationTree<P, V>) -> Self::Output;
+//!     fn report(derivation_tree: &DerivationTree<P, VS>) -> Self::Output;
 //! }
 //! ```
 //! Implementing a [Reporter](crate::report::Reporter) may involve a lot of heuristics
@@ -173,8 +183,13 @@
 //! # use pubgrub::report::{DefaultStringReporter, Reporter};
 //! # use pubgrub::error::PubGrubError;
 //! # use pubgrub::version::NumberVersion;
+//! # use pubgrub::range::Range;
+//! #
+//! # type NumVS = Range<NumberVersion>;
 //! #
-//! # let dependency_provider = OfflineDependencyProvider::<&str, NumberVersion>::new();
+//! # let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+//! # dependency_provider.add_dependencies("root", 1, vec![("menu", Range::higher_than(2))]);
+//! # dependency_provider.add_dependencies("menu", 1, vec![]);
 //! # let root_package = "root";
 //! # let root_version = 1;
 //! #
@@ -205,11 +220,27 @@
 //! Beware though that if you are using some kind of offline mode
 //! with a cache, you may want to know that some versions
 //! do not exist in your cache.
+//!
+//! # `no_std` support
+//!
+//! The default `std` feature can be disabled to build this crate
+//! with `#![no_std]`, only relying on the `alloc` crate.
+//! In that mode, [Map](type_aliases::Map) is a `hashbrown` hash map
+//! with the same hasher, instead of the one of `std`,
+//! and errors implement `core::error::Error`, which requires Rust 1.81.
 
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 #![allow(clippy::rc_buffer)]
 #![warn(missing_docs)]
 
+extern crate alloc;
+
+pub mod assignment;
 pub mod error;
+pub mod feature;
+pub mod incompatibility;
+pub mod interning;
+pub mod layered;
 pub mod package;
 pub mod range;
 pub mod report;
@@ -217,5 +248,6 @@ pub mod solver;
 pub mod term;
 pub mod type_aliases;
 pub mod version;
+pub mod version_set;
 
 mod internal;
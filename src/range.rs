@@ -11,17 +11,37 @@
 //!  - [any()](Range::any): the set of all possible versions
 //!  - [exact(v)](Range::exact): the set containing only the version v
 //!  - [higher_than(v)](Range::higher_than): the set defined by `v <= versions`
+//!  - [strictly_higher_than(v)](Range::strictly_higher_than): the set defined by `v < versions`
+//!  - [lower_than(v)](Range::lower_than): the set defined by `versions <= v`
 //!  - [strictly_lower_than(v)](Range::strictly_lower_than): the set defined by `versions < v`
 //!  - [between(v1, v2)](Range::between): the set defined by `v1 <= versions < v2`
+//!  - [interval(lower, upper)](Range::interval): the set of versions between two bounds,
+//!    each of them included, excluded or unbounded
 
-use std::cmp::Ordering;
-use std::fmt;
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::ops::Bound;
+use core::str::FromStr;
 
+use crate::error::Error;
 use crate::internal::small_vec::SmallVec;
-use crate::version::Version;
+use crate::version::{
+    NumberVersion, Prerelease, PrereleaseVersion, SemanticVersion, Version, VersionParseError,
+};
+use crate::version_set::VersionSet;
 
 /// A Range is a set of versions.
-#[derive(Debug, Clone, Eq, PartialEq)]
+///
+/// Ranges built with the functions of this module always have
+/// a canonical internal representation, so two ranges containing
+/// the same set of versions are equal, and hash equally if `V: Hash`.
+/// Ranges obtained by other means, like a deserialized sequence
+/// of overlapping or unordered intervals, do not have that guarantee.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Range<V: Version> {
@@ -59,6 +79,24 @@ impl<V: Version> Range<V> {
         }
     }
 
+    /// Set of all versions strictly higher than some version.
+    /// It is empty for the highest version.
+    pub fn strictly_higher_than(v: impl Into<V>) -> Self {
+        match v.into().checked_bump() {
+            Some(next) => Self::higher_than(next),
+            None => Self::none(),
+        }
+    }
+
+    /// Set of all versions lower or equal to some version.
+    /// It contains all versions for the highest version.
+    pub fn lower_than(v: impl Into<V>) -> Self {
+        match v.into().checked_bump() {
+            Some(next) => Self::strictly_lower_than(next),
+            None => Self::any(),
+        }
+    }
+
     /// Set of all versions strictly lower than some version.
     pub fn strictly_lower_than(v: impl Into<V>) -> Self {
         let v = v.into();
@@ -71,6 +109,29 @@ impl<V: Version> Range<V> {
         }
     }
 
+    /// Set containing exactly the given versions.
+    /// Consecutive versions, where one is the [bump](Version::checked_bump) of the other,
+    /// are merged into a single interval.
+    pub fn from_versions(versions: impl IntoIterator<Item = impl Into<V>>) -> Self {
+        let mut versions: Vec<V> = versions.into_iter().map(Into::into).collect();
+        versions.sort();
+        versions.dedup();
+        let mut segments: SmallVec<Interval<V>> = SmallVec::empty();
+        for v in versions {
+            // The highest version has no bump and ends an unbounded interval.
+            let bumped = v.checked_bump();
+            match segments.pop() {
+                Some((start, Some(end))) if end == v => segments.push((start, bumped)),
+                Some(last) => {
+                    segments.push(last);
+                    segments.push((v, bumped));
+                }
+                None => segments.push((v, bumped)),
+            }
+        }
+        Self { segments }
+    }
+
     /// Set of all versions comprised between two given versions.
     /// The lower bound is included and the higher bound excluded.
     /// `v1 <= v < v2`.
@@ -85,6 +146,59 @@ impl<V: Version> Range<V> {
             Self::none()
         }
     }
+
+    /// Set of all versions between two bounds,
+    /// each of them being included, excluded or unbounded.
+    /// This is the most general of the interval constructors,
+    /// `Range::between(v1, v2)` being `Range::interval(Included(v1), Excluded(v2))`.
+    ///
+    /// ```
+    /// # use core::ops::Bound::{Excluded, Included};
+    /// # use pubgrub::range::Range;
+    /// # use pubgrub::version::NumberVersion;
+    /// #
+    /// let range = Range::<NumberVersion>::interval(Excluded(1.into()), Included(3.into()));
+    /// assert_eq!(range, Range::between(2, 4));
+    /// ```
+    pub fn interval(lower: Bound<V>, upper: Bound<V>) -> Self {
+        let start = match lower {
+            Bound::Included(v) => v,
+            Bound::Excluded(v) => match v.checked_bump() {
+                Some(next) => next,
+                None => return Self::none(),
+            },
+            Bound::Unbounded => V::lowest(),
+        };
+        match upper {
+            Bound::Included(v) => match v.checked_bump() {
+                Some(next) => Self::between(start, next),
+                None => Self::higher_than(start),
+            },
+            Bound::Excluded(v) => Self::between(start, v),
+            Bound::Unbounded => Self::higher_than(start),
+        }
+    }
+}
+
+// Normalization.
+impl<V: Version> Range<V> {
+    /// Rebuild the canonical representation of this range.
+    ///
+    /// Versions are discrete, so bounds are always stored as an included start
+    /// and an excluded end, `v <= 3` being stored as `v < 3.bump()`.
+    /// As a result, ranges built with the functions of this module are already
+    /// normalized, and `Range::lower_than(3) == Range::strictly_lower_than(4)`
+    /// for [NumberVersion](crate::version::NumberVersion).
+    /// This is useful for ranges obtained by other means,
+    /// like a deserialized sequence of overlapping, unordered or empty intervals.
+    pub fn normalize(&self) -> Self {
+        self.segments
+            .iter()
+            .fold(Self::none(), |acc, (start, end)| match end {
+                None => acc.union(&Self::higher_than(start.clone())),
+                Some(end) => acc.union(&Self::between(start.clone(), end.clone())),
+            })
+    }
 }
 
 // Set operations.
@@ -118,6 +232,23 @@ impl<V: Version> Range<V> {
         }
     }
 
+    /// Versions of `bounds` that are not in this range.
+    /// The complement within [any](Range::any) is the same as [negate](Range::negate).
+    ///
+    /// ```
+    /// # use pubgrub::range::Range;
+    /// # use pubgrub::version::NumberVersion;
+    /// #
+    /// let range: Range<NumberVersion> = Range::between(3, 5);
+    /// assert_eq!(
+    ///     range.complement_within(&Range::between(1, 10)),
+    ///     Range::between(1, 3).union(&Range::between(5, 10))
+    /// );
+    /// ```
+    pub fn complement_within(&self, bounds: &Self) -> Self {
+        bounds.intersection(&self.negate())
+    }
+
     /// Helper function performing the negation of intervals in segments.
     /// For example:
     ///    [ (v1, None) ] => [ (start, Some(v1)) ]
@@ -142,13 +273,82 @@ impl<V: Version> Range<V> {
 
     // Union and intersection ##################################################
 
+    /// Check if this is the empty set of versions.
+    fn is_none(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Check if this is the set of all versions.
+    fn is_any(&self) -> bool {
+        matches!(self.segments.as_slice(), [(start, None)] if start == &V::lowest())
+    }
+
     /// Compute the union of two sets of versions.
     pub fn union(&self, other: &Self) -> Self {
+        // Fast paths avoiding the three negations.
+        if self.is_none() || other.is_any() {
+            return other.clone();
+        }
+        if other.is_none() || self.is_any() {
+            return self.clone();
+        }
         self.negate().intersection(&other.negate()).negate()
     }
 
+    /// Compute the union of two sets of versions, taking ownership of them.
+    /// This is the same as [union](Range::union),
+    /// except that trivial cases reuse one of the inputs instead of cloning it.
+    pub fn or(self, other: Self) -> Self {
+        if self.is_none() || other.is_any() {
+            return other;
+        }
+        if other.is_none() || self.is_any() {
+            return self;
+        }
+        self.union(&other)
+    }
+
+    /// Compute the intersection of two sets of versions, taking ownership of them.
+    /// This is the same as [intersection](Range::intersection),
+    /// except that trivial cases reuse one of the inputs instead of cloning it.
+    pub fn and(self, other: Self) -> Self {
+        if self.is_none() || other.is_any() {
+            return self;
+        }
+        if other.is_none() || self.is_any() {
+            return other;
+        }
+        self.intersection(&other)
+    }
+
+    /// Restrict this set of versions to the allowed `window`.
+    ///
+    /// This is the same as [intersection](Range::intersection),
+    /// named to convey the intent of applying a policy to a range.
+    pub fn clamp(&self, window: &Self) -> Self {
+        self.intersection(window)
+    }
+
+    /// Split this set of versions at a `pivot` version,
+    /// into the versions strictly lower than the pivot,
+    /// and the pivot with the versions higher than it.
+    /// The two halves are disjoint and their union is this set.
+    pub fn bisect(&self, pivot: &V) -> (Self, Self) {
+        (
+            self.intersection(&Self::strictly_lower_than(pivot.clone())),
+            self.intersection(&Self::higher_than(pivot.clone())),
+        )
+    }
+
     /// Compute the intersection of two sets of versions.
     pub fn intersection(&self, other: &Self) -> Self {
+        // Fast paths avoiding to rebuild segments.
+        if self.is_none() || other.is_any() {
+            return self.clone();
+        }
+        if other.is_none() || self.is_any() {
+            return other.clone();
+        }
         let mut segments = SmallVec::empty();
         let mut left_iter = self.segments.iter();
         let mut right_iter = other.segments.iter();
@@ -256,10 +456,570 @@ impl<V: Version> Range<V> {
         false
     }
 
+    /// Check if a range contains a given version, modulo version aliases.
+    /// When `eq` says that the version is an alias of a bound of the range,
+    /// like `1.0` for `1.0.0` in some ecosystems,
+    /// that bound is checked instead of the version itself.
+    /// Otherwise, this is the same as [contains](Range::contains).
+    pub fn contains_with(&self, version: &V, eq: impl Fn(&V, &V) -> bool) -> bool {
+        let alias = self
+            .segments
+            .iter()
+            .flat_map(|(start, end)| core::iter::once(start).chain(end))
+            .find(|bound| eq(version, bound));
+        self.contains(alias.unwrap_or(version))
+    }
+
+    /// Explain why a version is not in this range, naming the bounds around it,
+    /// or return [None] if the range contains it.
+    ///
+    /// ```
+    /// # use pubgrub::range::Range;
+    /// # use pubgrub::version::NumberVersion;
+    /// #
+    /// let range: Range<NumberVersion> = Range::between(1, 3).union(&Range::between(5, 7));
+    /// assert_eq!(range.explain_exclusion(&NumberVersion(2)), None);
+    /// assert_eq!(
+    ///     range.explain_exclusion(&NumberVersion(4)).unwrap(),
+    ///     "4 is in a gap of the range, from 3 to 5 excluded"
+    /// );
+    /// ```
+    pub fn explain_exclusion(&self, version: &V) -> Option<String> {
+        if self.contains(version) {
+            return None;
+        }
+        let mut previous_end = None;
+        for (start, end) in self.segments.iter() {
+            if version < start {
+                return Some(match previous_end {
+                    None => format!(
+                        "{} is lower than {}, the lowest allowed version",
+                        version, start
+                    ),
+                    Some(previous_end) => format!(
+                        "{} is in a gap of the range, from {} to {} excluded",
+                        version, previous_end, start
+                    ),
+                });
+            }
+            previous_end = end.as_ref();
+        }
+        Some(match previous_end {
+            Some(end) => format!(
+                "{} is not lower than {}, the excluded upper bound",
+                version, end
+            ),
+            None => format!("{} is not in the empty range", version),
+        })
+    }
+
+    /// Check if two ranges have no version in common,
+    /// without computing their intersection.
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        let mut left_iter = self.segments.iter();
+        let mut right_iter = other.segments.iter();
+        let mut left = left_iter.next();
+        let mut right = right_iter.next();
+        while let (Some((l1, l2)), Some((r1, r2))) = (left, right) {
+            let left_before = l2.as_ref().is_some_and(|l2| l2 <= r1);
+            let right_before = r2.as_ref().is_some_and(|r2| r2 <= l1);
+            if left_before {
+                left = left_iter.next();
+            } else if right_before {
+                right = right_iter.next();
+            } else {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Check if all versions of this range are in another range,
+    /// without computing any intermediate range.
+    pub fn subset_of(&self, other: &Self) -> bool {
+        let mut right_iter = other.segments.iter().peekable();
+        for (l1, l2) in &self.segments {
+            // Skip the intervals of other ending before this interval starts.
+            while right_iter
+                .next_if(|(_, r2)| r2.as_ref().is_some_and(|r2| r2 <= l1))
+                .is_some()
+            {}
+            // Since ranges are canonical, the interval must fit in a single one of other.
+            let contained = match right_iter.peek() {
+                None => false,
+                Some((r1, r2)) => {
+                    r1 <= l1
+                        && match (l2, r2) {
+                            (_, None) => true,
+                            (None, Some(_)) => false,
+                            (Some(l2), Some(r2)) => l2 <= r2,
+                        }
+                }
+            };
+            if !contained {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Number of disjoint intervals of this range,
+    /// 0 for the empty range and 1 for [any](Range::any).
+    /// Set operations are linear in the number of intervals,
+    /// so it is useful to reject overly complex constraints early.
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
     /// Return the lowest version in the range (if there is one).
     pub fn lowest_version(&self) -> Option<V> {
         self.segments.first().map(|(start, _)| start).cloned()
     }
+
+    /// Iterate over every version contained in this range, in increasing order,
+    /// stepping from the start of each interval with [Version::checked_bump].
+    /// The iterator is infinite if the range is unbounded above,
+    /// unless versions have a highest one.
+    ///
+    /// ```
+    /// # use pubgrub::range::Range;
+    /// # use pubgrub::version::NumberVersion;
+    /// #
+    /// let range: Range<NumberVersion> = Range::between(2, 5);
+    /// let versions: Vec<_> = range.iter_versions().map(|v| v.0).collect();
+    /// assert_eq!(versions, vec![2, 3, 4]);
+    /// ```
+    pub fn iter_versions(&self) -> impl Iterator<Item = V> + '_ {
+        self.segments.iter().flat_map(|(start, end)| {
+            core::iter::successors(Some(start.clone()), |v| v.checked_bump()).take_while(move |v| {
+                match end {
+                    Some(end) => v < end,
+                    None => true,
+                }
+            })
+        })
+    }
+
+    /// Smallest single interval containing the whole range,
+    /// from its lowest version to its upper bound.
+    /// For example, the hull of `[1, 2) ∪ [5, 7)` is `[1, 7)`.
+    pub fn hull(&self) -> Self {
+        match (self.segments.first(), self.segments.last()) {
+            (Some((start, _)), Some((_, end))) => Self {
+                segments: SmallVec::one((start.clone(), end.clone())),
+            },
+            _ => Self::none(),
+        }
+    }
+
+    /// Find the version of `all_versions` contained in this range
+    /// that is the closest to `target`.
+    /// The distance between two versions is the number of versions of `all_versions`
+    /// ordered between them, and ties are broken toward the higher version.
+    /// Returns [None] if no version of `all_versions` is contained in this range.
+    pub fn closest_to(&self, target: &V, all_versions: &[V]) -> Option<V> {
+        let mut versions: Vec<&V> = all_versions.iter().collect();
+        versions.sort();
+        versions.dedup();
+        // Ranks are doubled so that a missing target sits between two versions.
+        let split = versions.partition_point(|v| *v < target);
+        let target_rank = match versions.get(split) {
+            Some(v) if *v == target => 2 * split,
+            _ => (2 * split).saturating_sub(1),
+        };
+        let below = versions[..split]
+            .iter()
+            .rposition(|v| self.contains(v))
+            .map(|i| (target_rank - 2 * i, i));
+        let above = versions[split..]
+            .iter()
+            .position(|v| self.contains(v))
+            .map(|i| (2 * (split + i) - target_rank, split + i));
+        let closest = match (below, above) {
+            (Some((d_below, i_below)), Some((d_above, i_above))) => {
+                if d_below < d_above {
+                    i_below
+                } else {
+                    i_above
+                }
+            }
+            (Some((_, i)), None) | (None, Some((_, i))) => i,
+            (None, None) => return None,
+        };
+        Some(versions[closest].clone())
+    }
+
+    /// Keep only the versions of `all_versions` contained in this range
+    /// for which `keep` returns true.
+    /// This allows filtering on criteria that are not expressible as intervals.
+    /// The result is built with [from_versions](Range::from_versions),
+    /// so versions absent from `all_versions` are never part of it.
+    pub fn retain_versions(&self, all_versions: &[V], keep: impl Fn(&V) -> bool) -> Self {
+        Self::from_versions(
+            all_versions
+                .iter()
+                .filter(|v| self.contains(v) && keep(v))
+                .cloned(),
+        )
+    }
+
+    /// Intervals of this range without any of the versions of `all_versions`,
+    /// in increasing order.
+    /// They explain why a range can only be satisfied by versions that do not exist.
+    ///
+    /// ```
+    /// # use pubgrub::range::Range;
+    /// # use pubgrub::version::NumberVersion;
+    /// #
+    /// let range: Range<NumberVersion> = Range::higher_than(2);
+    /// let available = [NumberVersion(2), NumberVersion(4)];
+    /// assert_eq!(
+    ///     range.gaps(&available),
+    ///     vec![Range::exact(3), Range::higher_than(5)]
+    /// );
+    /// ```
+    pub fn gaps(&self, all_versions: &[V]) -> Vec<Self> {
+        let missing =
+            self.intersection(&Self::from_versions(all_versions.iter().cloned()).negate());
+        missing
+            .segments
+            .iter()
+            .map(|segment| Self {
+                segments: SmallVec::one(segment.clone()),
+            })
+            .collect()
+    }
+
+    /// Portion of this range covering only the versions of `all_versions`,
+    /// the complement of its [gaps](Range::gaps).
+    ///
+    /// ```
+    /// # use pubgrub::range::Range;
+    /// # use pubgrub::version::NumberVersion;
+    /// #
+    /// let range: Range<NumberVersion> = Range::between(1, 10);
+    /// let available = [NumberVersion(2), NumberVersion(5), NumberVersion(9)];
+    /// assert_eq!(
+    ///     range.shrink_to_available(&available),
+    ///     Range::exact(2).union(&Range::exact(5)).union(&Range::exact(9))
+    /// );
+    /// ```
+    pub fn shrink_to_available(&self, all_versions: &[V]) -> Self {
+        self.intersection(&Self::from_versions(all_versions.iter().cloned()))
+    }
+}
+
+impl<V: Version> Range<V> {
+    /// Apply a transformation to the bounds of every interval of this range.
+    /// The transformation must be strictly increasing,
+    /// such that the structure and ordering of intervals are preserved.
+    /// The lowest version is transformed like any other,
+    /// so a range unbounded below starts at the transformed lowest version.
+    /// Intervals that are not increasing after the transformation
+    /// are caught in debug builds.
+    pub fn map_versions<W: Version>(&self, f: impl Fn(&V) -> W) -> Range<W> {
+        let mut segments = SmallVec::empty();
+        for (start, end) in self.segments.iter() {
+            segments.push((f(start), end.as_ref().map(&f)));
+        }
+        let range = Range { segments };
+        debug_assert!(
+            range.is_increasing(),
+            "map_versions needs an increasing function"
+        );
+        range
+    }
+
+    /// Same as [map_versions](Range::map_versions) with a fallible transformation,
+    /// returning the first error encountered, in increasing order of bounds.
+    ///
+    /// ```
+    /// # use pubgrub::range::Range;
+    /// # use pubgrub::version::NumberVersion;
+    /// #
+    /// let ids: Range<NumberVersion> = Range::between(0, 2);
+    /// let versions = [10, 20, 30];
+    /// let lookup = |id: &NumberVersion| versions.get(id.0 as usize).copied().ok_or(id.0);
+    /// assert_eq!(
+    ///     ids.try_map_versions(|id| lookup(id).map(NumberVersion)),
+    ///     Ok(Range::between(10, 30))
+    /// );
+    /// ```
+    pub fn try_map_versions<W: Version, E>(
+        &self,
+        f: impl Fn(&V) -> Result<W, E>,
+    ) -> Result<Range<W>, E> {
+        let mut segments = SmallVec::empty();
+        for (start, end) in self.segments.iter() {
+            segments.push((f(start)?, end.as_ref().map(&f).transpose()?));
+        }
+        let range = Range { segments };
+        debug_assert!(
+            range.is_increasing(),
+            "try_map_versions needs an increasing function"
+        );
+        Ok(range)
+    }
+
+    /// Check that intervals are non-empty, ordered and disjoint.
+    fn is_increasing(&self) -> bool {
+        let segments = self.segments.as_slice();
+        segments.iter().all(|(start, end)| match end {
+            Some(end) => start < end,
+            None => true,
+        }) && segments
+            .windows(2)
+            .all(|pair| matches!(&pair[0].1, Some(end) if end < &pair[1].0))
+    }
+}
+
+impl Range<NumberVersion> {
+    /// Shift all the versions of this range by `offset`.
+    /// Versions shifted past the highest one are dropped,
+    /// so an interval ending past it becomes unbounded.
+    ///
+    /// ```
+    /// # use pubgrub::range::Range;
+    /// # use pubgrub::version::NumberVersion;
+    /// #
+    /// let range: Range<NumberVersion> = Range::between(1, 3);
+    /// assert_eq!(range.shift_by(1000), Range::between(1001, 1003));
+    /// ```
+    pub fn shift_by(&self, offset: u32) -> Self {
+        let mut segments = SmallVec::empty();
+        for (start, end) in self.segments.iter() {
+            let start = match start.0.checked_add(offset) {
+                Some(start) => NumberVersion(start),
+                None => break,
+            };
+            let end = end.and_then(|end| end.0.checked_add(offset).map(NumberVersion));
+            segments.push((start, end));
+        }
+        Self { segments }
+    }
+
+    /// [proptest](https://docs.rs/proptest) strategy generating ranges
+    /// of up to `max_segments` disjoint intervals,
+    /// with bounds no higher than `max_version`.
+    /// The last interval may be unbounded.
+    /// Only available with the `proptest` feature.
+    #[cfg(any(test, feature = "proptest"))]
+    pub fn arbitrary_with(
+        max_segments: usize,
+        max_version: u32,
+    ) -> impl proptest::strategy::Strategy<Value = Self> {
+        use proptest::prelude::*;
+        prop::collection::vec(0..=max_version, 0..=2 * max_segments).prop_map(|mut bounds| {
+            bounds.sort_unstable();
+            bounds.dedup();
+            let mut pair_iter = bounds.chunks_exact(2);
+            let mut segments = SmallVec::empty();
+            for pair in &mut pair_iter {
+                segments.push((NumberVersion(pair[0]), Some(NumberVersion(pair[1]))));
+            }
+            if let [v] = pair_iter.remainder() {
+                segments.push((NumberVersion(*v), None));
+            }
+            Self { segments }
+        })
+    }
+}
+
+impl Range<SemanticVersion> {
+    /// Versions from `start` up to `end` excluded,
+    /// or all versions from `start` if there is no `end`.
+    fn up_to(start: SemanticVersion, end: Option<SemanticVersion>) -> Self {
+        match end {
+            Some(end) => Self::between(start, end),
+            None => Self::higher_than(start),
+        }
+    }
+
+    /// Versions compatible with `v` following the caret rules of Cargo and npm,
+    /// where the leftmost non-zero component must not change:
+    /// `^1.2.3` is `[1.2.3, 2.0.0)`, `^0.2.3` is `[0.2.3, 0.3.0)`,
+    /// `^0.0.3` is `[0.0.3, 0.0.4)`, and `^0.0.0` is `[0.0.0, 0.0.1)`.
+    ///
+    /// ```
+    /// # use pubgrub::range::Range;
+    /// # use pubgrub::version::SemanticVersion;
+    /// #
+    /// let range = Range::<SemanticVersion>::compatible_with((0, 2, 3));
+    /// assert_eq!(range, Range::between((0, 2, 3), (0, 3, 0)));
+    /// ```
+    pub fn compatible_with(v: impl Into<SemanticVersion>) -> Self {
+        let v = v.into();
+        let (major, minor, _) = v.into();
+        let end = if major > 0 {
+            v.checked_bump_major()
+        } else if minor > 0 {
+            v.checked_bump_minor()
+        } else {
+            v.checked_bump()
+        };
+        Self::up_to(v, end)
+    }
+
+    /// Range of versions matching a partial version with a trailing wildcard,
+    /// following the semantics of npm and Cargo.
+    /// Missing components match any value, so `1.2.*` and `1.2` both mean `[1.2.0, 1.3.0)`,
+    /// `1.*` and `1` mean `[1.0.0, 2.0.0)`, and `*` matches any version.
+    /// A full version only matches itself.
+    ///
+    /// ```
+    /// # use pubgrub::range::Range;
+    /// # use pubgrub::version::SemanticVersion;
+    /// #
+    /// let range = Range::<SemanticVersion>::wildcard("1.2.*").unwrap();
+    /// assert_eq!(range, Range::between((1, 2, 0), (1, 3, 0)));
+    /// ```
+    pub fn wildcard(pattern: &str) -> Result<Self, VersionParseError> {
+        if pattern == "*" {
+            return Ok(Self::any());
+        }
+        let parse_u32 = |part: &str| {
+            part.parse::<u32>()
+                .map_err(|e| VersionParseError::ParseIntError {
+                    full_version: pattern.to_string(),
+                    version_part: part.to_string(),
+                    parse_error: e.to_string(),
+                })
+        };
+        let prefix = pattern.strip_suffix(".*").unwrap_or(pattern);
+        let parts = prefix
+            .split('.')
+            .map(parse_u32)
+            .collect::<Result<Vec<_>, _>>()?;
+        match parts[..] {
+            [major] => {
+                let start = SemanticVersion::new(major, 0, 0);
+                Ok(Self::up_to(start, start.checked_bump_major()))
+            }
+            [major, minor] => {
+                let start = SemanticVersion::new(major, minor, 0);
+                Ok(Self::up_to(start, start.checked_bump_minor()))
+            }
+            [major, minor, patch] => {
+                let version = SemanticVersion::new(major, minor, patch);
+                Ok(Self::up_to(version, version.checked_bump()))
+            }
+            _ => Err(VersionParseError::NotThreeParts {
+                full_version: pattern.to_string(),
+            }),
+        }
+    }
+}
+
+impl<V: Prerelease> Range<V> {
+    /// Check if a version is in this range and may be selected,
+    /// prereleases being opt-in.
+    /// A prerelease is only selectable if the lower bound of the interval containing it
+    /// is a prerelease of the same release,
+    /// so `v >= 1.0.0` does not select `1.1.0-beta`, but `v >= 1.1.0-beta` does.
+    pub fn contains_selectable(&self, version: &V) -> bool {
+        let selectable_from = |start: &V| {
+            !version.is_prerelease()
+                || (start.is_prerelease() && start.release() == version.release())
+        };
+        for (v1, maybe_v2) in &self.segments {
+            match maybe_v2 {
+                None => return v1 <= version && selectable_from(v1),
+                Some(v2) => {
+                    if version < v1 {
+                        return false;
+                    } else if version < v2 {
+                        return selectable_from(v1);
+                    }
+                }
+            }
+        }
+        false
+    }
+}
+
+impl Range<PrereleaseVersion> {
+    /// Stable versions of this range, without any of its prereleases.
+    /// Prereleases of a release sit just below it,
+    /// so each interval keeps the releases from the one of its start,
+    /// up to the one of its end excluded:
+    /// `>=1.0.0-alpha, <2.0.0` becomes `>=1.0.0, <2.0.0`,
+    /// and intervals made only of prereleases of the same release are removed.
+    pub fn trim_prereleases(&self) -> Range<SemanticVersion> {
+        self.segments
+            .iter()
+            .fold(Range::none(), |trimmed, (start, end)| {
+                let start = start.semantic_release();
+                let stable = match end {
+                    Some(end) => Range::between(start, end.semantic_release()),
+                    None => Range::higher_than(start),
+                };
+                trimmed.union(&stable)
+            })
+    }
+}
+
+impl<V: Version> VersionSet for Range<V> {
+    type V = V;
+
+    fn empty() -> Self {
+        Range::none()
+    }
+
+    fn singleton(v: Self::V) -> Self {
+        Range::exact(v)
+    }
+
+    fn complement(&self) -> Self {
+        Range::negate(self)
+    }
+
+    fn intersection(&self, other: &Self) -> Self {
+        Range::intersection(self, other)
+    }
+
+    fn contains(&self, v: &Self::V) -> bool {
+        Range::contains(self, v)
+    }
+
+    fn full() -> Self {
+        Range::any()
+    }
+
+    fn union(&self, other: &Self) -> Self {
+        Range::union(self, other)
+    }
+
+    fn is_disjoint(&self, other: &Self) -> bool {
+        Range::is_disjoint(self, other)
+    }
+
+    fn subset_of(&self, other: &Self) -> bool {
+        Range::subset_of(self, other)
+    }
+}
+
+// FUZZING #####################################################################
+
+/// Valid ranges made of any number of disjoint intervals,
+/// the last one being possibly unbounded.
+#[cfg(feature = "arbitrary")]
+impl<'a, V: Version + arbitrary::Arbitrary<'a>> arbitrary::Arbitrary<'a> for Range<V> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut bounds = (0..u.arbitrary_len::<V>()?)
+            .map(|_| u.arbitrary())
+            .collect::<arbitrary::Result<Vec<V>>>()?;
+        bounds.sort_unstable();
+        bounds.dedup();
+        let mut pair_iter = bounds.chunks_exact(2);
+        let mut segments = SmallVec::empty();
+        for pair in &mut pair_iter {
+            segments.push((pair[0].clone(), Some(pair[1].clone())));
+        }
+        if let [v] = pair_iter.remainder() {
+            segments.push((v.clone(), None));
+        }
+        Ok(Self { segments })
+    }
 }
 
 // REPORT ######################################################################
@@ -268,9 +1028,11 @@ impl<V: Version> fmt::Display for Range<V> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self.segments.as_slice() {
             [] => write!(f, "∅"),
-            [(start, None)] if start == &V::lowest() => write!(f, "∗"),
+            [(start, None)] if start == &V::lowest() => write!(f, "*"),
             [(start, None)] => write!(f, "{} <= v", start),
-            [(start, Some(end))] if end == &start.bump() => write!(f, "{}", start),
+            [(start, Some(end))] if start.checked_bump().as_ref() == Some(end) => {
+                write!(f, "{}", start)
+            }
             [(start, Some(end))] if start == &V::lowest() => write!(f, "v < {}", end),
             [(start, Some(end))] => write!(f, "{} <= v < {}", start, end),
             more_than_one_interval => {
@@ -278,23 +1040,128 @@ impl<V: Version> fmt::Display for Range<V> {
                     .iter()
                     .map(interval_to_string)
                     .collect();
-                write!(f, "{}", string_intervals.join("  "))
+                write!(f, "{}", string_intervals.join(" ∪ "))
             }
         }
     }
 }
 
+/// Compact interval notation, where single versions are displayed as is.
 fn interval_to_string<V: Version>((start, maybe_end): &Interval<V>) -> String {
     match maybe_end {
-        Some(end) => format!("[ {}, {} [", start, end),
-        None => format!("[ {}, ∞ [", start),
+        Some(end) if start.checked_bump().as_ref() == Some(end) => start.to_string(),
+        Some(end) => format!("[{}, {})", start, end),
+        None => format!("[{}, ∞)", start),
+    }
+}
+
+// PARSING #####################################################################
+
+/// Error parsing a [Range] with [Range::parse_with].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RangeParseError<E> {
+    /// A comparator does not start with `>=`, `>`, `<=`, `<` or `=`,
+    /// and is not the `*` wildcard.
+    InvalidComparator(String),
+    /// The version of a comparator could not be parsed.
+    InvalidVersion {
+        /// Comparator containing the version.
+        comparator: String,
+        /// Error returned by the version parser.
+        error: E,
+    },
+}
+
+impl<E: fmt::Display> fmt::Display for RangeParseError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidComparator(comparator) => {
+                write!(f, "invalid comparator '{}'", comparator)
+            }
+            Self::InvalidVersion { comparator, error } => {
+                write!(f, "invalid version in '{}': {}", comparator, error)
+            }
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> Error for RangeParseError<E> {}
+
+impl<V: Version> Range<V> {
+    /// Parse a constraint made of comparators `>=`, `>`, `<=`, `<` and `=`,
+    /// or the `*` wildcard,
+    /// combined with `,` for "and" and `||` for "or", "and" binding tighter.
+    /// Versions are parsed by `parse_version`,
+    /// so the grammar of ranges does not depend on the format of versions.
+    ///
+    /// ```
+    /// # use pubgrub::range::Range;
+    /// # use pubgrub::version::NumberVersion;
+    /// #
+    /// let range = Range::parse_with(">=1, <3 || =5", |v| v.parse::<NumberVersion>()).unwrap();
+    /// assert_eq!(range, Range::between(1, 3).union(&Range::exact(5)));
+    /// ```
+    pub fn parse_with<E>(
+        s: &str,
+        parse_version: impl Fn(&str) -> Result<V, E>,
+    ) -> Result<Self, RangeParseError<E>> {
+        let mut range = Self::none();
+        for alternative in s.split("||") {
+            let mut conjunction = Self::any();
+            for comparator in alternative.split(',') {
+                let comparator = comparator.trim();
+                if comparator == "*" {
+                    continue;
+                }
+                let (constructor, version): (fn(V) -> Self, _) =
+                    if let Some(v) = comparator.strip_prefix(">=") {
+                        (Self::higher_than, v)
+                    } else if let Some(v) = comparator.strip_prefix("<=") {
+                        (Self::lower_than, v)
+                    } else if let Some(v) = comparator.strip_prefix('>') {
+                        (Self::strictly_higher_than, v)
+                    } else if let Some(v) = comparator.strip_prefix('<') {
+                        (Self::strictly_lower_than, v)
+                    } else if let Some(v) = comparator.strip_prefix('=') {
+                        (Self::exact, v)
+                    } else {
+                        return Err(RangeParseError::InvalidComparator(comparator.to_string()));
+                    };
+                let version = parse_version(version.trim()).map_err(|error| {
+                    RangeParseError::InvalidVersion {
+                        comparator: comparator.to_string(),
+                        error,
+                    }
+                })?;
+                conjunction = conjunction.intersection(&constructor(version));
+            }
+            range = range.union(&conjunction);
+        }
+        Ok(range)
+    }
+}
+
+/// Parse a range with [Range::parse_with], using the [FromStr] implementation of versions.
+///
+/// ```
+/// # use pubgrub::range::Range;
+/// # use pubgrub::version::SemanticVersion;
+/// #
+/// let range: Range<SemanticVersion> = ">=1.0.0, <2.0.0".parse().unwrap();
+/// assert_eq!(range, Range::between((1, 0, 0), (2, 0, 0)));
+/// ```
+impl<V: Version + FromStr> FromStr for Range<V> {
+    type Err = RangeParseError<V::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(s, V::from_str)
     }
 }
 
 // TESTS #######################################################################
 
 #[cfg(test)]
-pub mod tests {
+pub(crate) mod tests {
     use proptest::prelude::*;
 
     use crate::version::NumberVersion;
@@ -302,29 +1169,652 @@ pub mod tests {
     use super::*;
 
     pub fn strategy() -> impl Strategy<Value = Range<NumberVersion>> {
-        prop::collection::vec(any::<u32>(), 0..10).prop_map(|mut vec| {
-            vec.sort_unstable();
-            vec.dedup();
-            let mut pair_iter = vec.chunks_exact(2);
-            let mut segments = SmallVec::empty();
-            while let Some([v1, v2]) = pair_iter.next() {
-                segments.push((NumberVersion(*v1), Some(NumberVersion(*v2))));
-            }
-            if let [v] = pair_iter.remainder() {
-                segments.push((NumberVersion(*v), None));
-            }
-            Range { segments }
-        })
+        Range::arbitrary_with(5, u32::MAX)
     }
 
     fn version_strat() -> impl Strategy<Value = NumberVersion> {
         any::<u32>().prop_map(NumberVersion)
     }
 
+    /// Ranges built with set operations on small versions, in a random order.
+    fn small_ops_strategy() -> impl Strategy<Value = Range<NumberVersion>> {
+        prop::collection::vec((0..4u8, 0..8u32, 0..8u32), 0..8).prop_map(|ops| {
+            ops.into_iter()
+                .fold(Range::none(), |range, (op, v1, v2)| match op {
+                    0 => range.union(&Range::exact(v1)),
+                    1 => range.union(&Range::between(v1, v2)),
+                    2 => range.intersection(&Range::higher_than(v1).negate()),
+                    _ => range.negate().union(&Range::strictly_lower_than(v2)),
+                })
+        })
+    }
+
+    /// Segments are sorted, non-empty, non-overlapping and non-adjacent.
+    fn is_canonical(range: &Range<NumberVersion>) -> bool {
+        let non_empty = range.segments.iter().all(|(start, end)| match end {
+            Some(end) => start < end,
+            None => true,
+        });
+        let separated = range.segments.windows(2).all(|pair| match &pair[0].1 {
+            Some(end) => end < &pair[1].0,
+            None => false,
+        });
+        non_empty && separated
+    }
+
+    #[test]
+    fn display() {
+        let display = |range: Range<NumberVersion>| range.to_string();
+        assert_eq!(display(Range::none()), "∅");
+        assert_eq!(display(Range::any()), "*");
+        assert_eq!(display(Range::exact(3)), "3");
+        assert_eq!(display(Range::higher_than(5)), "5 <= v");
+        assert_eq!(display(Range::strictly_lower_than(3)), "v < 3");
+        assert_eq!(display(Range::between(1, 3)), "1 <= v < 3");
+        assert_eq!(
+            display(Range::between(1, 3).union(&Range::higher_than(5))),
+            "[1, 3) ∪ [5, ∞)"
+        );
+        assert_eq!(
+            display(
+                Range::exact(0)
+                    .union(&Range::between(2, 4))
+                    .union(&Range::exact(7))
+            ),
+            "0 ∪ [2, 4) ∪ 7"
+        );
+    }
+
+    #[test]
+    fn clamp_to_window() {
+        let range: Range<NumberVersion> = Range::higher_than(1);
+        let window = Range::strictly_lower_than(5);
+        assert_eq!(range.clamp(&window), Range::between(1, 5));
+    }
+
+    #[test]
+    fn hull_of_segments() {
+        let range: Range<NumberVersion> = Range::between(1, 2).union(&Range::between(5, 7));
+        assert_eq!(range.hull(), Range::between(1, 7));
+        assert_eq!(range.hull().to_string(), "1 <= v < 7");
+        let single: Range<NumberVersion> = Range::between(3, 4);
+        assert_eq!(single.hull(), single);
+        let unbounded: Range<NumberVersion> = Range::exact(1).union(&Range::higher_than(5));
+        assert_eq!(unbounded.hull(), Range::higher_than(1));
+        assert_eq!(Range::<NumberVersion>::none().hull(), Range::none());
+    }
+
+    #[test]
+    fn retain_odd_versions() {
+        let all_versions: Vec<NumberVersion> = (0..10).map(NumberVersion).collect();
+        let range: Range<NumberVersion> = Range::between(1, 6);
+        let odd = range.retain_versions(&all_versions, |v| v.0 % 2 == 1);
+        assert_eq!(odd, Range::from_versions(vec![1, 3, 5]));
+        assert!(!odd.contains(&NumberVersion(2)));
+        assert!(!odd.contains(&NumberVersion(7)));
+        assert_eq!(range.retain_versions(&all_versions, |_| true), range);
+    }
+
+    #[test]
+    fn shift_preserves_segments() {
+        let range: Range<NumberVersion> = Range::between(1, 3);
+        assert_eq!(range.shift_by(1000), Range::between(1001, 1003));
+        let gapped = Range::between(1, 3).union(&Range::higher_than(5));
+        assert_eq!(
+            gapped.shift_by(10),
+            Range::between(11, 13).union(&Range::higher_than(15))
+        );
+        assert_eq!(Range::<NumberVersion>::none().shift_by(10), Range::none());
+
+        // Versions past the highest one are dropped.
+        let max = u32::MAX;
+        assert_eq!(
+            gapped.shift_by(max - 11),
+            Range::between(max - 10, max - 8).union(&Range::higher_than(max - 6))
+        );
+        assert_eq!(gapped.shift_by(max - 2), Range::higher_than(max - 1));
+        assert_eq!(gapped.shift_by(max), Range::none());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "map_versions needs an increasing function")]
+    fn map_versions_with_a_decreasing_function() {
+        let range: Range<NumberVersion> = Range::between(1, 3);
+        let _ = range.map_versions(|v| NumberVersion(10 - v.0));
+    }
+
+    #[test]
+    fn closest_allowed_version() {
+        let all_versions: Vec<NumberVersion> = (0..10).map(NumberVersion).collect();
+        let range: Range<NumberVersion> = Range::between(3, 6);
+        let closest = |target: u32| range.closest_to(&NumberVersion(target), &all_versions);
+        assert_eq!(closest(1), Some(NumberVersion(3)));
+        assert_eq!(closest(4), Some(NumberVersion(4)));
+        assert_eq!(closest(9), Some(NumberVersion(5)));
+        // Ties are broken toward the higher version.
+        let gapped = Range::exact(2).union(&Range::exact(6));
+        assert_eq!(
+            gapped.closest_to(&NumberVersion(4), &all_versions),
+            Some(NumberVersion(6))
+        );
+        // The target does not need to be an available version.
+        let sparse: Vec<NumberVersion> = vec![NumberVersion(1), NumberVersion(8)];
+        assert_eq!(
+            Range::higher_than(0).closest_to(&NumberVersion(5), &sparse),
+            Some(NumberVersion(8))
+        );
+        assert_eq!(
+            Range::none().closest_to(&NumberVersion(4), &all_versions),
+            None
+        );
+        assert_eq!(range.closest_to(&NumberVersion(4), &[]), None);
+    }
+
+    #[test]
+    fn interval_endpoints() {
+        let v = |n: u32| NumberVersion(n);
+        let closed = Range::interval(Bound::Included(v(1)), Bound::Included(v(3)));
+        assert!(closed.contains(&v(1)));
+        assert!(closed.contains(&v(3)));
+        assert!(!closed.contains(&v(4)));
+        let open = Range::interval(Bound::Excluded(v(1)), Bound::Excluded(v(3)));
+        assert!(!open.contains(&v(1)));
+        assert!(open.contains(&v(2)));
+        assert!(!open.contains(&v(3)));
+        assert_eq!(open, Range::exact(2));
+        assert_eq!(
+            Range::interval(Bound::Included(v(1)), Bound::Excluded(v(3))),
+            Range::between(1, 3)
+        );
+        assert_eq!(
+            Range::interval(Bound::Excluded(v(1)), Bound::Unbounded),
+            Range::strictly_higher_than(1)
+        );
+        assert_eq!(
+            Range::interval(Bound::Unbounded, Bound::Included(v(3))),
+            Range::lower_than(3)
+        );
+        assert_eq!(
+            Range::<NumberVersion>::interval(Bound::Unbounded, Bound::Unbounded),
+            Range::any()
+        );
+        assert_eq!(
+            Range::interval(Bound::Excluded(v(2)), Bound::Excluded(v(3))),
+            Range::none()
+        );
+    }
+
+    /// Versions made of any number of dot-separated parts,
+    /// where `1.0` and `1.0.0` are different versions.
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+    struct DottedVersion(Vec<u32>);
+
+    impl fmt::Display for DottedVersion {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let parts: Vec<_> = self.0.iter().map(|p| p.to_string()).collect();
+            write!(f, "{}", parts.join("."))
+        }
+    }
+
+    impl Version for DottedVersion {
+        fn lowest() -> Self {
+            Self(Vec::new())
+        }
+        fn bump(&self) -> Self {
+            let mut parts = self.0.clone();
+            parts.push(0);
+            Self(parts)
+        }
+    }
+
+    #[test]
+    fn contains_with_aliases() {
+        let v = |parts: &[u32]| DottedVersion(parts.to_vec());
+        let trailing_zeros = |v1: &DottedVersion, v2: &DottedVersion| {
+            let trim = |v: &DottedVersion| {
+                let mut parts = v.0.clone();
+                while parts.last() == Some(&0) {
+                    parts.pop();
+                }
+                parts
+            };
+            trim(v1) == trim(v2)
+        };
+        let exact = Range::exact(v(&[1, 0, 0]));
+        assert!(!exact.contains(&v(&[1, 0])));
+        assert!(!exact.contains_with(&v(&[1, 0]), |v1, v2| v1 == v2));
+        assert!(exact.contains_with(&v(&[1, 0]), trailing_zeros));
+
+        // 2.0 is lower than 2.0.0, but is the excluded upper bound with aliases.
+        let between = Range::between(v(&[1, 0, 0]), v(&[2, 0, 0]));
+        assert!(between.contains(&v(&[2, 0])));
+        assert!(!between.contains_with(&v(&[2, 0]), trailing_zeros));
+        assert!(between.contains_with(&v(&[1, 5]), trailing_zeros));
+    }
+
+    #[test]
+    fn gaps_between_available_versions() {
+        let available = [NumberVersion(2), NumberVersion(4)];
+        let range: Range<NumberVersion> = Range::between(2, 5);
+        assert_eq!(range.gaps(&available), vec![Range::exact(3)]);
+        let range: Range<NumberVersion> = Range::between(2, 8);
+        assert_eq!(
+            range.gaps(&available),
+            vec![Range::exact(3), Range::between(5, 8)]
+        );
+        assert!(Range::exact(4).gaps(&available).is_empty());
+        assert_eq!(Range::<NumberVersion>::any().gaps(&[]), vec![Range::any()]);
+    }
+
+    #[test]
+    fn parse_with_version_callback() {
+        let parse = |s| Range::parse_with(s, |v: &str| v.parse::<u32>().map(NumberVersion));
+        assert_eq!(parse(">=1, <3"), Ok(Range::between(1, 3)));
+        assert_eq!(parse(">1,<=3"), Ok(Range::between(2, 4)));
+        assert_eq!(
+            parse("=1 || >= 5"),
+            Ok(Range::exact(1).union(&Range::higher_than(5)))
+        );
+        assert_eq!(parse("*"), Ok(Range::any()));
+        assert_eq!(parse(">=3, <1"), Ok(Range::none()));
+        assert_eq!(
+            parse("~1"),
+            Err(RangeParseError::InvalidComparator("~1".to_string()))
+        );
+        assert!(matches!(
+            parse(">= one"),
+            Err(RangeParseError::InvalidVersion { comparator, .. }) if comparator == ">= one"
+        ));
+    }
+
+    #[test]
+    fn bounds_at_the_highest_version() {
+        let max = NumberVersion(u32::MAX);
+        assert_eq!(
+            Range::strictly_higher_than(max),
+            Range::<NumberVersion>::none()
+        );
+        assert_eq!(Range::lower_than(max), Range::<NumberVersion>::any());
+        assert!(Range::higher_than(max).contains(&max));
+        assert!(!Range::strictly_lower_than(max).contains(&max));
+
+        // The highest patch is followed by the next minor.
+        let max_patch = SemanticVersion::new(1, 2, u32::MAX);
+        assert_eq!(
+            Range::<SemanticVersion>::strictly_higher_than(max_patch),
+            Range::higher_than((1, 3, 0))
+        );
+        let max = SemanticVersion::new(u32::MAX, u32::MAX, u32::MAX);
+        assert_eq!(
+            Range::strictly_higher_than(max),
+            Range::<SemanticVersion>::none()
+        );
+        assert_eq!(Range::lower_than(max), Range::<SemanticVersion>::any());
+    }
+
+    #[test]
+    fn constructors_at_the_highest_version() {
+        let max = NumberVersion(u32::MAX);
+        assert_eq!(
+            Range::interval(Bound::Excluded(max), Bound::Unbounded),
+            Range::<NumberVersion>::none()
+        );
+        assert_eq!(
+            Range::interval(Bound::Included(NumberVersion(1)), Bound::Included(max)),
+            Range::higher_than(1)
+        );
+
+        let max = u32::MAX;
+        assert_eq!(
+            Range::wildcard("4294967295"),
+            Ok(Range::higher_than((max, 0, 0)))
+        );
+        assert_eq!(
+            Range::wildcard("4294967295.4294967295.*"),
+            Ok(Range::higher_than((max, max, 0)))
+        );
+        // The highest minor is followed by the next major.
+        assert_eq!(
+            Range::wildcard("1.4294967295"),
+            Ok(Range::between((1, max, 0), (2, 0, 0)))
+        );
+        assert_eq!(
+            Range::wildcard("4294967295.4294967295.4294967295"),
+            Ok(Range::higher_than((max, max, max)))
+        );
+
+        assert_eq!(
+            Range::compatible_with((max, 0, 0)),
+            Range::higher_than((max, 0, 0))
+        );
+        assert_eq!(
+            Range::compatible_with((0, max, 3)),
+            Range::between((0, max, 3), (1, 0, 0))
+        );
+        assert_eq!(
+            Range::compatible_with((0, 0, max)),
+            Range::between((0, 0, max), (0, 1, 0))
+        );
+    }
+
+    #[test]
+    fn display_at_the_highest_version() {
+        // The version following the highest patch is the next minor, for both bump methods.
+        let max_patch = SemanticVersion::new(0, 0, u32::MAX);
+        assert_eq!(max_patch.bump(), SemanticVersion::new(0, 1, 0));
+        assert_eq!(max_patch.checked_bump(), Some(max_patch.bump()));
+        let range = Range::<SemanticVersion>::compatible_with(max_patch);
+        assert_eq!(range.to_string(), "0.0.4294967295");
+        assert_eq!(
+            range.union(&Range::exact((1, 0, 0))).to_string(),
+            "0.0.4294967295 ∪ 1.0.0"
+        );
+
+        let max = SemanticVersion::new(u32::MAX, u32::MAX, u32::MAX);
+        assert_eq!(max.checked_bump(), None);
+        assert_eq!(
+            Range::<SemanticVersion>::higher_than(max).to_string(),
+            "4294967295.4294967295.4294967295 <= v"
+        );
+    }
+
+    #[test]
+    fn segment_count_of_ranges() {
+        assert_eq!(Range::<NumberVersion>::none().segment_count(), 0);
+        assert_eq!(Range::<NumberVersion>::any().segment_count(), 1);
+        assert_eq!(Range::<NumberVersion>::between(1, 3).segment_count(), 1);
+        let multi = Range::<NumberVersion>::exact(1)
+            .union(&Range::between(4, 6))
+            .union(&Range::higher_than(9));
+        assert_eq!(multi.segment_count(), 3);
+        // Adjacent intervals are merged.
+        let merged = Range::<NumberVersion>::between(1, 3).union(&Range::between(3, 5));
+        assert_eq!(merged.segment_count(), 1);
+    }
+
+    #[test]
+    fn caret_compatible_ranges() {
+        let caret = |v: (u32, u32, u32)| Range::<SemanticVersion>::compatible_with(v);
+        assert_eq!(caret((1, 2, 3)), Range::between((1, 2, 3), (2, 0, 0)));
+        assert_eq!(caret((0, 2, 3)), Range::between((0, 2, 3), (0, 3, 0)));
+        assert_eq!(caret((0, 0, 3)), Range::between((0, 0, 3), (0, 0, 4)));
+        assert_eq!(caret((0, 0, 0)), Range::between((0, 0, 0), (0, 0, 1)));
+    }
+
+    #[test]
+    fn iter_versions_of_ranges() {
+        let versions = |range: Range<NumberVersion>| -> Vec<u32> {
+            range.iter_versions().map(|v| v.0).collect()
+        };
+        assert_eq!(versions(Range::between(2, 5)), vec![2, 3, 4]);
+        assert_eq!(
+            versions(Range::between(2, 4).union(&Range::exact(7))),
+            vec![2, 3, 7]
+        );
+        assert!(versions(Range::none()).is_empty());
+        let unbounded: Range<NumberVersion> = Range::higher_than(8);
+        let first: Vec<_> = unbounded.iter_versions().take(3).map(|v| v.0).collect();
+        assert_eq!(first, vec![8, 9, 10]);
+        // Iteration stops at the highest version.
+        let highest = Range::higher_than(u32::MAX - 1);
+        assert_eq!(versions(highest), vec![u32::MAX - 1, u32::MAX]);
+    }
+
+    #[test]
+    fn try_map_versions_of_ranges() {
+        let range: Range<NumberVersion> = Range::between(1, 3).union(&Range::higher_than(5));
+        let times_ten = |v: &NumberVersion| Ok::<_, u32>(NumberVersion(v.0 * 10));
+        assert_eq!(
+            range.try_map_versions(times_ten),
+            Ok(Range::between(10, 30).union(&Range::higher_than(50)))
+        );
+        let fail_on_3 = |v: &NumberVersion| match v.0 {
+            3 => Err(v.0),
+            _ => Ok(NumberVersion(v.0 * 10)),
+        };
+        assert_eq!(range.try_map_versions(fail_on_3), Err(3));
+    }
+
+    #[test]
+    fn wildcard_ranges() {
+        let wildcard = |pattern| Range::<SemanticVersion>::wildcard(pattern);
+        assert_eq!(wildcard("1"), Ok(Range::between((1, 0, 0), (2, 0, 0))));
+        assert_eq!(wildcard("1.*"), Ok(Range::between((1, 0, 0), (2, 0, 0))));
+        assert_eq!(wildcard("1.2"), Ok(Range::between((1, 2, 0), (1, 3, 0))));
+        assert_eq!(wildcard("1.2.*"), Ok(Range::between((1, 2, 0), (1, 3, 0))));
+        assert_eq!(wildcard("1.2.3"), Ok(Range::exact((1, 2, 3))));
+        assert_eq!(wildcard("*"), Ok(Range::any()));
+        assert!(wildcard("1.*.3").is_err());
+        assert!(wildcard("1.2.3.4").is_err());
+        assert!(wildcard("").is_err());
+    }
+
+    #[test]
+    fn explain_exclusion_of_versions() {
+        let range: Range<NumberVersion> = Range::between(2, 4).union(&Range::between(6, 8));
+        assert_eq!(
+            range.explain_exclusion(&NumberVersion(1)).unwrap(),
+            "1 is lower than 2, the lowest allowed version"
+        );
+        assert_eq!(
+            range.explain_exclusion(&NumberVersion(4)).unwrap(),
+            "4 is in a gap of the range, from 4 to 6 excluded"
+        );
+        assert_eq!(
+            range.explain_exclusion(&NumberVersion(9)).unwrap(),
+            "9 is not lower than 8, the excluded upper bound"
+        );
+        assert_eq!(range.explain_exclusion(&NumberVersion(3)), None);
+        assert_eq!(
+            Range::none().explain_exclusion(&NumberVersion(0)).unwrap(),
+            "0 is not in the empty range"
+        );
+        assert_eq!(
+            Range::higher_than(2).explain_exclusion(&NumberVersion(5)),
+            None
+        );
+    }
+
+    #[test]
+    fn complement_within_bounds() {
+        let bounds: Range<NumberVersion> = Range::between(2, 8);
+        let range = Range::between(0, 3)
+            .union(&Range::exact(5))
+            .union(&Range::higher_than(7));
+        let complement = range.complement_within(&bounds);
+        assert_eq!(
+            complement,
+            Range::between(3, 5).union(&Range::between(6, 7))
+        );
+        assert!(complement.subset_of(&bounds));
+        assert!(complement.is_disjoint(&range));
+        assert_eq!(range.complement_within(&Range::any()), range.negate());
+        assert_eq!(bounds.complement_within(&bounds), Range::none());
+    }
+
+    #[test]
+    fn shrink_to_available_versions() {
+        let available: Vec<NumberVersion> = (0..12).step_by(3).map(NumberVersion).collect();
+        let range: Range<NumberVersion> = Range::between(1, 10);
+        let shrunk = range.shrink_to_available(&available);
+        for v in 0..15 {
+            let v = NumberVersion(v);
+            assert_eq!(
+                shrunk.contains(&v),
+                range.contains(&v) && available.contains(&v)
+            );
+        }
+        assert_eq!(
+            shrunk,
+            Range::exact(3)
+                .union(&Range::exact(6))
+                .union(&Range::exact(9))
+        );
+        assert_eq!(range.shrink_to_available(&[]), Range::none());
+    }
+
+    #[test]
+    fn bisect_inside_a_segment() {
+        let range: Range<NumberVersion> = Range::between(1, 3)
+            .union(&Range::between(5, 9))
+            .union(&Range::higher_than(12));
+        let (lower, higher) = range.bisect(&NumberVersion(7));
+        assert_eq!(lower, Range::between(1, 3).union(&Range::between(5, 7)));
+        assert_eq!(higher, Range::between(7, 9).union(&Range::higher_than(12)));
+        assert_eq!(lower.union(&higher), range);
+        assert!(lower.is_disjoint(&higher));
+    }
+
+    #[test]
+    fn from_versions_with_gap() {
+        let range: Range<NumberVersion> = Range::from_versions(vec![4, 1, 2]);
+        assert_eq!(range, Range::between(1, 3).union(&Range::exact(4)));
+        assert!(range.contains(&NumberVersion(1)));
+        assert!(range.contains(&NumberVersion(2)));
+        assert!(!range.contains(&NumberVersion(3)));
+        assert!(range.contains(&NumberVersion(4)));
+        assert_eq!(
+            Range::<NumberVersion>::from_versions(Vec::<u32>::new()),
+            Range::none()
+        );
+    }
+
+    #[test]
+    fn trim_prereleases_keeps_stable_versions() {
+        let pre = |v: &str| v.parse::<PrereleaseVersion>().unwrap();
+        let stable = |v: &str| v.parse::<SemanticVersion>().unwrap();
+        let range = Range::between(pre("1.0.0-alpha"), pre("2.0.0"));
+        let trimmed = range.trim_prereleases();
+        assert!(trimmed.contains(&stable("1.0.0")));
+        assert!(!trimmed.contains(&stable("2.0.0")));
+        assert_eq!(trimmed, Range::between(stable("1.0.0"), stable("2.0.0")));
+
+        // Prereleases in the middle of the range are removed too.
+        let beta = Range::exact(pre("1.5.0-beta"));
+        assert_eq!(range.intersection(&beta).trim_prereleases(), Range::none());
+        let around_prereleases = Range::between(pre("1.0.0"), pre("1.2.0"))
+            .union(&Range::between(pre("1.5.0-alpha"), pre("1.5.0-beta")))
+            .union(&Range::higher_than(pre("3.0.0-rc")));
+        assert_eq!(
+            around_prereleases.trim_prereleases(),
+            Range::between(stable("1.0.0"), stable("1.2.0"))
+                .union(&Range::higher_than(stable("3.0.0")))
+        );
+        assert!(!around_prereleases
+            .trim_prereleases()
+            .contains(&stable("1.5.0")));
+
+        // Excluding a prerelease does not split the stable versions around it.
+        assert_eq!(
+            range.intersection(&beta.negate()).trim_prereleases(),
+            trimmed
+        );
+    }
+
+    #[test]
+    fn from_versions_at_the_highest_version() {
+        let max = u32::MAX;
+        let range: Range<NumberVersion> = Range::from_versions(vec![max, 1, max - 1]);
+        assert_eq!(range, Range::exact(1).union(&Range::higher_than(max - 1)));
+        assert!(range.contains(&NumberVersion(max)));
+
+        let all_versions = [NumberVersion(1), NumberVersion(max)];
+        assert_eq!(
+            Range::any().gaps(&all_versions),
+            vec![Range::exact(0), Range::between(2, max)]
+        );
+        assert_eq!(
+            Range::higher_than(2).shrink_to_available(&all_versions),
+            Range::higher_than(max)
+        );
+        assert_eq!(
+            Range::any().retain_versions(&all_versions, |v| v.0 > 1),
+            Range::higher_than(max)
+        );
+    }
+
+    #[test]
+    fn normalize_equivalent_bounds() {
+        let strict: Range<NumberVersion> = Range::strictly_lower_than(4);
+        assert_eq!(strict.normalize(), Range::lower_than(3).normalize());
+        assert_eq!(strict.normalize(), strict);
+    }
+
+    #[test]
+    fn normalize_non_canonical_segments() {
+        let mut segments = SmallVec::empty();
+        segments.push((NumberVersion(5), None));
+        segments.push((NumberVersion(1), Some(NumberVersion(3))));
+        segments.push((NumberVersion(2), Some(NumberVersion(4))));
+        segments.push((NumberVersion(9), Some(NumberVersion(9))));
+        let range = Range { segments };
+        assert_eq!(
+            range.normalize(),
+            Range::between(1, 4).union(&Range::higher_than(5))
+        );
+    }
+
+    #[test]
+    fn equal_ranges_hash_equally() {
+        use std::collections::HashSet;
+        let mut set = HashSet::new();
+        set.insert(Range::between(1, 3));
+        set.insert(Range::exact(1).union(&Range::exact(2)));
+        set.insert(Range::higher_than(1).intersection(&Range::strictly_lower_than(3)));
+        assert_eq!(set.len(), 1);
+        set.insert(Range::<NumberVersion>::any());
+        set.insert(Range::higher_than(0));
+        set.insert(Range::none());
+        set.insert(Range::between(3, 1));
+        assert_eq!(set.len(), 3);
+    }
+
     proptest! {
 
         // Testing negate ----------------------------------
 
+        #[test]
+        fn is_disjoint_agrees_with_intersection(
+            r1 in Range::arbitrary_with(8, 100),
+            r2 in Range::arbitrary_with(8, 100),
+        ) {
+            assert_eq!(r1.is_disjoint(&r2), r1.intersection(&r2) == Range::none());
+        }
+
+        #[test]
+        fn subset_of_agrees_with_intersection(
+            r1 in Range::arbitrary_with(8, 100),
+            r2 in Range::arbitrary_with(8, 100),
+        ) {
+            assert_eq!(r1.subset_of(&r2), r1.intersection(&r2) == r1);
+            assert!(r1.subset_of(&r1.union(&r2)));
+        }
+
+        #[test]
+        fn bisect_halves_are_disjoint_and_complete(range in strategy(), pivot in version_strat()) {
+            let (lower, higher) = range.bisect(&pivot);
+            assert_eq!(lower.union(&higher), range);
+            assert_eq!(lower.intersection(&higher), Range::none());
+        }
+
+        #[test]
+        fn de_morgan_laws(
+            r1 in Range::arbitrary_with(8, 100),
+            r2 in Range::arbitrary_with(8, 100),
+        ) {
+            assert_eq!(r1.union(&r2).negate(), r1.negate().intersection(&r2.negate()));
+            assert_eq!(r1.intersection(&r2).negate(), r1.negate().union(&r2.negate()));
+        }
+
+        #[test]
+        fn complement_within_is_subset_and_disjoint(range in strategy(), bounds in strategy()) {
+            let complement = range.complement_within(&bounds);
+            assert!(complement.subset_of(&bounds));
+            assert!(complement.is_disjoint(&range));
+            assert_eq!(complement.union(&range.intersection(&bounds)), bounds);
+        }
+
         #[test]
         fn negate_is_different(range in strategy()) {
             assert_ne!(range.negate(), range);
@@ -377,13 +1867,53 @@ pub mod tests {
             assert_eq!(r1.intersection(&r2).contains(&version), r1.contains(&version) && r2.contains(&version));
         }
 
+        // Testing canonical representation --------------
+
+        #[test]
+        fn operations_keep_canonical_segments(r1 in small_ops_strategy(), r2 in small_ops_strategy()) {
+            assert!(is_canonical(&r1));
+            assert!(is_canonical(&r1.intersection(&r2)));
+            assert!(is_canonical(&r1.union(&r2)));
+            assert!(is_canonical(&r1.negate()));
+        }
+
+        #[test]
+        fn equality_is_set_equality(r1 in small_ops_strategy(), r2 in small_ops_strategy()) {
+            // Bounds are all lower than 10, so these versions cover every case.
+            let same_versions = (0..=10).all(|v| r1.contains(&NumberVersion(v)) == r2.contains(&NumberVersion(v)));
+            assert_eq!(r1 == r2, same_versions);
+        }
+
+        #[test]
+        fn union_order_does_not_matter(r1 in small_ops_strategy(), r2 in small_ops_strategy(), r3 in small_ops_strategy()) {
+            assert_eq!(r1.union(&r2.union(&r3)), r3.union(&r1).union(&r2));
+        }
+
         // Testing union -----------------------------------
 
+        #[test]
+        fn owning_operations_match_borrowing_ones(r1 in strategy(), r2 in strategy()) {
+            assert_eq!(r1.clone().and(r2.clone()), r1.intersection(&r2));
+            assert_eq!(r1.clone().or(r2.clone()), r1.union(&r2));
+        }
+
         #[test]
         fn union_of_complements_is_any(range in strategy()) {
             assert_eq!(range.negate().union(&range), Range::any());
         }
 
+        #[test]
+        fn union_with_none_is_identity(range in strategy()) {
+            assert_eq!(range.union(&Range::none()), range);
+            assert_eq!(Range::none().union(&range), range);
+        }
+
+        #[test]
+        fn union_with_any_is_any(range in strategy()) {
+            assert_eq!(range.union(&Range::any()), Range::any());
+            assert_eq!(Range::any().union(&range), Range::any());
+        }
+
         #[test]
         fn union_contains_either(r1 in strategy(), r2 in strategy(), version in version_strat()) {
             assert_eq!(r1.union(&r2).contains(&version), r1.contains(&version) || r2.contains(&version));
@@ -391,11 +1921,45 @@ pub mod tests {
 
         // Testing contains --------------------------------
 
+        #[test]
+        fn hull_contains_range(range in strategy()) {
+            let hull = range.hull();
+            assert_eq!(hull.intersection(&range), range);
+            assert!(hull.segments.len() <= 1);
+        }
+
         #[test]
         fn always_contains_exact(version in version_strat()) {
             assert!(Range::exact(version).contains(&version));
         }
 
+        #[test]
+        fn strictly_higher_than_excludes_bound(version in version_strat()) {
+            assert!(Range::higher_than(version).contains(&version));
+            assert!(!Range::strictly_higher_than(version).contains(&version));
+        }
+
+        #[test]
+        fn lower_than_includes_bound(version in version_strat()) {
+            assert!(Range::lower_than(version).contains(&version));
+            assert!(!Range::strictly_lower_than(version).contains(&version));
+        }
+
+        #[test]
+        fn from_versions_contains_exactly(versions in prop::collection::vec(0u32..20, 0..10), version in 0u32..20) {
+            let range = Range::<NumberVersion>::from_versions(versions.iter().copied());
+            assert_eq!(range.contains(&NumberVersion(version)), versions.contains(&version));
+            assert_eq!(range, versions.iter().fold(Range::none(), |r, v| r.union(&Range::exact(*v))));
+        }
+
+        #[test]
+        fn closed_interval_from_bounds(v1 in version_strat(), v2 in version_strat(), version in version_strat()) {
+            let closed = Range::higher_than(v1).intersection(&Range::lower_than(v2));
+            let open = Range::strictly_higher_than(v1).intersection(&Range::strictly_lower_than(v2));
+            assert_eq!(closed.contains(&version), v1 <= version && version <= v2);
+            assert_eq!(open.contains(&version), v1 < version && version < v2);
+        }
+
         #[test]
         fn contains_negation(range in strategy(), version in version_strat()) {
             assert_ne!(range.contains(&version), range.negate().contains(&version));
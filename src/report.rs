@@ -3,54 +3,80 @@
 //! Build a report as clear as possible as to why
 //! dependency solving failed.
 
-use std::fmt;
-use std::ops::{Deref, DerefMut};
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::{Deref, DerefMut};
 
 use crate::package::Package;
 use crate::range::Range;
 use crate::term::Term;
 use crate::type_aliases::Map;
 use crate::version::Version;
+use crate::version_set::VersionSet;
 
 /// Reporter trait.
-pub trait Reporter<P: Package, V: Version> {
+pub trait Reporter<P: Package, VS: VersionSet> {
     /// Output type of the report.
     type Output;
 
     /// Generate a report from the derivation tree
     /// describing the resolution failure.
-    fn report(derivation_tree: &DerivationTree<P, V>) -> Self::Output;
+    fn report(derivation_tree: &DerivationTree<P, VS>) -> Self::Output;
 }
 
 /// Derivation tree resulting in the impossibility
 /// to solve the dependencies of our root package.
 #[derive(Debug, Clone)]
-pub enum DerivationTree<P: Package, V: Version> {
+pub enum DerivationTree<P: Package, VS: VersionSet> {
     /// External incompatibility.
-    External(External<P, V>),
+    External(External<P, VS>),
     /// Incompatibility derived from two others.
-    Derived(Derived<P, V>),
+    Derived(Derived<P, VS>),
 }
 
 /// Incompatibilities that are not derived from others,
 /// they have their own reason.
-#[derive(Debug, Clone)]
-pub enum External<P: Package, V: Version> {
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "VS::V: serde::Serialize, VS: serde::Serialize, P: serde::Serialize",
+        deserialize = "VS::V: serde::Deserialize<'de>, VS: serde::Deserialize<'de>, P: serde::Deserialize<'de>"
+    ))
+)]
+pub enum External<P: Package, VS: VersionSet> {
     /// Initial incompatibility aiming at picking the root package for the first decision.
-    NotRoot(P, V),
+    NotRoot(P, VS::V),
     /// There are no versions in the given range for this package.
-    NoVersions(P, Range<V>),
+    NoVersions(P, VS),
     /// Dependencies of the package are unavailable for versions in that range.
-    UnavailableDependencies(P, Range<V>),
+    UnavailableDependencies(P, VS),
     /// Incompatibility coming from the dependencies of a given package.
-    FromDependencyOf(P, Range<V>, P, Range<V>),
+    FromDependencyOf(P, VS, P, VS),
+    /// Two packages in the given ranges that can never be selected together.
+    ForbiddenCombination(P, VS, P, VS),
+    /// The package can only be selected at that version.
+    Pinned(P, VS::V),
+    /// The package at that version forbids the selection of another package.
+    Forbidden(P, VS::V, P),
+    /// The package at that version depends on a package
+    /// only when another one is selected in the given range.
+    ConditionalDependency(P, VS::V, P, VS, P, VS),
+    /// The package at that version is in a lockstep group with another package,
+    /// which can only be selected at the same version.
+    Lockstep(P, VS::V, P),
 }
 
 /// Incompatibility derived from two others.
 #[derive(Debug, Clone)]
-pub struct Derived<P: Package, V: Version> {
+pub struct Derived<P: Package, VS: VersionSet> {
     /// Terms of the incompatibility.
-    pub terms: Map<P, Term<V>>,
+    pub terms: Map<P, Term<VS>>,
     /// Indicate if that incompatibility is present multiple times
     /// in the derivation tree.
     /// If that is the case, it has a unique id, provided in that option.
@@ -58,12 +84,12 @@ pub struct Derived<P: Package, V: Version> {
     /// and refer to the explanation for the other times.
     pub shared_id: Option<usize>,
     /// First cause.
-    pub cause1: Box<DerivationTree<P, V>>,
+    pub cause1: Box<DerivationTree<P, VS>>,
     /// Second cause.
-    pub cause2: Box<DerivationTree<P, V>>,
+    pub cause2: Box<DerivationTree<P, VS>>,
 }
 
-impl<P: Package, V: Version> DerivationTree<P, V> {
+impl<P: Package, VS: VersionSet> DerivationTree<P, VS> {
     /// Merge the [NoVersions](External::NoVersions) external incompatibilities
     /// with the other one they are matched with
     /// in a derived incompatibility.
@@ -100,7 +126,7 @@ impl<P: Package, V: Version> DerivationTree<P, V> {
         }
     }
 
-    fn merge_no_versions(self, package: P, range: Range<V>) -> Option<Self> {
+    fn merge_no_versions(self, package: P, range: VS) -> Option<Self> {
         match self {
             // TODO: take care of the Derived case.
             // Once done, we can remove the Option.
@@ -108,6 +134,21 @@ impl<P: Package, V: Version> DerivationTree<P, V> {
             DerivationTree::External(External::NotRoot(_, _)) => {
                 panic!("How did we end up with a NoVersions merged with a NotRoot?")
             }
+            // A pin cannot be expressed with a range of versions.
+            DerivationTree::External(External::Pinned(_, _)) => None,
+            DerivationTree::External(External::ConditionalDependency(..)) => None,
+            DerivationTree::External(External::Lockstep(..)) => None,
+            // Any version of the forbidden package is already forbidden,
+            // but the version of the package forbidding it cannot be widened.
+            DerivationTree::External(External::Forbidden(p, v, forbidden)) => {
+                if forbidden == package {
+                    Some(DerivationTree::External(External::Forbidden(
+                        p, v, forbidden,
+                    )))
+                } else {
+                    None
+                }
+            }
             DerivationTree::External(External::NoVersions(_, r)) => Some(DerivationTree::External(
                 External::NoVersions(package, range.union(&r)),
             )),
@@ -134,25 +175,162 @@ impl<P: Package, V: Version> DerivationTree<P, V> {
                     )))
                 }
             }
+            DerivationTree::External(External::ForbiddenCombination(p1, r1, p2, r2)) => {
+                if p1 == package {
+                    Some(DerivationTree::External(External::ForbiddenCombination(
+                        p1,
+                        r1.union(&range),
+                        p2,
+                        r2,
+                    )))
+                } else {
+                    Some(DerivationTree::External(External::ForbiddenCombination(
+                        p1,
+                        r1,
+                        p2,
+                        r2.union(&range),
+                    )))
+                }
+            }
+        }
+    }
+}
+
+impl<P: Package, V: Version> DerivationTree<P, Range<V>> {
+    /// Suggest relaxations of the dependencies of the root package
+    /// that would remove the conflicts of this derivation tree.
+    /// For every dependency of the root package participating in the conflict,
+    /// the suggested range is the union of the root requirement
+    /// with the incompatible requirements of other packages on that dependency.
+    /// This is best-effort: applying a suggestion may still lead to another conflict.
+    pub fn suggestions(&self) -> Vec<(P, Range<V>)> {
+        let mut externals = Vec::new();
+        self.collect_externals(&mut externals);
+        let root = match self.root() {
+            Some((root, _)) => root,
+            None => return Vec::new(),
+        };
+        let mut suggestions = Vec::new();
+        for external in externals.iter() {
+            let (dependency, root_range) = match external {
+                External::FromDependencyOf(package, _, dependency, range) if package == &root => {
+                    (dependency, range)
+                }
+                _ => continue,
+            };
+            let mut suggested = root_range.clone();
+            for other in externals.iter() {
+                if let External::FromDependencyOf(package, _, dep, range) = other {
+                    if package != &root
+                        && dep == dependency
+                        && range.intersection(root_range) == Range::none()
+                    {
+                        suggested = suggested.union(range);
+                    }
+                }
+            }
+            if &suggested != root_range {
+                suggestions.push((dependency.clone(), suggested));
+            }
+        }
+        suggestions
+    }
+
+    /// Root package and version of the resolution that failed,
+    /// the only package of the terminal incompatibility.
+    pub(crate) fn root(&self) -> Option<(P, V)> {
+        match self {
+            DerivationTree::External(External::NotRoot(package, version)) => {
+                Some((package.clone(), version.clone()))
+            }
+            DerivationTree::External(_) => None,
+            DerivationTree::Derived(derived) => match derived.terms.iter().next() {
+                Some((package, Term::Positive(range))) if derived.terms.len() == 1 => {
+                    Some((package.clone(), range.lowest_version()?))
+                }
+                _ => None,
+            },
+        }
+    }
+}
+
+impl<P: Package, VS: VersionSet> DerivationTree<P, VS> {
+    /// Collect the distinct external incompatibilities of this derivation tree.
+    pub(crate) fn collect_externals(&self, externals: &mut Vec<External<P, VS>>) {
+        match self {
+            DerivationTree::External(external) => {
+                if !externals.contains(external) {
+                    externals.push(external.clone());
+                }
+            }
+            DerivationTree::Derived(derived) => {
+                derived.cause1.collect_externals(externals);
+                derived.cause2.collect_externals(externals);
+            }
+        }
+    }
+
+    /// Replace every package of this derivation tree.
+    pub(crate) fn map_packages<Q: Package>(
+        self,
+        f: &mut impl FnMut(P) -> Q,
+    ) -> DerivationTree<Q, VS> {
+        match self {
+            DerivationTree::External(external) => {
+                DerivationTree::External(external.map_packages(f))
+            }
+            DerivationTree::Derived(derived) => DerivationTree::Derived(Derived {
+                terms: derived
+                    .terms
+                    .into_iter()
+                    .map(|(p, term)| (f(p), term))
+                    .collect(),
+                shared_id: derived.shared_id,
+                cause1: Box::new(derived.cause1.map_packages(f)),
+                cause2: Box::new(derived.cause2.map_packages(f)),
+            }),
+        }
+    }
+}
+
+impl<P: Package, VS: VersionSet> External<P, VS> {
+    /// Replace every package of this external incompatibility.
+    pub(crate) fn map_packages<Q: Package>(self, f: &mut impl FnMut(P) -> Q) -> External<Q, VS> {
+        match self {
+            Self::NotRoot(p, v) => External::NotRoot(f(p), v),
+            Self::NoVersions(p, r) => External::NoVersions(f(p), r),
+            Self::UnavailableDependencies(p, r) => External::UnavailableDependencies(f(p), r),
+            Self::FromDependencyOf(p1, r1, p2, r2) => {
+                External::FromDependencyOf(f(p1), r1, f(p2), r2)
+            }
+            Self::ForbiddenCombination(p1, r1, p2, r2) => {
+                External::ForbiddenCombination(f(p1), r1, f(p2), r2)
+            }
+            Self::Pinned(p, v) => External::Pinned(f(p), v),
+            Self::Forbidden(p, v, forbidden) => External::Forbidden(f(p), v, f(forbidden)),
+            Self::ConditionalDependency(p, v, condition, range, dep, dep_range) => {
+                External::ConditionalDependency(f(p), v, f(condition), range, f(dep), dep_range)
+            }
+            Self::Lockstep(p, v, other) => External::Lockstep(f(p), v, f(other)),
         }
     }
 }
 
-impl<P: Package, V: Version> fmt::Display for External<P, V> {
+impl<P: Package, VS: VersionSet> fmt::Display for External<P, VS> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::NotRoot(package, version) => {
                 write!(f, "we are solving dependencies of {} {}", package, version)
             }
             Self::NoVersions(package, range) => {
-                if range == &Range::any() {
+                if range == &VS::full() {
                     write!(f, "there is no available version for {}", package)
                 } else {
                     write!(f, "there is no version of {} in {}", package, range)
                 }
             }
             Self::UnavailableDependencies(package, range) => {
-                if range == &Range::any() {
+                if range == &VS::full() {
                     write!(f, "dependencies of {} are unavailable", package)
                 } else {
                     write!(
@@ -163,16 +341,163 @@ impl<P: Package, V: Version> fmt::Display for External<P, V> {
                 }
             }
             Self::FromDependencyOf(p, range_p, dep, range_dep) => {
-                if range_p == &Range::any() && range_dep == &Range::any() {
+                if range_p == &VS::full() && range_dep == &VS::full() {
                     write!(f, "{} depends on {}", p, dep)
-                } else if range_p == &Range::any() {
+                } else if range_p == &VS::full() {
                     write!(f, "{} depends on {} {}", p, dep, range_dep)
-                } else if range_dep == &Range::any() {
+                } else if range_dep == &VS::full() {
                     write!(f, "{} {} depends on {}", p, range_p, dep)
                 } else {
                     write!(f, "{} {} depends on {} {}", p, range_p, dep, range_dep)
                 }
             }
+            Self::ForbiddenCombination(p1, range1, p2, range2) => {
+                let describe = |p: &P, range: &VS| {
+                    if range == &VS::full() {
+                        p.to_string()
+                    } else {
+                        format!("{} {}", p, range)
+                    }
+                };
+                write!(
+                    f,
+                    "{} is incompatible with {}",
+                    describe(p1, range1),
+                    describe(p2, range2)
+                )
+            }
+            Self::Pinned(package, version) => {
+                write!(f, "{} is pinned to {}", package, version)
+            }
+            Self::Forbidden(package, version, forbidden) => {
+                write!(f, "{} {} forbids {}", package, version, forbidden)
+            }
+            Self::ConditionalDependency(p, v, condition, range, dep, dep_range) => {
+                let describe = |p: &P, range: &VS| {
+                    if range == &VS::full() {
+                        p.to_string()
+                    } else {
+                        format!("{} {}", p, range)
+                    }
+                };
+                write!(
+                    f,
+                    "{} {} depends on {} when {} is selected",
+                    p,
+                    v,
+                    describe(dep, dep_range),
+                    describe(condition, range)
+                )
+            }
+            Self::Lockstep(package, version, other) => {
+                write!(
+                    f,
+                    "{} {} is in lockstep with {}, which must also be selected at {}",
+                    package, version, other, version
+                )
+            }
+        }
+    }
+}
+
+// SERIALIZATION ###############################################################
+
+/// The derivation tree is serialized as a flat list of incompatibilities,
+/// where derived incompatibilities refer to their causes by id,
+/// the id of an incompatibility being its index in that list.
+/// Shared derivations and identical external incompatibilities
+/// are only serialized once, to avoid an exponential blowup.
+///
+/// ```json
+/// {
+///   "root": 2,
+///   "incompatibilities": [
+///     { "id": 0, "kind": "external", "external": { "NotRoot": ["root", 1] } },
+///     { "id": 1, "kind": "external", "external": { "NoVersions": ["a", ...] } },
+///     { "id": 2, "kind": "derived", "terms": { ... }, "cause1": 0, "cause2": 1 }
+///   ]
+/// }
+/// ```
+#[cfg(feature = "serde")]
+impl<P, VS> serde::Serialize for DerivationTree<P, VS>
+where
+    P: Package + serde::Serialize,
+    VS: VersionSet + serde::Serialize,
+    VS::V: serde::Serialize,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut incompatibilities = Vec::new();
+        let mut shared_ids = Map::default();
+        let root = flatten(self, &mut incompatibilities, &mut shared_ids);
+        FlatDerivationTree {
+            root,
+            incompatibilities,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+#[serde(bound(serialize = "P: serde::Serialize, VS: serde::Serialize, VS::V: serde::Serialize"))]
+struct FlatDerivationTree<'a, P: Package, VS: VersionSet> {
+    root: usize,
+    incompatibilities: Vec<FlatIncompatibility<'a, P, VS>>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[serde(bound(serialize = "P: serde::Serialize, VS: serde::Serialize, VS::V: serde::Serialize"))]
+enum FlatIncompatibility<'a, P: Package, VS: VersionSet> {
+    External {
+        id: usize,
+        external: &'a External<P, VS>,
+    },
+    Derived {
+        id: usize,
+        terms: &'a Map<P, Term<VS>>,
+        cause1: usize,
+        cause2: usize,
+    },
+}
+
+/// Push the incompatibilities of a derivation tree, causes first,
+/// and return the id of its root incompatibility.
+#[cfg(feature = "serde")]
+fn flatten<'a, P: Package, VS: VersionSet>(
+    tree: &'a DerivationTree<P, VS>,
+    incompatibilities: &mut Vec<FlatIncompatibility<'a, P, VS>>,
+    shared_ids: &mut Map<usize, usize>,
+) -> usize {
+    match tree {
+        DerivationTree::External(external) => {
+            let existing = incompatibilities.iter().position(|incompat| {
+                matches!(incompat, FlatIncompatibility::External { external: e, .. } if *e == external)
+            });
+            existing.unwrap_or_else(|| {
+                let id = incompatibilities.len();
+                incompatibilities.push(FlatIncompatibility::External { id, external });
+                id
+            })
+        }
+        DerivationTree::Derived(derived) => {
+            if let Some(id) = derived.shared_id.and_then(|s| shared_ids.get(&s)) {
+                return *id;
+            }
+            let cause1 = flatten(&derived.cause1, incompatibilities, shared_ids);
+            let cause2 = flatten(&derived.cause2, incompatibilities, shared_ids);
+            let id = incompatibilities.len();
+            incompatibilities.push(FlatIncompatibility::Derived {
+                id,
+                terms: &derived.terms,
+                cause1,
+                cause2,
+            });
+            if let Some(shared_id) = derived.shared_id {
+                shared_ids.insert(shared_id, id);
+            }
+            id
         }
     }
 }
@@ -198,17 +523,17 @@ impl DefaultStringReporter {
         }
     }
 
-    fn build_recursive<P: Package, V: Version>(&mut self, derived: &Derived<P, V>) {
+    fn build_recursive<P: Package, VS: VersionSet>(&mut self, derived: &Derived<P, VS>) {
         self.build_recursive_helper(derived);
         if let Some(id) = derived.shared_id {
-            if self.shared_with_ref.get(&id) == None {
+            if !self.shared_with_ref.contains_key(&id) {
                 self.add_line_ref();
                 self.shared_with_ref.insert(id, self.ref_count);
             }
         };
     }
 
-    fn build_recursive_helper<P: Package, V: Version>(&mut self, current: &Derived<P, V>) {
+    fn build_recursive_helper<P: Package, VS: VersionSet>(&mut self, current: &Derived<P, VS>) {
         match (current.cause1.deref(), current.cause2.deref()) {
             (DerivationTree::External(external1), DerivationTree::External(external2)) => {
                 // Simplest case, we just combine two external incompatibilities.
@@ -264,7 +589,7 @@ impl DefaultStringReporter {
                     //     and finally conclude.
                     (None, None) => {
                         self.build_recursive(derived1);
-                        if derived1.shared_id != None {
+                        if derived1.shared_id.is_some() {
                             self.lines.push("".into());
                             self.build_recursive(current);
                         } else {
@@ -285,11 +610,11 @@ impl DefaultStringReporter {
     ///
     /// The result will depend on the fact that the derived incompatibility
     /// has already been explained or not.
-    fn report_one_each<P: Package, V: Version>(
+    fn report_one_each<P: Package, VS: VersionSet>(
         &mut self,
-        derived: &Derived<P, V>,
-        external: &External<P, V>,
-        current_terms: &Map<P, Term<V>>,
+        derived: &Derived<P, VS>,
+        external: &External<P, VS>,
+        current_terms: &Map<P, Term<VS>>,
     ) {
         match self.line_ref_of(derived.shared_id) {
             Some(ref_id) => self.lines.push(Self::explain_ref_and_external(
@@ -303,11 +628,11 @@ impl DefaultStringReporter {
     }
 
     /// Report one derived (without a line ref yet) and one external.
-    fn report_recurse_one_each<P: Package, V: Version>(
+    fn report_recurse_one_each<P: Package, VS: VersionSet>(
         &mut self,
-        derived: &Derived<P, V>,
-        external: &External<P, V>,
-        current_terms: &Map<P, Term<V>>,
+        derived: &Derived<P, VS>,
+        external: &External<P, VS>,
+        current_terms: &Map<P, Term<VS>>,
     ) {
         match (derived.cause1.deref(), derived.cause2.deref()) {
             // If the derived cause has itself one external prior cause,
@@ -341,10 +666,10 @@ impl DefaultStringReporter {
     // String explanations #####################################################
 
     /// Simplest case, we just combine two external incompatibilities.
-    fn explain_both_external<P: Package, V: Version>(
-        external1: &External<P, V>,
-        external2: &External<P, V>,
-        current_terms: &Map<P, Term<V>>,
+    fn explain_both_external<P: Package, VS: VersionSet>(
+        external1: &External<P, VS>,
+        external2: &External<P, VS>,
+        current_terms: &Map<P, Term<VS>>,
     ) -> String {
         // TODO: order should be chosen to make it more logical.
         format!(
@@ -356,12 +681,12 @@ impl DefaultStringReporter {
     }
 
     /// Both causes have already been explained so we use their refs.
-    fn explain_both_ref<P: Package, V: Version>(
+    fn explain_both_ref<P: Package, VS: VersionSet>(
         ref_id1: usize,
-        derived1: &Derived<P, V>,
+        derived1: &Derived<P, VS>,
         ref_id2: usize,
-        derived2: &Derived<P, V>,
-        current_terms: &Map<P, Term<V>>,
+        derived2: &Derived<P, VS>,
+        current_terms: &Map<P, Term<VS>>,
     ) -> String {
         // TODO: order should be chosen to make it more logical.
         format!(
@@ -377,11 +702,11 @@ impl DefaultStringReporter {
     /// One cause is derived (already explained so one-line),
     /// the other is a one-line external cause,
     /// and finally we conclude with the current incompatibility.
-    fn explain_ref_and_external<P: Package, V: Version>(
+    fn explain_ref_and_external<P: Package, VS: VersionSet>(
         ref_id: usize,
-        derived: &Derived<P, V>,
-        external: &External<P, V>,
-        current_terms: &Map<P, Term<V>>,
+        derived: &Derived<P, VS>,
+        external: &External<P, VS>,
+        current_terms: &Map<P, Term<VS>>,
     ) -> String {
         // TODO: order should be chosen to make it more logical.
         format!(
@@ -394,9 +719,9 @@ impl DefaultStringReporter {
     }
 
     /// Add an external cause to the chain of explanations.
-    fn and_explain_external<P: Package, V: Version>(
-        external: &External<P, V>,
-        current_terms: &Map<P, Term<V>>,
+    fn and_explain_external<P: Package, VS: VersionSet>(
+        external: &External<P, VS>,
+        current_terms: &Map<P, Term<VS>>,
     ) -> String {
         format!(
             "And because {}, {}.",
@@ -406,10 +731,10 @@ impl DefaultStringReporter {
     }
 
     /// Add an already explained incompat to the chain of explanations.
-    fn and_explain_ref<P: Package, V: Version>(
+    fn and_explain_ref<P: Package, VS: VersionSet>(
         ref_id: usize,
-        derived: &Derived<P, V>,
-        current_terms: &Map<P, Term<V>>,
+        derived: &Derived<P, VS>,
+        current_terms: &Map<P, Term<VS>>,
     ) -> String {
         format!(
             "And because {} ({}), {}.",
@@ -420,10 +745,10 @@ impl DefaultStringReporter {
     }
 
     /// Add an already explained incompat to the chain of explanations.
-    fn and_explain_prior_and_external<P: Package, V: Version>(
-        prior_external: &External<P, V>,
-        external: &External<P, V>,
-        current_terms: &Map<P, Term<V>>,
+    fn and_explain_prior_and_external<P: Package, VS: VersionSet>(
+        prior_external: &External<P, VS>,
+        external: &External<P, VS>,
+        current_terms: &Map<P, Term<VS>>,
     ) -> String {
         format!(
             "And because {} and {}, {}.",
@@ -434,7 +759,7 @@ impl DefaultStringReporter {
     }
 
     /// Try to print terms of an incompatibility in a human-readable way.
-    pub fn string_terms<P: Package, V: Version>(terms: &Map<P, Term<V>>) -> String {
+    pub fn string_terms<P: Package, VS: VersionSet>(terms: &Map<P, Term<VS>>) -> String {
         let terms_vec: Vec<_> = terms.iter().collect();
         match terms_vec.as_slice() {
             [] => "version solving failed".into(),
@@ -469,10 +794,10 @@ impl DefaultStringReporter {
     }
 }
 
-impl<P: Package, V: Version> Reporter<P, V> for DefaultStringReporter {
+impl<P: Package, VS: VersionSet> Reporter<P, VS> for DefaultStringReporter {
     type Output = String;
 
-    fn report(derivation_tree: &DerivationTree<P, V>) -> Self::Output {
+    fn report(derivation_tree: &DerivationTree<P, VS>) -> Self::Output {
         match derivation_tree {
             DerivationTree::External(external) => external.to_string(),
             DerivationTree::Derived(derived) => {
@@ -29,7 +29,7 @@
 //! as long as packages (P) and versions (V) implement
 //! the [Package](crate::package::Package) and [Version](crate::version::Version) traits.
 //! [Package](crate::package::Package) is strictly equivalent and automatically generated
-//! for any type that implement [Clone] + [Eq] + [Hash] + [Debug] + [Display](std::fmt::Display).
+//! for any type that implement [Clone] + [Eq] + [Hash] + [Debug] + [Display](core::fmt::Display).
 //! [Version](crate::version::Version) simply states that versions are ordered,
 //! that there should be
 //! a minimal [lowest](crate::version::Version::lowest) version (like 0.0.0 in semantic versions),
@@ -37,6 +37,9 @@
 //! what the next version closest to this one is ([bump](crate::version::Version::bump)).
 //! For semantic versions, [bump](crate::version::Version::bump) corresponds to
 //! an increment of the patch number.
+//! The solver manipulates sets of versions through the
+//! [VersionSet](crate::version_set::VersionSet) trait,
+//! implemented by [Range](crate::range::Range) for any such version type.
 //!
 //! ## API
 //!
@@ -44,16 +47,19 @@
 //! # use pubgrub::solver::{resolve, OfflineDependencyProvider};
 //! # use pubgrub::version::NumberVersion;
 //! # use pubgrub::error::PubGrubError;
+//! # use pubgrub::range::Range;
 //! #
-//! # fn try_main() -> Result<(), PubGrubError<&'static str, NumberVersion>> {
-//! #     let dependency_provider = OfflineDependencyProvider::<&str, NumberVersion>::new();
+//! # type NumVS = Range<NumberVersion>;
+//! #
+//! # fn try_main() -> Result<(), PubGrubError<&'static str, NumVS>> {
+//! #     let dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
 //! #     let package = "root";
 //! #     let version = 1;
 //! let solution = resolve(&dependency_provider, package, version)?;
 //! #     Ok(())
 //! # }
 //! # fn main() {
-//! #     assert!(matches!(try_main(), Err(PubGrubError::NoSolution(_))));
+//! #     assert!(matches!(try_main(), Err(PubGrubError::RootVersionNotAvailable { .. })));
 //! # }
 //! ```
 //!
@@ -65,162 +71,2022 @@
 //! to satisfy the dependencies of that package and version pair.
 //! If there is no solution, the reason will be provided as clear as possible.
 
-use std::borrow::Borrow;
-use std::collections::{BTreeMap, BTreeSet as Set};
-use std::error::Error;
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet as Set, VecDeque};
+use alloc::format;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cell::{Cell, RefCell};
+use core::str::FromStr;
 
-use crate::error::PubGrubError;
+use crate::assignment::Assignment;
+use crate::error::{Error, PubGrubError};
+use crate::incompatibility::Incompatibility;
+use crate::internal::arena::Arena;
 use crate::internal::core::State;
-use crate::internal::incompatibility::Incompatibility;
+use crate::internal::partial_solution::PartialSolution;
 use crate::package::Package;
 use crate::range::Range;
-use crate::type_aliases::{Map, SelectedDependencies};
+use crate::report::{DerivationTree, External};
+use crate::term::Term;
+use crate::type_aliases::{Dependents, Map, SelectedDependencies};
 use crate::version::Version;
+use crate::version_set::VersionSet;
+
+/// Main function of the library.
+/// Finds a set of packages satisfying dependency bounds for a given package + version pair.
+pub fn resolve<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<P, VS>,
+    package: P,
+    version: impl Into<VS::V>,
+) -> Result<SelectedDependencies<P, VS::V>, PubGrubError<P, VS>> {
+    resolve_with_incompatibilities(
+        dependency_provider,
+        package,
+        version.into(),
+        Vec::new(),
+        &PreviousSatisfierLevel,
+        &mut Recording::new(),
+    )
+}
+
+/// Check if there is a solution to the dependencies of a given package + version pair.
+/// This agrees exactly with [resolve] returning `Ok`,
+/// and fails with the same errors, except for [PubGrubError::NoSolution].
+/// It is faster than [resolve] since neither the solution
+/// nor the derivation tree explaining a failure are built.
+pub fn is_satisfiable<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<P, VS>,
+    package: P,
+    version: impl Into<VS::V>,
+) -> Result<bool, PubGrubError<P, VS>> {
+    let mut recording = Recording::new();
+    recording.check_only = true;
+    let result = resolve_with_incompatibilities(
+        dependency_provider,
+        package,
+        version.into(),
+        Vec::new(),
+        &PreviousSatisfierLevel,
+        &mut recording,
+    );
+    match result {
+        Ok(_) => Ok(true),
+        Err(PubGrubError::NoSolution(_)) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// Same as [resolve], except that the packages in `pins`, typically coming from a lock file,
+/// can only be selected at their pinned version.
+/// Pinned packages are not forced into the solution if nothing depends on them.
+/// Pins are never backtracked, and a pin incompatible with the dependencies
+/// is reported with [External::Pinned](crate::report::External::Pinned).
+pub fn resolve_with_pins<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<P, VS>,
+    package: P,
+    version: impl Into<VS::V>,
+    pins: &Map<P, VS::V>,
+) -> Result<SelectedDependencies<P, VS::V>, PubGrubError<P, VS>> {
+    let pins = pins
+        .iter()
+        .map(|(p, v)| Incompatibility::pinned(p.clone(), v.clone()))
+        .collect();
+    resolve_with_incompatibilities(
+        dependency_provider,
+        package,
+        version.into(),
+        pins,
+        &PreviousSatisfierLevel,
+        &mut Recording::new(),
+    )
+}
+
+/// Check that a lock, typically read from a lock file, is still a valid solution
+/// for the dependencies of a given package + version pair, without searching for another one.
+/// Starting from the root package at `version`, whatever its entry in the lock,
+/// the dependencies of every locked package reached must be locked at a version in range,
+/// including the [development dependencies](DependencyProvider::get_dev_dependencies)
+/// of the root package, like with [resolve].
+/// [Virtual packages](DependencyProvider::is_virtual), absent from locks,
+/// are satisfied by a version whose dependencies are all locked in range.
+/// The first violation is reported with [PubGrubError::LockViolation],
+/// and package versions with unknown dependencies with [PubGrubError::UnknownDependencies].
+/// The locked versions reached must also avoid every
+/// [known incompatibility](DependencyProvider::known_incompatibilities),
+/// otherwise it is reported with [PubGrubError::LockIncompatibility].
+/// Locked packages that are not reached are ignored.
+pub fn verify_lock<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<P, VS>,
+    package: P,
+    version: impl Into<VS::V>,
+    lock: &Map<P, VS::V>,
+) -> Result<(), PubGrubError<P, VS>> {
+    let root = package.clone();
+    let mut visited: Map<P, VS::V> = Map::default();
+    let mut to_visit = alloc::vec![(package, version.into())];
+    while let Some((p, v)) = to_visit.pop() {
+        if visited.contains_key(&p) {
+            continue;
+        }
+        let retrieving_error = |source| PubGrubError::ErrorRetrievingDependencies {
+            package: p.clone(),
+            version: v.clone(),
+            source,
+        };
+        let mut dependencies = dependency_provider
+            .get_dependencies(&p, &v)
+            .map_err(retrieving_error)?;
+        if p == root {
+            let dev_dependencies = dependency_provider
+                .get_dev_dependencies(&p, &v)
+                .map_err(retrieving_error)?;
+            dependencies = with_dev_dependencies(dependencies, dev_dependencies);
+        }
+        let dependencies = match dependencies {
+            Dependencies::Known(dependencies) => dependencies,
+            Dependencies::Unknown => {
+                return Err(PubGrubError::UnknownDependencies {
+                    package: p,
+                    version: v,
+                })
+            }
+        };
+        for (dependency, range) in dependencies {
+            let locked = match visited.get(&dependency) {
+                Some(locked) => Some(locked.clone()),
+                None if dependency_provider.is_virtual(&dependency) => {
+                    locked_virtual_version(dependency_provider, &dependency, &range, lock)?
+                }
+                None => lock.get(&dependency).cloned(),
+            };
+            match locked {
+                Some(locked) if range.contains(&locked) => {
+                    to_visit.push((dependency, locked));
+                }
+                _ => {
+                    return Err(PubGrubError::LockViolation {
+                        locked,
+                        package: p,
+                        version: v,
+                        dependency,
+                    })
+                }
+            }
+        }
+        visited.insert(p, v);
+    }
+    for incompatibility in dependency_provider.known_incompatibilities() {
+        let satisfied = incompatibility
+            .iter()
+            .all(|(p, term)| match visited.get(p) {
+                Some(v) => term.contains(v),
+                None => !term.is_positive(),
+            });
+        if satisfied {
+            return Err(match incompatibility.external() {
+                Some(external) => PubGrubError::LockIncompatibility(external),
+                None => PubGrubError::Failure(format!(
+                    "the lock matches the incompatibility {}",
+                    incompatibility
+                )),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Version of a virtual package in `range` for [verify_lock].
+/// Virtual packages are never locked, since they are absent from the solutions of [resolve],
+/// so this is the first version, in the order of
+/// [choose_package_version](DependencyProvider::choose_package_version),
+/// whose dependencies are all locked at a version in range.
+/// If no version qualifies, the violation of the first one is reported,
+/// and [None] is returned if there is no version in range at all.
+fn locked_virtual_version<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<P, VS>,
+    package: &P,
+    range: &VS,
+    lock: &Map<P, VS::V>,
+) -> Result<Option<VS::V>, PubGrubError<P, VS>> {
+    let mut remaining = range.clone();
+    let mut first_violation = None;
+    loop {
+        let (_, version) = dependency_provider
+            .choose_package_version(core::iter::once((package, &remaining)))
+            .map_err(PubGrubError::ErrorChoosingPackageVersion)?;
+        let version = match version {
+            Some(version) => version,
+            None => break,
+        };
+        let dependencies = dependency_provider
+            .get_dependencies(package, &version)
+            .map_err(|source| PubGrubError::ErrorRetrievingDependencies {
+                package: package.clone(),
+                version: version.clone(),
+                source,
+            })?;
+        let violation = match dependencies {
+            Dependencies::Known(dependencies) => dependencies
+                .into_iter()
+                .find(|(dependency, range)| {
+                    !lock.get(dependency).is_some_and(|l| range.contains(l))
+                })
+                .map(|(dependency, _)| PubGrubError::LockViolation {
+                    locked: lock.get(&dependency).cloned(),
+                    package: package.clone(),
+                    version: version.clone(),
+                    dependency,
+                }),
+            Dependencies::Unknown => Some(PubGrubError::UnknownDependencies {
+                package: package.clone(),
+                version: version.clone(),
+            }),
+        };
+        match violation {
+            None => return Ok(Some(version)),
+            Some(violation) => {
+                first_violation.get_or_insert(violation);
+            }
+        }
+        remaining = remaining.intersection(&VS::singleton(version).complement());
+    }
+    match first_violation {
+        Some(violation) => Err(violation),
+        None => Ok(None),
+    }
+}
+
+/// Same as [resolve], except that the solution is kept close to a `baseline`,
+/// typically the previous solution read from a lock file.
+/// Whenever a package of the baseline can still be selected at its baseline version,
+/// it is decided first and at that version,
+/// so packages only deviate from the baseline when forced to.
+/// This is a greedy preference, which does not guarantee
+/// the fewest changes among all the solutions.
+/// Unlike [resolve_with_pins], the baseline never causes a failure.
+pub fn resolve_closest_to<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<P, VS>,
+    package: P,
+    version: impl Into<VS::V>,
+    baseline: &SelectedDependencies<P, VS::V>,
+) -> Result<SelectedDependencies<P, VS::V>, PubGrubError<P, VS>> {
+    let baseline_provider = BaselineDependencyProvider {
+        dependency_provider,
+        baseline,
+    };
+    resolve(&baseline_provider, package, version)
+}
+
+/// Dependency provider used by [resolve_closest_to],
+/// choosing baseline versions when possible and delegating everything else.
+struct BaselineDependencyProvider<'a, P: Package, V, DP> {
+    dependency_provider: &'a DP,
+    baseline: &'a SelectedDependencies<P, V>,
+}
+
+impl<P: Package, VS: VersionSet, DP: DependencyProvider<P, VS>> DependencyProvider<P, VS>
+    for BaselineDependencyProvider<'_, P, VS::V, DP>
+{
+    fn choose_package_version<T: Borrow<P>, U: Borrow<VS>>(
+        &self,
+        potential_packages: impl Iterator<Item = (T, U)>,
+    ) -> Result<(T, Option<VS::V>), Box<dyn Error>> {
+        let mut potential_packages: Vec<(T, U)> = potential_packages.collect();
+        let in_baseline = potential_packages.iter().position(|(p, range)| {
+            self.baseline
+                .get(p.borrow())
+                .is_some_and(|v| range.borrow().contains(v))
+        });
+        match in_baseline {
+            Some(index) => {
+                let (package, _) = potential_packages.swap_remove(index);
+                let version = self.baseline[package.borrow()].clone();
+                Ok((package, Some(version)))
+            }
+            None => self
+                .dependency_provider
+                .choose_package_version(potential_packages.into_iter()),
+        }
+    }
+
+    fn prioritize(&self, package: &P, range: &VS) -> u32 {
+        self.dependency_provider.prioritize(package, range)
+    }
+
+    fn score_version(&self, package: &P, version: &VS::V) -> i64 {
+        self.dependency_provider.score_version(package, version)
+    }
+
+    fn get_dependencies(
+        &self,
+        package: &P,
+        version: &VS::V,
+    ) -> Result<Dependencies<P, VS>, Box<dyn Error>> {
+        self.dependency_provider.get_dependencies(package, version)
+    }
+
+    fn get_dev_dependencies(
+        &self,
+        package: &P,
+        version: &VS::V,
+    ) -> Result<Dependencies<P, VS>, Box<dyn Error>> {
+        self.dependency_provider
+            .get_dev_dependencies(package, version)
+    }
+
+    fn package_exists(&self, package: &P) -> bool {
+        self.dependency_provider.package_exists(package)
+    }
+
+    fn accept_version(&self, package: &P, version: &VS::V) -> bool {
+        self.dependency_provider.accept_version(package, version)
+    }
+
+    fn is_virtual(&self, package: &P) -> bool {
+        self.dependency_provider.is_virtual(package)
+    }
+
+    fn known_incompatibilities(&self) -> Vec<Incompatibility<P, VS>> {
+        self.dependency_provider.known_incompatibilities()
+    }
+
+    fn should_cancel(&self) -> Result<(), Box<dyn Error>> {
+        self.dependency_provider.should_cancel()
+    }
+}
+
+/// Same as [resolve], also recording the decisions and backtracks of the resolution
+/// in a [SolverTrace], which can be replayed later with [replay].
+#[allow(clippy::type_complexity)]
+pub fn resolve_recording<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<P, VS>,
+    package: P,
+    version: impl Into<VS::V>,
+) -> (
+    Result<SelectedDependencies<P, VS::V>, PubGrubError<P, VS>>,
+    SolverTrace<P, VS::V>,
+) {
+    let version = version.into();
+    let mut recording = Recording::new();
+    recording.trace = Some(Vec::new());
+    let result = resolve_with_incompatibilities(
+        dependency_provider,
+        package.clone(),
+        version.clone(),
+        Vec::new(),
+        &PreviousSatisfierLevel,
+        &mut recording,
+    );
+    let trace = SolverTrace {
+        root: (package, version),
+        steps: recording.trace.unwrap_or_default(),
+    };
+    (result, trace)
+}
+
+/// Replay a resolution recorded with [resolve_recording],
+/// taking the same decisions instead of asking the dependency provider.
+/// If the resolution does not go through the exact same steps,
+/// for example because dependencies changed,
+/// it fails with [PubGrubError::ReplayDivergence] at the first differing step.
+pub fn replay<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<P, VS>,
+    trace: &SolverTrace<P, VS::V>,
+) -> Result<SelectedDependencies<P, VS::V>, PubGrubError<P, VS>> {
+    let replay_provider = ReplayDependencyProvider {
+        dependency_provider,
+        decisions: trace
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                TraceStep::Decision(package, version) => Some((package, version)),
+                TraceStep::Backtrack(_) => None,
+            })
+            .collect(),
+        next_decision: Cell::new(0),
+    };
+    let mut recording = Recording::new();
+    recording.trace = Some(Vec::new());
+    let result = resolve_with_incompatibilities(
+        &replay_provider,
+        trace.root.0.clone(),
+        trace.root.1.clone(),
+        Vec::new(),
+        &PreviousSatisfierLevel,
+        &mut recording,
+    );
+    let steps = recording.trace.unwrap_or_default();
+    let divergence = steps
+        .iter()
+        .zip(&trace.steps)
+        .position(|(replayed, recorded)| replayed != recorded)
+        .or_else(|| (steps.len() != trace.steps.len()).then_some(steps.len()));
+    match divergence {
+        Some(step) => Err(PubGrubError::ReplayDivergence { step }),
+        None => result,
+    }
+}
+
+/// Same as [resolve], made step by step while iterating over its events.
+/// Each call to `next` advances the resolution until its next decision or backtrack,
+/// such that packages can be shown as soon as they are decided.
+/// The last event is the result of the resolution.
+///
+/// ```
+/// # use pubgrub::range::Range;
+/// # use pubgrub::solver::{resolve_iter, OfflineDependencyProvider, SolverEvent, TraceStep};
+/// # use pubgrub::version::NumberVersion;
+/// #
+/// let mut dependency_provider = OfflineDependencyProvider::<&str, Range<NumberVersion>>::new();
+/// dependency_provider.add_dependencies("root", 1, vec![("menu", Range::any())]);
+/// dependency_provider.add_dependencies("menu", 1, vec![]);
+///
+/// for event in resolve_iter(&dependency_provider, "root", 1) {
+///     match event {
+///         SolverEvent::Step(TraceStep::Decision(package, Some(version))) => {
+///             println!("picked {} {}", package, version)
+///         }
+///         SolverEvent::Done(solution) => assert_eq!(solution.unwrap().len(), 2),
+///         _ => {}
+///     }
+/// }
+/// ```
+pub fn resolve_iter<'a, P: Package + 'a, VS: VersionSet + 'a>(
+    dependency_provider: &'a impl DependencyProvider<P, VS>,
+    package: P,
+    version: impl Into<VS::V>,
+) -> impl Iterator<Item = SolverEvent<P, VS>> + 'a {
+    let mut recording = Recording::new();
+    recording.trace = Some(Vec::new());
+    let mut search = Some(Search::new(
+        dependency_provider,
+        package,
+        version.into(),
+        Vec::new(),
+        &mut recording.stats,
+    ));
+    let mut events = VecDeque::new();
+    core::iter::from_fn(move || {
+        while events.is_empty() {
+            let result = search
+                .as_mut()?
+                .step(dependency_provider, &PreviousSatisfierLevel, &mut recording)
+                .transpose();
+            if let Some(trace) = recording.trace.as_mut() {
+                events.extend(trace.drain(..).map(SolverEvent::Step));
+            }
+            if let Some(result) = result {
+                search = None;
+                events.push_back(SolverEvent::Done(result));
+            }
+        }
+        events.pop_front()
+    })
+}
+
+/// Same as [resolve], except that conflicts are resolved
+/// by backtracking to the decision level chosen by `backtrack_strategy`
+/// instead of the level of the previous satisfier.
+/// This is meant for experimenting with other backjumping policies,
+/// [resolve] being equivalent to using [PreviousSatisfierLevel].
+pub fn resolve_with_backtrack_strategy<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<P, VS>,
+    package: P,
+    version: impl Into<VS::V>,
+    backtrack_strategy: &impl BacktrackStrategy,
+) -> Result<SelectedDependencies<P, VS::V>, PubGrubError<P, VS>> {
+    resolve_with_incompatibilities(
+        dependency_provider,
+        package,
+        version.into(),
+        Vec::new(),
+        backtrack_strategy,
+        &mut Recording::new(),
+    )
+}
+
+/// Policy choosing the decision level to backtrack to after a conflict.
+///
+/// When resolving a conflict, the solver learns an incompatibility
+/// satisfied by the partial solution.
+/// Its satisfier, the assignment that made it satisfied, is at `satisfier_level`,
+/// while all its other terms were already satisfied at `previous_satisfier_level`.
+/// Backtracking to any level in between removes the satisfier,
+/// so that the learned incompatibility can be used to derive a new assignment.
+pub trait BacktrackStrategy {
+    /// Decision level to backtrack to.
+    /// Levels outside of `previous_satisfier_level..satisfier_level`
+    /// are brought back to the closest level in that range.
+    fn backtrack_level(&self, previous_satisfier_level: u32, satisfier_level: u32) -> u32;
+}
+
+/// Default [BacktrackStrategy] of PubGrub,
+/// jumping back as far as possible, to the level of the previous satisfier.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PreviousSatisfierLevel;
+
+impl BacktrackStrategy for PreviousSatisfierLevel {
+    fn backtrack_level(&self, previous_satisfier_level: u32, _satisfier_level: u32) -> u32 {
+        previous_satisfier_level
+    }
+}
+
+/// Policy restarting the search after a number of conflicts,
+/// used with [resolve_with_restarts].
+///
+/// A restart backtracks to the decision of the root package,
+/// keeping every incompatibility learned so far,
+/// and shuffles the order of the packages sharing the highest priority
+/// before they are given to [DependencyProvider::choose_package_version].
+/// Providers breaking ties by that order, like [choose_package_with_fewest_versions],
+/// then make different decisions
+/// among packages with the same number of versions after each restart.
+/// The number of conflicts between two restarts doubles after each of them,
+/// such that the resolution always terminates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestartPolicy {
+    /// Number of conflicts before the first restart.
+    pub conflicts: usize,
+    /// Seed of the random shuffles, the same seed giving the same resolution.
+    pub seed: u64,
+}
+
+/// Same as [resolve], restarting the search as configured by `restart_policy`,
+/// which may help with inputs leading to a lot of backtracking.
+/// Statistics about the resolution, including the number of restarts, are also returned.
+#[allow(clippy::type_complexity)]
+pub fn resolve_with_restarts<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<P, VS>,
+    package: P,
+    version: impl Into<VS::V>,
+    restart_policy: RestartPolicy,
+) -> (
+    Result<SelectedDependencies<P, VS::V>, PubGrubError<P, VS>>,
+    SolverStats,
+) {
+    let mut recording = Recording::new();
+    recording.restarts = Some(Restarts::new(restart_policy));
+    let result = resolve_with_incompatibilities(
+        dependency_provider,
+        package,
+        version.into(),
+        Vec::new(),
+        &PreviousSatisfierLevel,
+        &mut recording,
+    );
+    (result, recording.stats)
+}
+
+/// State of the restarts of a resolution.
+struct Restarts {
+    /// Number of conflicts between the last restart and the next one.
+    interval: usize,
+    /// Number of conflicts at the last restart.
+    last_conflicts: usize,
+    /// State of the SplitMix64 random number generator.
+    rng: u64,
+}
+
+impl Restarts {
+    fn new(policy: RestartPolicy) -> Self {
+        Self {
+            interval: policy.conflicts.max(1),
+            last_conflicts: 0,
+            rng: policy.seed,
+        }
+    }
+
+    /// Check if enough conflicts happened since the last restart,
+    /// and if so, prepare the next one.
+    fn should_restart(&mut self, conflicts: usize) -> bool {
+        if conflicts - self.last_conflicts < self.interval {
+            return false;
+        }
+        self.interval = self.interval.saturating_mul(2);
+        true
+    }
+
+    fn next_random(&mut self) -> u64 {
+        self.rng = self.rng.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Fisher-Yates shuffle.
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = (self.next_random() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+/// Same as [resolve], also returning statistics about the resolution,
+/// whether it succeeded or not.
+#[allow(clippy::type_complexity)]
+pub fn resolve_with_stats<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<P, VS>,
+    package: P,
+    version: impl Into<VS::V>,
+) -> (
+    Result<SelectedDependencies<P, VS::V>, PubGrubError<P, VS>>,
+    SolverStats,
+) {
+    let mut recording = Recording::new();
+    let result = resolve_with_incompatibilities(
+        dependency_provider,
+        package,
+        version.into(),
+        Vec::new(),
+        &PreviousSatisfierLevel,
+        &mut recording,
+    );
+    (result, recording.stats)
+}
+
+/// Same as [resolve], also returning every decision committed to the partial solution,
+/// in order, including the ones later undone by backtracking.
+/// Unlike a [SolverTrace], it does not contain backtracks nor versions rejected
+/// before being decided, which makes it convenient for golden tests:
+/// with a deterministic dependency provider, the decisions are always the same.
+#[allow(clippy::type_complexity)]
+pub fn resolve_with_decisions<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<P, VS>,
+    package: P,
+    version: impl Into<VS::V>,
+) -> (
+    Result<SelectedDependencies<P, VS::V>, PubGrubError<P, VS>>,
+    Vec<(P, VS::V)>,
+) {
+    let mut recording = Recording::new();
+    recording.decisions = Some(Vec::new());
+    let result = resolve_with_incompatibilities(
+        dependency_provider,
+        package,
+        version.into(),
+        Vec::new(),
+        &PreviousSatisfierLevel,
+        &mut recording,
+    );
+    (result, recording.decisions.unwrap_or_default())
+}
+
+/// Same as [resolve], also returning the decisions and derivations
+/// of the final partial solution, in the order in which they were made.
+/// If there is no solution, these are the assignments at the time of the final conflict.
+/// See the [assignment](crate::assignment) module for an example.
+#[allow(clippy::type_complexity)]
+pub fn resolve_with_assignments<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<P, VS>,
+    package: P,
+    version: impl Into<VS::V>,
+) -> (
+    Result<SelectedDependencies<P, VS::V>, PubGrubError<P, VS>>,
+    Vec<Assignment<P, VS>>,
+) {
+    let mut recording = Recording::new();
+    recording.assignments = Some(Vec::new());
+    let result = resolve_with_incompatibilities(
+        dependency_provider,
+        package,
+        version.into(),
+        Vec::new(),
+        &PreviousSatisfierLevel,
+        &mut recording,
+    );
+    (result, recording.assignments.unwrap_or_default())
+}
+
+/// Same as [resolve], also returning every incompatibility known to the solver
+/// at the end of the resolution, in the order in which they were added.
+/// They include the ones obtained from the dependency provider,
+/// with an [external](Incompatibility::external) cause,
+/// and the ones learned from conflicts, without external cause.
+/// Each incompatibility is a clause forbidding its terms to be satisfied together,
+/// which makes it possible to cross-check a failure with other solvers.
+#[allow(clippy::type_complexity)]
+pub fn resolve_with_learned_incompatibilities<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<P, VS>,
+    package: P,
+    version: impl Into<VS::V>,
+) -> (
+    Result<SelectedDependencies<P, VS::V>, PubGrubError<P, VS>>,
+    Vec<Incompatibility<P, VS>>,
+) {
+    let mut recording = Recording::new();
+    recording.incompatibilities = Some(Vec::new());
+    let result = resolve_with_incompatibilities(
+        dependency_provider,
+        package,
+        version.into(),
+        Vec::new(),
+        &PreviousSatisfierLevel,
+        &mut recording,
+    );
+    (result, recording.incompatibilities.unwrap_or_default())
+}
+
+/// Same as [resolve], also returning the dependents of each selected package,
+/// to present the solution as a dependency tree.
+/// Dependents are those of the dependencies seen by the solver
+/// that are satisfied by the solution, including development dependencies of the root.
+/// The root package has no dependents.
+///
+/// ```
+/// # use pubgrub::range::Range;
+/// # use pubgrub::solver::{resolve_with_tree, OfflineDependencyProvider};
+/// # use pubgrub::version::NumberVersion;
+/// #
+/// let mut dependency_provider = OfflineDependencyProvider::<&str, Range<NumberVersion>>::new();
+/// dependency_provider.add_dependencies("root", 1, vec![("menu", Range::any())]);
+/// dependency_provider.add_dependencies("menu", 1, vec![]);
+///
+/// let (solution, dependents) = resolve_with_tree(&dependency_provider, "root", 1).unwrap();
+/// assert_eq!(dependents["menu"]["root"], Range::any());
+/// assert!(!dependents.contains_key("root"));
+/// ```
+#[allow(clippy::type_complexity)]
+pub fn resolve_with_tree<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<P, VS>,
+    package: P,
+    version: impl Into<VS::V>,
+) -> Result<(SelectedDependencies<P, VS::V>, Dependents<P, VS>), PubGrubError<P, VS>> {
+    let mut recording = Recording::new();
+    recording.incompatibilities = Some(Vec::new());
+    let solution = resolve_with_incompatibilities(
+        dependency_provider,
+        package,
+        version.into(),
+        Vec::new(),
+        &PreviousSatisfierLevel,
+        &mut recording,
+    )?;
+    let mut dependents: Dependents<P, VS> = Map::default();
+    for incompat in recording.incompatibilities.unwrap_or_default() {
+        if let Some(External::FromDependencyOf(p, range, dependency, dependency_range)) =
+            incompat.external()
+        {
+            let dependent_selected = solution.get(&p).is_some_and(|v| range.contains(v));
+            if dependent_selected && solution.contains_key(&dependency) {
+                dependents
+                    .entry(dependency)
+                    .or_default()
+                    .insert(p, dependency_range);
+            }
+        }
+    }
+    Ok((solution, dependents))
+}
+
+/// Same as [resolve], calling `observer` with the terms accumulated for each package
+/// every time unit propagation is done, just before the next decision.
+/// This makes it possible to follow how constraints narrow while the solver progresses.
+///
+/// ```
+/// # use pubgrub::range::Range;
+/// # use pubgrub::solver::{resolve_with_terms, OfflineDependencyProvider};
+/// # use pubgrub::term::Term;
+/// # use pubgrub::version::NumberVersion;
+/// #
+/// let mut dependency_provider = OfflineDependencyProvider::<&str, Range<NumberVersion>>::new();
+/// dependency_provider.add_dependencies("root", 1, vec![("menu", Range::higher_than(2))]);
+/// dependency_provider.add_dependencies("menu", 3, vec![]);
+///
+/// let mut menu_terms = Vec::new();
+/// resolve_with_terms(&dependency_provider, "root", 1, |terms| {
+///     menu_terms.extend(terms.get(&"menu").cloned());
+/// })
+/// .unwrap();
+/// // Deciding menu 3 narrows its term to that version.
+/// assert_eq!(
+///     menu_terms,
+///     vec![
+///         Term::Positive(Range::higher_than(2)),
+///         Term::Positive(Range::exact(3)),
+///     ]
+/// );
+/// ```
+pub fn resolve_with_terms<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<P, VS>,
+    package: P,
+    version: impl Into<VS::V>,
+    mut observer: impl FnMut(&PackageTerms<P, VS>),
+) -> Result<SelectedDependencies<P, VS::V>, PubGrubError<P, VS>> {
+    let mut recording = Recording::new();
+    recording.observer = Some(&mut observer);
+    resolve_with_incompatibilities(
+        dependency_provider,
+        package,
+        version.into(),
+        Vec::new(),
+        &PreviousSatisfierLevel,
+        &mut recording,
+    )
+}
+
+/// Read-only view of the terms accumulated for each package
+/// by the solver during a resolution, given to the observer of [resolve_with_terms].
+/// Each term is the intersection of the decision and derivations of its package.
+pub struct PackageTerms<'a, P: Package, VS: VersionSet> {
+    partial_solution: &'a PartialSolution<P, VS>,
+    incompatibility_store: &'a Arena<Incompatibility<P, VS>>,
+}
+
+impl<P: Package, VS: VersionSet> PackageTerms<'_, P, VS> {
+    /// Iterate over the packages having accumulated constraints, with their term.
+    pub fn iter(&self) -> impl Iterator<Item = (&P, &Term<VS>)> {
+        self.partial_solution.package_terms()
+    }
+
+    /// Term accumulated for a package, if any.
+    pub fn get(&self, package: &P) -> Option<&Term<VS>> {
+        self.partial_solution.term_intersection_for_package(package)
+    }
+
+    /// Earliest assignment such that the incompatibility is satisfied
+    /// by the partial solution up to and including that assignment,
+    /// or `None` if the incompatibility is not satisfied.
+    pub fn satisfier_of(
+        &self,
+        incompatibility: &Incompatibility<P, VS>,
+    ) -> Option<Assignment<P, VS>> {
+        self.partial_solution
+            .satisfier_of(incompatibility, self.incompatibility_store)
+    }
+
+    /// Undecided packages that can be picked for the next decision,
+    /// with their term and number of versions from `list_available_versions` in it,
+    /// sorted by increasing count, like [choose_package_with_fewest_versions] ranks them.
+    /// The first one is the package that helper would pick,
+    /// and the whole ranking is useful to experiment with other heuristics.
+    pub fn ranked_potential_packages<I, F>(
+        &self,
+        list_available_versions: F,
+    ) -> Vec<(P, Term<VS>, usize)>
+    where
+        I: Iterator<Item = VS::V>,
+        F: Fn(&P) -> I,
+    {
+        self.partial_solution
+            .ranked_potential_packages(list_available_versions)
+    }
+}
+
+/// Statistics about a resolution, obtained with [resolve_with_stats].
+/// They are meant to compare dependency providers or inputs,
+/// and may change with the implementation of the solver.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SolverStats {
+    /// Number of versions added to the partial solution as decisions.
+    pub decisions: usize,
+    /// Number of terms derived by unit propagation, including after conflicts.
+    pub derivations: usize,
+    /// Number of times the partial solution was backtracked after a conflict.
+    pub backtracks: usize,
+    /// Number of conflicts resolved, without counting the final one if there is no solution.
+    pub conflicts: usize,
+    /// Number of restarts of the search, only done with [resolve_with_restarts].
+    pub restarts: usize,
+    /// Number of calls to the methods of the [DependencyProvider].
+    pub provider_calls: usize,
+    /// Number of relations between an incompatibility and the partial solution
+    /// computed during unit propagation.
+    /// Incompatibilities already known to be contradicted are skipped.
+    pub relations: usize,
+}
+
+/// Outputs of a resolution other than its result.
+/// Statistics are always accumulated,
+/// while the trace, assignments and incompatibilities are only recorded if initialized.
+/// The search is only restarted if restarts are initialized,
+/// and the terms are only observed if there is an observer.
+struct Recording<'a, P: Package, VS: VersionSet> {
+    stats: SolverStats,
+    trace: Option<Vec<TraceStep<P, VS::V>>>,
+    decisions: Option<Vec<(P, VS::V)>>,
+    assignments: Option<Vec<Assignment<P, VS>>>,
+    incompatibilities: Option<Vec<Incompatibility<P, VS>>>,
+    restarts: Option<Restarts>,
+    #[allow(clippy::type_complexity)]
+    observer: Option<&'a mut dyn FnMut(&PackageTerms<P, VS>)>,
+    /// Only check if there is a solution, without building it
+    /// or the derivation tree of a failure.
+    check_only: bool,
+}
+
+impl<P: Package, VS: VersionSet> Recording<'_, P, VS> {
+    /// Only accumulate statistics.
+    fn new() -> Self {
+        Self {
+            stats: SolverStats::default(),
+            trace: None,
+            decisions: None,
+            assignments: None,
+            incompatibilities: None,
+            restarts: None,
+            observer: None,
+            check_only: false,
+        }
+    }
+}
+
+/// Resolve with additional incompatibilities,
+/// on top of the ones known by the dependency provider.
+/// Statistics, steps, final assignments and incompatibilities of the resolution
+/// are saved in `recording`.
+fn resolve_with_incompatibilities<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<P, VS>,
+    package: P,
+    version: VS::V,
+    incompatibilities: Vec<Incompatibility<P, VS>>,
+    backtrack_strategy: &impl BacktrackStrategy,
+    recording: &mut Recording<'_, P, VS>,
+) -> Result<SelectedDependencies<P, VS::V>, PubGrubError<P, VS>> {
+    let mut search = Search::new(
+        dependency_provider,
+        package,
+        version,
+        incompatibilities,
+        &mut recording.stats,
+    );
+    search.state.report_failures = !recording.check_only;
+    let result = loop {
+        if let Some(result) = search
+            .step(dependency_provider, backtrack_strategy, recording)
+            .transpose()
+        {
+            break result;
+        }
+    };
+    let state = search.state;
+    if let Some(assignments) = recording.assignments.as_mut() {
+        *assignments = state
+            .partial_solution
+            .assignments(&state.incompatibility_store);
+    }
+    if let Some(incompatibilities) = recording.incompatibilities.as_mut() {
+        *incompatibilities = state.incompatibility_store.iter().cloned().collect();
+    }
+    result
+}
+
+/// Progress of the main loop of the solver, between two of its iterations.
+struct Search<P: Package, VS: VersionSet> {
+    state: State<P, VS>,
+    added_dependencies: Map<P, Set<VS::V>>,
+    root: (P, VS::V),
+    next: P,
+}
+
+impl<P: Package, VS: VersionSet> Search<P, VS> {
+    /// Initialize the solver state with the incompatibilities known by the dependency provider,
+    /// and additional ones.
+    fn new(
+        dependency_provider: &impl DependencyProvider<P, VS>,
+        package: P,
+        version: VS::V,
+        incompatibilities: Vec<Incompatibility<P, VS>>,
+        stats: &mut SolverStats,
+    ) -> Self {
+        let mut state = State::init(package.clone(), version.clone());
+        stats.provider_calls += 1;
+        for incompat in dependency_provider
+            .known_incompatibilities()
+            .into_iter()
+            .chain(incompatibilities)
+        {
+            state.add_incompatibility(incompat);
+        }
+        Self {
+            state,
+            added_dependencies: Map::default(),
+            root: (package.clone(), version),
+            next: package,
+        }
+    }
+
+    /// One iteration of the main loop of the solver,
+    /// alternating unit propagation and decision making.
+    /// Return the solution once there is no package left to decide.
+    #[allow(clippy::type_complexity)]
+    fn step(
+        &mut self,
+        dependency_provider: &impl DependencyProvider<P, VS>,
+        backtrack_strategy: &impl BacktrackStrategy,
+        recording: &mut Recording<'_, P, VS>,
+    ) -> Result<Option<SelectedDependencies<P, VS::V>>, PubGrubError<P, VS>> {
+        let Self {
+            state,
+            added_dependencies,
+            root,
+            next,
+        } = self;
+        let stats = &mut recording.stats;
+        let trace = &mut recording.trace;
+        let decisions = &mut recording.decisions;
+        let restarts = &mut recording.restarts;
+        stats.provider_calls += 1;
+        dependency_provider
+            .should_cancel()
+            .map_err(|err| PubGrubError::ErrorInShouldCancel(err))?;
+
+        let decision_level = state.partial_solution.current_decision_level();
+        state.unit_propagation(next.clone(), backtrack_strategy, stats)?;
+        if let Some(restarts) = restarts.as_mut() {
+            if restarts.should_restart(stats.conflicts) {
+                stats.restarts += 1;
+                state.restart(backtrack_strategy, stats)?;
+                restarts.last_conflicts = stats.conflicts;
+            }
+        }
+        #[cfg(feature = "debug-checks")]
+        state
+            .partial_solution
+            .check_invariants(&state.incompatibility_store);
+        if let Some(observer) = recording.observer.as_mut() {
+            observer(&PackageTerms {
+                partial_solution: &state.partial_solution,
+                incompatibility_store: &state.incompatibility_store,
+            });
+        }
+        let backtracked_level = state.partial_solution.current_decision_level();
+        if let Some(trace) = trace.as_mut() {
+            if backtracked_level < decision_level {
+                trace.push(TraceStep::Backtrack(backtracked_level.0));
+            }
+        }
+
+        let potential_packages = state.partial_solution.potential_packages();
+        if potential_packages.is_none() {
+            drop(potential_packages);
+            // The borrow checker did not like using a match on potential_packages.
+            // This `if ... is_none ... drop` is a workaround.
+            // I believe this is a case where Polonius could help, when and if it lands in rustc.
+            if recording.check_only {
+                return Ok(Some(SelectedDependencies::default()));
+            }
+            return state
+                .partial_solution
+                .extract_solution()
+                .map(|mut solution| {
+                    solution.retain(|p, _| !dependency_provider.is_virtual(p));
+                    Some(solution)
+                })
+                .ok_or_else(|| {
+                    let undecided: Vec<_> = state
+                        .partial_solution
+                        .unsatisfied_derivations()
+                        .iter()
+                        .map(|(p, term)| format!("{} {}", p, term))
+                        .collect();
+                    PubGrubError::Failure(format!(
+                        "How did we end up with no package to choose but no solution? \
+                 Undecided packages: {}",
+                        undecided.join(", ")
+                    ))
+                });
+        }
+        // The root package is always decided first, at the decision level kept by backtracking,
+        // even if known incompatibilities already derived terms of other packages.
+        let root_decided = state.partial_solution.current_decision_level().0 > 0;
+        // Only the packages sharing the highest priority are up for decision.
+        let prioritized_packages: Vec<_> = potential_packages
+            .unwrap()
+            .filter(|(p, _)| root_decided || *p == &root.0)
+            .map(|(p, r)| (dependency_provider.prioritize(p, r), p, r))
+            .collect();
+        stats.provider_calls += prioritized_packages.len() + 1;
+        let max_priority = prioritized_packages.iter().map(|(prio, _, _)| *prio).max();
+        let mut candidates: Vec<_> = prioritized_packages
+            .into_iter()
+            .filter(|(prio, _, _)| Some(*prio) == max_priority)
+            .map(|(_, p, r)| (p, r))
+            .collect();
+        if let Some(restarts) = restarts.as_mut() {
+            if stats.restarts > 0 {
+                restarts.shuffle(&mut candidates);
+            }
+        }
+        let decision = dependency_provider
+            .choose_package_version(candidates.into_iter())
+            .map_err(PubGrubError::ErrorChoosingPackageVersion)?;
+        *next = decision.0.clone();
+        if let Some(trace) = trace.as_mut() {
+            trace.push(TraceStep::Decision(next.clone(), decision.1.clone()));
+        }
+
+        // Pick the next compatible version.
+        let term_intersection = state
+            .partial_solution
+            .term_intersection_for_package(next)
+            .expect("a package was chosen but we don't have a term.");
+        let v = match decision.1 {
+            // The root is only ever allowed at the requested version,
+            // so the provider does not know about that version.
+            None if *next == root.0 => {
+                return Err(PubGrubError::RootVersionNotAvailable {
+                    package: root.0.clone(),
+                    version: root.1.clone(),
+                });
+            }
+            None => {
+                stats.provider_calls += 1;
+                // A missing package is only an error if it is required
+                // whatever the decisions, that is as soon as the root is decided.
+                // Otherwise, the versions requiring it are avoided like any other conflict.
+                if !dependency_provider.package_exists(next) {
+                    let required =
+                        Incompatibility::no_versions(next.clone(), Term::Positive(VS::full()));
+                    let satisfier = state
+                        .partial_solution
+                        .satisfier_of(&required, &state.incompatibility_store);
+                    if satisfier.is_some_and(|s| s.decision_level() <= 1) {
+                        return Err(PubGrubError::PackageNotFound {
+                            package: next.clone(),
+                        });
+                    }
+                }
+                let inc = Incompatibility::no_versions(next.clone(), term_intersection.clone());
+                state.add_incompatibility(inc);
+                return Ok(None);
+            }
+            Some(x) => x,
+        };
+        if !term_intersection.contains(&v) {
+            return Err(PubGrubError::ErrorChoosingPackageVersion(
+                "choose_package_version picked an incompatible version".into(),
+            ));
+        }
+        stats.provider_calls += 1;
+        if !dependency_provider.accept_version(next, &v) {
+            let inc = Incompatibility::no_versions(next.clone(), Term::exact(v));
+            state.add_incompatibility(inc);
+            return Ok(None);
+        }
+
+        if added_dependencies
+            .entry(next.clone())
+            .or_default()
+            .insert(v.clone())
+        {
+            // Retrieve that package dependencies.
+            let p: &P = next;
+            let retrieving_error = |err| PubGrubError::ErrorRetrievingDependencies {
+                package: p.clone(),
+                version: v.clone(),
+                source: err,
+            };
+            stats.provider_calls += 1;
+            let mut dependencies = dependency_provider
+                .get_dependencies(p, &v)
+                .map_err(retrieving_error)?;
+            // Development dependencies only apply to the root package.
+            if p == &root.0 {
+                stats.provider_calls += 1;
+                let dev_dependencies = dependency_provider
+                    .get_dev_dependencies(p, &v)
+                    .map_err(retrieving_error)?;
+                dependencies = with_dev_dependencies(dependencies, dev_dependencies);
+            }
+            let dependencies = match dependencies {
+                Dependencies::Unknown => {
+                    state.add_incompatibility(Incompatibility::unavailable_dependencies(
+                        p.clone(),
+                        v.clone(),
+                    ));
+                    return Ok(None);
+                }
+                Dependencies::Known(x) => {
+                    if x.contains_key(p) {
+                        return Err(PubGrubError::SelfDependency {
+                            package: p.clone(),
+                            version: v.clone(),
+                        });
+                    }
+                    if let Some((dependent, _)) = x.iter().find(|(_, r)| r.is_empty()) {
+                        return Err(PubGrubError::DependencyOnTheEmptySet {
+                            package: p.clone(),
+                            version: v.clone(),
+                            dependent: dependent.clone(),
+                        });
+                    }
+                    x
+                }
+            };
+
+            // Add that package and version if the dependencies are not problematic.
+            let dep_incompats =
+                state.add_incompatibility_from_dependencies(p.clone(), v.clone(), &dependencies);
+
+            // TODO: I don't think this check can actually happen.
+            // We might want to put it under #[cfg(debug_assertions)].
+            if state.incompatibility_store[dep_incompats.clone()]
+                .iter()
+                .any(|incompat| state.is_terminal(incompat))
+            {
+                // For a dependency incompatibility to be terminal,
+                // it can only mean that root depend on not root?
+                return Err(PubGrubError::Failure(
+                    "Root package depends on itself at a different version?".into(),
+                ));
+            }
+            if state.partial_solution.add_version(
+                p.clone(),
+                v.clone(),
+                dep_incompats,
+                &state.incompatibility_store,
+            ) {
+                stats.decisions += 1;
+                if let Some(decisions) = decisions.as_mut() {
+                    decisions.push((p.clone(), v));
+                }
+            }
+        } else {
+            // `dep_incompats` are already in `incompatibilities` so we know there are not satisfied
+            // terms and can add the decision directly.
+            state.partial_solution.add_decision(next.clone(), v.clone());
+            stats.decisions += 1;
+            if let Some(decisions) = decisions.as_mut() {
+                decisions.push((next.clone(), v));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Collect dependencies, intersecting the requirements of packages appearing multiple times.
+fn intersect_dependencies<P: Package, VS: VersionSet>(
+    dependencies: impl IntoIterator<Item = (P, VS)>,
+) -> DependencyConstraints<P, VS> {
+    let mut constraints: DependencyConstraints<P, VS> = Map::default();
+    for (package, range) in dependencies {
+        let range = match constraints.get(&package) {
+            Some(existing) => existing.intersection(&range),
+            None => range,
+        };
+        constraints.insert(package, range);
+    }
+    constraints
+}
+
+/// Add development dependencies to the regular ones,
+/// intersecting the requirements of packages present in both.
+fn with_dev_dependencies<P: Package, VS: VersionSet>(
+    dependencies: Dependencies<P, VS>,
+    dev_dependencies: Dependencies<P, VS>,
+) -> Dependencies<P, VS> {
+    match (dependencies, dev_dependencies) {
+        (Dependencies::Known(mut dependencies), Dependencies::Known(dev_dependencies)) => {
+            for (package, range) in dev_dependencies {
+                let range = match dependencies.get(&package) {
+                    Some(existing) => existing.intersection(&range),
+                    None => range,
+                };
+                dependencies.insert(package, range);
+            }
+            Dependencies::Known(dependencies)
+        }
+        _ => Dependencies::Unknown,
+    }
+}
+
+/// One step of a resolution, recorded in a [SolverTrace].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TraceStep<P, V> {
+    /// A package was chosen for decision, with the version picked,
+    /// or [None] if there was no version available in range.
+    Decision(P, Option<V>),
+    /// The partial solution was backtracked to that decision level.
+    Backtrack(u32),
+}
+
+/// Decisions and backtracks of a resolution,
+/// recorded with [resolve_recording] and replayed with [replay].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SolverTrace<P, V> {
+    /// Root package and version of the resolution.
+    pub root: (P, V),
+    /// Steps of the resolution, in order.
+    pub steps: Vec<TraceStep<P, V>>,
+}
+
+/// Event of a resolution made step by step with [resolve_iter].
+#[derive(Debug)]
+pub enum SolverEvent<P: Package, VS: VersionSet> {
+    /// A decision or backtrack, as recorded in a [SolverTrace].
+    Step(TraceStep<P, VS::V>),
+    /// Result of the resolution, always the last event.
+    Done(Result<SelectedDependencies<P, VS::V>, PubGrubError<P, VS>>),
+}
+
+/// Dependency provider used by [replay],
+/// taking the recorded decisions and delegating everything else.
+struct ReplayDependencyProvider<'a, P: Package, V, DP> {
+    dependency_provider: &'a DP,
+    decisions: Vec<(&'a P, &'a Option<V>)>,
+    next_decision: Cell<usize>,
+}
+
+impl<P: Package, VS: VersionSet, DP: DependencyProvider<P, VS>> DependencyProvider<P, VS>
+    for ReplayDependencyProvider<'_, P, VS::V, DP>
+{
+    fn choose_package_version<T: Borrow<P>, U: Borrow<VS>>(
+        &self,
+        mut potential_packages: impl Iterator<Item = (T, U)>,
+    ) -> Result<(T, Option<VS::V>), Box<dyn Error>> {
+        let index = self.next_decision.get();
+        self.next_decision.set(index + 1);
+        let (package, version) = self
+            .decisions
+            .get(index)
+            .ok_or("the recorded trace has no more decisions")?;
+        let (chosen, _) = potential_packages
+            .find(|(p, _)| p.borrow() == *package)
+            .ok_or("the recorded decision is not a potential package")?;
+        Ok((chosen, (*version).clone()))
+    }
+
+    fn prioritize(&self, package: &P, range: &VS) -> u32 {
+        self.dependency_provider.prioritize(package, range)
+    }
+
+    fn score_version(&self, package: &P, version: &VS::V) -> i64 {
+        self.dependency_provider.score_version(package, version)
+    }
+
+    fn get_dependencies(
+        &self,
+        package: &P,
+        version: &VS::V,
+    ) -> Result<Dependencies<P, VS>, Box<dyn Error>> {
+        self.dependency_provider.get_dependencies(package, version)
+    }
+
+    fn get_dev_dependencies(
+        &self,
+        package: &P,
+        version: &VS::V,
+    ) -> Result<Dependencies<P, VS>, Box<dyn Error>> {
+        self.dependency_provider
+            .get_dev_dependencies(package, version)
+    }
+
+    fn package_exists(&self, package: &P) -> bool {
+        self.dependency_provider.package_exists(package)
+    }
+
+    fn accept_version(&self, package: &P, version: &VS::V) -> bool {
+        self.dependency_provider.accept_version(package, version)
+    }
+
+    fn is_virtual(&self, package: &P) -> bool {
+        self.dependency_provider.is_virtual(package)
+    }
+
+    fn known_incompatibilities(&self) -> Vec<Incompatibility<P, VS>> {
+        self.dependency_provider.known_incompatibilities()
+    }
+
+    fn should_cancel(&self) -> Result<(), Box<dyn Error>> {
+        self.dependency_provider.should_cancel()
+    }
+}
+
+/// Finds a set of packages satisfying the dependencies of several root packages at once.
+///
+/// Since packages are generic, the solver cannot invent a package name by itself.
+/// Instead, `root` and `root_version` identify a synthetic package,
+/// unknown to the dependency provider, which depends on all the given `roots`
+/// at their exact versions.
+/// Conflicts between the roots are reported like any other failure,
+/// and the synthetic package is removed from the returned solution.
+pub fn resolve_multi<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<P, VS>,
+    root: P,
+    root_version: impl Into<VS::V>,
+    roots: impl IntoIterator<Item = (P, VS::V)>,
+) -> Result<SelectedDependencies<P, VS::V>, PubGrubError<P, VS>> {
+    let multi_root_provider = MultiRootDependencyProvider {
+        dependency_provider,
+        root: root.clone(),
+        root_version: root_version.into(),
+        root_dependencies: roots
+            .into_iter()
+            .map(|(p, v)| (p, VS::singleton(v)))
+            .collect(),
+    };
+    let mut solution = resolve(
+        &multi_root_provider,
+        root.clone(),
+        multi_root_provider.root_version.clone(),
+    )?;
+    solution.remove(&root);
+    Ok(solution)
+}
+
+/// Dependency provider used by [resolve_multi],
+/// answering for the synthetic root package and delegating everything else.
+struct MultiRootDependencyProvider<'a, P: Package, VS: VersionSet, DP> {
+    dependency_provider: &'a DP,
+    root: P,
+    root_version: VS::V,
+    root_dependencies: DependencyConstraints<P, VS>,
+}
+
+impl<P: Package, VS: VersionSet, DP: DependencyProvider<P, VS>> DependencyProvider<P, VS>
+    for MultiRootDependencyProvider<'_, P, VS, DP>
+{
+    fn choose_package_version<T: Borrow<P>, U: Borrow<VS>>(
+        &self,
+        potential_packages: impl Iterator<Item = (T, U)>,
+    ) -> Result<(T, Option<VS::V>), Box<dyn Error>> {
+        let mut potential_packages: Vec<_> = potential_packages.collect();
+        match potential_packages
+            .iter()
+            .position(|(p, _)| p.borrow() == &self.root)
+        {
+            Some(index) => Ok((
+                potential_packages.swap_remove(index).0,
+                Some(self.root_version.clone()),
+            )),
+            None => self
+                .dependency_provider
+                .choose_package_version(potential_packages.into_iter()),
+        }
+    }
+
+    fn prioritize(&self, package: &P, range: &VS) -> u32 {
+        self.dependency_provider.prioritize(package, range)
+    }
+
+    fn score_version(&self, package: &P, version: &VS::V) -> i64 {
+        self.dependency_provider.score_version(package, version)
+    }
+
+    fn get_dependencies(
+        &self,
+        package: &P,
+        version: &VS::V,
+    ) -> Result<Dependencies<P, VS>, Box<dyn Error>> {
+        if package == &self.root && version == &self.root_version {
+            Ok(Dependencies::Known(self.root_dependencies.clone()))
+        } else {
+            self.dependency_provider.get_dependencies(package, version)
+        }
+    }
+
+    fn get_dev_dependencies(
+        &self,
+        package: &P,
+        version: &VS::V,
+    ) -> Result<Dependencies<P, VS>, Box<dyn Error>> {
+        if package == &self.root && version == &self.root_version {
+            Ok(Dependencies::Known(DependencyConstraints::default()))
+        } else {
+            self.dependency_provider
+                .get_dev_dependencies(package, version)
+        }
+    }
+
+    fn package_exists(&self, package: &P) -> bool {
+        self.dependency_provider.package_exists(package)
+    }
+
+    fn accept_version(&self, package: &P, version: &VS::V) -> bool {
+        self.dependency_provider.accept_version(package, version)
+    }
+
+    fn is_virtual(&self, package: &P) -> bool {
+        self.dependency_provider.is_virtual(package)
+    }
+
+    fn known_incompatibilities(&self) -> Vec<Incompatibility<P, VS>> {
+        self.dependency_provider.known_incompatibilities()
+    }
+
+    fn should_cancel(&self) -> Result<(), Box<dyn Error>> {
+        self.dependency_provider.should_cancel()
+    }
+}
+
+/// Compute a minimal set of the external incompatibilities of a derivation tree
+/// that are still impossible to satisfy all together.
+/// Each external incompatibility is dropped in turn,
+/// and kept out of the core if the remaining ones still have no solution.
+/// This names the fewest packages actually responsible for the failure,
+/// at the cost of one resolution per external incompatibility.
+pub fn minimal_core<P: Package, V: Version>(
+    derivation_tree: &DerivationTree<P, Range<V>>,
+) -> Vec<External<P, Range<V>>> {
+    let mut core = Vec::new();
+    derivation_tree.collect_externals(&mut core);
+    let root = match derivation_tree.root() {
+        Some(root) => root,
+        None => return core,
+    };
+    let mut index = 0;
+    while index < core.len() {
+        if matches!(core[index], External::NotRoot(_, _)) {
+            index += 1;
+            continue;
+        }
+        let candidate = core.remove(index);
+        let provider = CoreDependencyProvider { externals: &core };
+        match resolve(&provider, root.0.clone(), root.1.clone()) {
+            Err(PubGrubError::NoSolution(_)) => {}
+            _ => {
+                core.insert(index, candidate);
+                index += 1;
+            }
+        }
+    }
+    core
+}
+
+/// Dependency provider only aware of a set of external incompatibilities,
+/// used to check if they can be satisfied together.
+struct CoreDependencyProvider<'a, P: Package, V: Version> {
+    externals: &'a [External<P, Range<V>>],
+}
+
+impl<P: Package, V: Version> DependencyProvider<P, Range<V>> for CoreDependencyProvider<'_, P, V> {
+    fn choose_package_version<T: Borrow<P>, U: Borrow<Range<V>>>(
+        &self,
+        mut potential_packages: impl Iterator<Item = (T, U)>,
+    ) -> Result<(T, Option<V>), Box<dyn Error>> {
+        let (package, range) = potential_packages
+            .next()
+            .expect("potential_packages gave us an empty iterator");
+        let version = range.borrow().lowest_version();
+        Ok((package, version))
+    }
+
+    fn get_dependencies(
+        &self,
+        _package: &P,
+        _version: &V,
+    ) -> Result<Dependencies<P, Range<V>>, Box<dyn Error>> {
+        Ok(Dependencies::Known(Map::default()))
+    }
+
+    fn known_incompatibilities(&self) -> Vec<Incompatibility<P, Range<V>>> {
+        self.externals
+            .iter()
+            .filter(|external| !matches!(external, External::NotRoot(_, _)))
+            .map(|external| Incompatibility::from_external(external.clone()))
+            .collect()
+    }
+}
+
+/// Finds a set of packages satisfying the dependencies of a package and version pair,
+/// widening some of the dependencies of that root package to any version if needed.
+///
+/// If there is no solution, dependencies of the root package are relaxed,
+/// by replacing their range with [VersionSet::full],
+/// trying all sets of one dependency, then all sets of two, and so on,
+/// until a solution is found.
+/// The solution is returned with the original constraints of the relaxed dependencies,
+/// which form a smallest set of constraints to widen.
+/// If no relaxation leads to a solution, the error of the original resolution is returned.
+/// Since this may resolve once per set of dependencies,
+/// it is meant for root packages with few dependencies.
+#[allow(clippy::type_complexity)]
+pub fn resolve_with_relaxation<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<P, VS>,
+    package: P,
+    version: impl Into<VS::V>,
+) -> Result<(SelectedDependencies<P, VS::V>, Vec<(P, VS)>), PubGrubError<P, VS>> {
+    let version = version.into();
+    let error = match resolve(dependency_provider, package.clone(), version.clone()) {
+        Ok(solution) => return Ok((solution, Vec::new())),
+        Err(error @ PubGrubError::NoSolution(_)) => error,
+        Err(error) => return Err(error),
+    };
+    let root_dependencies: Vec<(P, VS)> =
+        match dependency_provider.get_dependencies(&package, &version) {
+            Ok(Dependencies::Known(dependencies)) => dependencies.into_iter().collect(),
+            _ => return Err(error),
+        };
+    for relaxed_count in 1..=root_dependencies.len() {
+        // Indices of the relaxed dependencies, in increasing order.
+        let mut indices: Vec<usize> = (0..relaxed_count).collect();
+        loop {
+            let relaxed: Vec<(P, VS)> = indices
+                .iter()
+                .map(|&i| root_dependencies[i].clone())
+                .collect();
+            let relaxed_provider = RelaxedRootDependencyProvider {
+                dependency_provider,
+                root: package.clone(),
+                root_version: version.clone(),
+                relaxed: relaxed.iter().map(|(p, _)| p.clone()).collect(),
+                removed: Vec::new(),
+            };
+            match resolve(&relaxed_provider, package.clone(), version.clone()) {
+                Ok(solution) => return Ok((solution, relaxed)),
+                Err(PubGrubError::NoSolution(_)) => {}
+                Err(error) => return Err(error),
+            }
+            // Next combination of indices, in lexicographic order.
+            let last_movable = (0..relaxed_count)
+                .rev()
+                .find(|&i| indices[i] < root_dependencies.len() - relaxed_count + i);
+            match last_movable {
+                None => break,
+                Some(i) => {
+                    indices[i] += 1;
+                    for j in i + 1..relaxed_count {
+                        indices[j] = indices[j - 1] + 1;
+                    }
+                }
+            }
+        }
+    }
+    Err(error)
+}
+
+/// Collects the independent conflicts preventing the resolution
+/// of a package and version pair, instead of stopping at the first one.
+///
+/// Every time the resolution fails, the derivation tree of the failure is recorded,
+/// and the dependencies of the root package involved in it are removed
+/// before resolving again, to surface conflicts between the remaining dependencies.
+/// This stops once the resolution succeeds,
+/// or when a conflict does not involve any dependency of the root package.
+/// An empty list means that there is a solution.
+/// This is best-effort, conflicts sharing a dependency of the root package
+/// being reported as one, and costs one resolution per conflict.
+pub fn collect_conflicts<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<P, VS>,
+    package: P,
+    version: impl Into<VS::V>,
+) -> Result<Vec<DerivationTree<P, VS>>, PubGrubError<P, VS>> {
+    let version = version.into();
+    let mut conflicts = Vec::new();
+    let mut removed: Vec<P> = Vec::new();
+    loop {
+        let excised_provider = RelaxedRootDependencyProvider {
+            dependency_provider,
+            root: package.clone(),
+            root_version: version.clone(),
+            relaxed: Vec::new(),
+            removed: removed.clone(),
+        };
+        let derivation_tree = match resolve(&excised_provider, package.clone(), version.clone()) {
+            Ok(_) => return Ok(conflicts),
+            Err(PubGrubError::NoSolution(derivation_tree)) => derivation_tree,
+            Err(error) => return Err(error),
+        };
+        let mut externals = Vec::new();
+        derivation_tree.collect_externals(&mut externals);
+        let involved: Vec<P> = externals
+            .into_iter()
+            .filter_map(|external| match external {
+                // Development dependencies of the root package cannot be removed.
+                External::FromDependencyOf(p, range, dependency, _)
+                    if p == package
+                        && range.contains(&version)
+                        && !removed.contains(&dependency) =>
+                {
+                    Some(dependency)
+                }
+                _ => None,
+            })
+            .collect();
+        conflicts.push(derivation_tree);
+        if involved.is_empty() {
+            return Ok(conflicts);
+        }
+        removed.extend(involved);
+    }
+}
+
+/// Dependency provider used by [resolve_with_relaxation] and [collect_conflicts],
+/// accepting any version of some dependencies of the root package,
+/// removing some others, and delegating everything else.
+struct RelaxedRootDependencyProvider<'a, P: Package, V, DP> {
+    dependency_provider: &'a DP,
+    root: P,
+    root_version: V,
+    relaxed: Vec<P>,
+    removed: Vec<P>,
+}
+
+impl<P: Package, VS: VersionSet, DP: DependencyProvider<P, VS>> DependencyProvider<P, VS>
+    for RelaxedRootDependencyProvider<'_, P, VS::V, DP>
+{
+    fn choose_package_version<T: Borrow<P>, U: Borrow<VS>>(
+        &self,
+        potential_packages: impl Iterator<Item = (T, U)>,
+    ) -> Result<(T, Option<VS::V>), Box<dyn Error>> {
+        self.dependency_provider
+            .choose_package_version(potential_packages)
+    }
+
+    fn prioritize(&self, package: &P, range: &VS) -> u32 {
+        self.dependency_provider.prioritize(package, range)
+    }
+
+    fn score_version(&self, package: &P, version: &VS::V) -> i64 {
+        self.dependency_provider.score_version(package, version)
+    }
+
+    fn get_dependencies(
+        &self,
+        package: &P,
+        version: &VS::V,
+    ) -> Result<Dependencies<P, VS>, Box<dyn Error>> {
+        let dependencies = self
+            .dependency_provider
+            .get_dependencies(package, version)?;
+        if package != &self.root || version != &self.root_version {
+            return Ok(dependencies);
+        }
+        Ok(match dependencies {
+            Dependencies::Unknown => Dependencies::Unknown,
+            Dependencies::Known(mut constraints) => {
+                for relaxed in &self.relaxed {
+                    constraints.insert(relaxed.clone(), VS::full());
+                }
+                for removed in &self.removed {
+                    constraints.remove(removed);
+                }
+                Dependencies::Known(constraints)
+            }
+        })
+    }
+
+    fn get_dev_dependencies(
+        &self,
+        package: &P,
+        version: &VS::V,
+    ) -> Result<Dependencies<P, VS>, Box<dyn Error>> {
+        self.dependency_provider
+            .get_dev_dependencies(package, version)
+    }
+
+    fn package_exists(&self, package: &P) -> bool {
+        self.dependency_provider.package_exists(package)
+    }
+
+    fn accept_version(&self, package: &P, version: &VS::V) -> bool {
+        self.dependency_provider.accept_version(package, version)
+    }
+
+    fn is_virtual(&self, package: &P) -> bool {
+        self.dependency_provider.is_virtual(package)
+    }
+
+    fn known_incompatibilities(&self) -> Vec<Incompatibility<P, VS>> {
+        self.dependency_provider.known_incompatibilities()
+    }
+
+    fn should_cancel(&self) -> Result<(), Box<dyn Error>> {
+        self.dependency_provider.should_cancel()
+    }
+}
+
+/// Finds a set of packages satisfying the dependencies of a package and version pair,
+/// only exploring dependencies up to `max_depth` levels away from the root package.
+///
+/// The depth of a package is its shortest dependency distance from the root
+/// among the dependencies explored so far, direct dependencies of the root being at depth 1.
+/// Dependencies of packages at depth `max_depth` are ignored,
+/// so deeper packages are unconstrained and excluded from the solution,
+/// which is only a preview of the full resolution.
+///
+/// ```
+/// # use pubgrub::range::Range;
+/// # use pubgrub::solver::{resolve_with_max_depth, OfflineDependencyProvider};
+/// # use pubgrub::version::NumberVersion;
+/// #
+/// let mut dependency_provider = OfflineDependencyProvider::<&str, Range<NumberVersion>>::new();
+/// dependency_provider.add_dependencies("root", 1, vec![("menu", Range::any())]);
+/// dependency_provider.add_dependencies("menu", 1, vec![("dropdown", Range::any())]);
+/// dependency_provider.add_dependencies("dropdown", 1, vec![]);
+///
+/// let solution = resolve_with_max_depth(&dependency_provider, "root", 1, 1).unwrap();
+/// assert!(solution.contains_key("menu"));
+/// assert!(!solution.contains_key("dropdown"));
+/// ```
+pub fn resolve_with_max_depth<P: Package, VS: VersionSet>(
+    dependency_provider: &impl DependencyProvider<P, VS>,
+    package: P,
+    version: impl Into<VS::V>,
+    max_depth: usize,
+) -> Result<SelectedDependencies<P, VS::V>, PubGrubError<P, VS>> {
+    let mut depths = Map::default();
+    depths.insert(package.clone(), 0);
+    let bounded_provider = BoundedDepthDependencyProvider {
+        dependency_provider,
+        max_depth,
+        depths: RefCell::new(depths),
+    };
+    resolve(&bounded_provider, package, version)
+}
+
+/// Dependency provider used by [resolve_with_max_depth],
+/// tracking the depth of packages to drop the dependencies beyond the maximum depth,
+/// and delegating everything else.
+struct BoundedDepthDependencyProvider<'a, P: Package, DP> {
+    dependency_provider: &'a DP,
+    max_depth: usize,
+    depths: RefCell<Map<P, usize>>,
+}
+
+impl<P: Package, DP> BoundedDepthDependencyProvider<'_, P, DP> {
+    /// Keep the dependencies of a package that are within the maximum depth,
+    /// and record their depth.
+    fn bounded<VS: VersionSet>(
+        &self,
+        package: &P,
+        dependencies: Dependencies<P, VS>,
+    ) -> Dependencies<P, VS> {
+        let mut depths = self.depths.borrow_mut();
+        let depth = depths
+            .get(package)
+            .map_or(usize::MAX, |d| d.saturating_add(1));
+        match dependencies {
+            Dependencies::Known(_) if depth > self.max_depth => {
+                Dependencies::Known(DependencyConstraints::default())
+            }
+            Dependencies::Known(constraints) => {
+                for dependency in constraints.keys() {
+                    let dependency_depth = depths.entry(dependency.clone()).or_insert(depth);
+                    *dependency_depth = depth.min(*dependency_depth);
+                }
+                Dependencies::Known(constraints)
+            }
+            Dependencies::Unknown => Dependencies::Unknown,
+        }
+    }
+}
+
+impl<P: Package, VS: VersionSet, DP: DependencyProvider<P, VS>> DependencyProvider<P, VS>
+    for BoundedDepthDependencyProvider<'_, P, DP>
+{
+    fn choose_package_version<T: Borrow<P>, U: Borrow<VS>>(
+        &self,
+        potential_packages: impl Iterator<Item = (T, U)>,
+    ) -> Result<(T, Option<VS::V>), Box<dyn Error>> {
+        self.dependency_provider
+            .choose_package_version(potential_packages)
+    }
+
+    fn prioritize(&self, package: &P, range: &VS) -> u32 {
+        self.dependency_provider.prioritize(package, range)
+    }
 
-/// Main function of the library.
-/// Finds a set of packages satisfying dependency bounds for a given package + version pair.
-pub fn resolve<P: Package, V: Version>(
-    dependency_provider: &impl DependencyProvider<P, V>,
-    package: P,
-    version: impl Into<V>,
-) -> Result<SelectedDependencies<P, V>, PubGrubError<P, V>> {
-    let mut state = State::init(package.clone(), version.into());
-    let mut added_dependencies: Map<P, Set<V>> = Map::default();
-    let mut next = package;
-    loop {
-        dependency_provider
-            .should_cancel()
-            .map_err(|err| PubGrubError::ErrorInShouldCancel(err))?;
+    fn score_version(&self, package: &P, version: &VS::V) -> i64 {
+        self.dependency_provider.score_version(package, version)
+    }
 
-        state.unit_propagation(next)?;
+    fn get_dependencies(
+        &self,
+        package: &P,
+        version: &VS::V,
+    ) -> Result<Dependencies<P, VS>, Box<dyn Error>> {
+        let dependencies = self
+            .dependency_provider
+            .get_dependencies(package, version)?;
+        Ok(self.bounded(package, dependencies))
+    }
 
-        let potential_packages = state.partial_solution.potential_packages();
-        if potential_packages.is_none() {
-            drop(potential_packages);
-            // The borrow checker did not like using a match on potential_packages.
-            // This `if ... is_none ... drop` is a workaround.
-            // I believe this is a case where Polonius could help, when and if it lands in rustc.
-            return state.partial_solution.extract_solution().ok_or_else(|| {
-                PubGrubError::Failure(
-                    "How did we end up with no package to choose but no solution?".into(),
-                )
-            });
-        }
-        let decision = dependency_provider
-            .choose_package_version(potential_packages.unwrap())
-            .map_err(PubGrubError::ErrorChoosingPackageVersion)?;
-        next = decision.0.clone();
+    fn get_dev_dependencies(
+        &self,
+        package: &P,
+        version: &VS::V,
+    ) -> Result<Dependencies<P, VS>, Box<dyn Error>> {
+        let dev_dependencies = self
+            .dependency_provider
+            .get_dev_dependencies(package, version)?;
+        Ok(self.bounded(package, dev_dependencies))
+    }
 
-        // Pick the next compatible version.
-        let term_intersection = state
-            .partial_solution
-            .term_intersection_for_package(&next)
-            .expect("a package was chosen but we don't have a term.");
-        let v = match decision.1 {
-            None => {
-                let inc = Incompatibility::no_versions(next.clone(), term_intersection.clone());
-                state.add_incompatibility(inc);
-                continue;
-            }
-            Some(x) => x,
-        };
-        if !term_intersection.contains(&v) {
-            return Err(PubGrubError::ErrorChoosingPackageVersion(
-                "choose_package_version picked an incompatible version".into(),
-            ));
-        }
+    fn package_exists(&self, package: &P) -> bool {
+        self.dependency_provider.package_exists(package)
+    }
 
-        if added_dependencies
-            .entry(next.clone())
-            .or_default()
-            .insert(v.clone())
-        {
-            // Retrieve that package dependencies.
-            let p = &next;
-            let dependencies =
-                match dependency_provider
-                    .get_dependencies(&p, &v)
-                    .map_err(|err| PubGrubError::ErrorRetrievingDependencies {
-                        package: p.clone(),
-                        version: v.clone(),
-                        source: err,
-                    })? {
-                    Dependencies::Unknown => {
-                        state.add_incompatibility(Incompatibility::unavailable_dependencies(
-                            p.clone(),
-                            v.clone(),
-                        ));
-                        continue;
-                    }
-                    Dependencies::Known(x) => {
-                        if x.contains_key(&p) {
-                            return Err(PubGrubError::SelfDependency {
-                                package: p.clone(),
-                                version: v.clone(),
-                            });
-                        }
-                        if let Some((dependent, _)) = x.iter().find(|(_, r)| r == &&Range::none()) {
-                            return Err(PubGrubError::DependencyOnTheEmptySet {
-                                package: p.clone(),
-                                version: v.clone(),
-                                dependent: dependent.clone(),
-                            });
-                        }
-                        x
-                    }
-                };
+    fn accept_version(&self, package: &P, version: &VS::V) -> bool {
+        self.dependency_provider.accept_version(package, version)
+    }
 
-            // Add that package and version if the dependencies are not problematic.
-            let dep_incompats =
-                state.add_incompatibility_from_dependencies(p.clone(), v.clone(), &dependencies);
+    fn is_virtual(&self, package: &P) -> bool {
+        self.dependency_provider.is_virtual(package)
+    }
 
-            // TODO: I don't think this check can actually happen.
-            // We might want to put it under #[cfg(debug_assertions)].
-            if state.incompatibility_store[dep_incompats.clone()]
-                .iter()
-                .any(|incompat| state.is_terminal(incompat))
-            {
-                // For a dependency incompatibility to be terminal,
-                // it can only mean that root depend on not root?
-                return Err(PubGrubError::Failure(
-                    "Root package depends on itself at a different version?".into(),
-                ));
-            }
-            state.partial_solution.add_version(
-                p.clone(),
-                v,
-                dep_incompats,
-                &state.incompatibility_store,
-            );
-        } else {
-            // `dep_incompats` are already in `incompatibilities` so we know there are not satisfied
-            // terms and can add the decision directly.
-            state.partial_solution.add_decision(next.clone(), v);
-        }
+    fn known_incompatibilities(&self) -> Vec<Incompatibility<P, VS>> {
+        self.dependency_provider.known_incompatibilities()
+    }
+
+    fn should_cancel(&self) -> Result<(), Box<dyn Error>> {
+        self.dependency_provider.should_cancel()
     }
 }
 
 /// An enum used by [DependencyProvider] that holds information about package dependencies.
-/// For each [Package] there is a [Range] of concrete versions it allows as a dependency.
+/// For each [Package] there is a set of concrete versions it allows as a dependency.
 #[derive(Clone)]
-pub enum Dependencies<P: Package, V: Version> {
+pub enum Dependencies<P: Package, VS: VersionSet> {
     /// Package dependencies are unavailable.
     Unknown,
     /// Container for all available package versions.
-    Known(DependencyConstraints<P, V>),
+    Known(DependencyConstraints<P, VS>),
+}
+
+impl<P: Package, VS: VersionSet> Dependencies<P, VS> {
+    /// Start building a list of dependencies, one package at a time.
+    pub fn builder() -> DependenciesBuilder<P, VS> {
+        DependenciesBuilder {
+            dependencies: Vec::new(),
+        }
+    }
+}
+
+/// Builder of the list of dependencies of a package, as expected by
+/// [OfflineDependencyProvider::add_dependencies].
+/// Dependencies are kept in the order in which they were first required.
+///
+/// ```
+/// # use pubgrub::range::Range;
+/// # use pubgrub::solver::Dependencies;
+/// # use pubgrub::version::NumberVersion;
+/// #
+/// let dependencies = Dependencies::<&str, Range<NumberVersion>>::builder()
+///     .require("menu", Range::higher_than(1))
+///     .require("icons", Range::any())
+///     .require("menu", Range::strictly_lower_than(3))
+///     .build();
+/// assert_eq!(
+///     dependencies,
+///     vec![("menu", Range::between(1, 3)), ("icons", Range::any())]
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct DependenciesBuilder<P: Package, VS: VersionSet> {
+    dependencies: Vec<(P, VS)>,
+}
+
+impl<P: Package, VS: VersionSet> DependenciesBuilder<P, VS> {
+    /// Require a version of `package` in the given set of versions.
+    /// Requiring the same package multiple times merges the requirements,
+    /// keeping the intersection of all the given sets of versions.
+    pub fn require(mut self, package: P, versions: VS) -> Self {
+        match self.dependencies.iter_mut().find(|(p, _)| p == &package) {
+            Some((_, existing)) => *existing = existing.intersection(&versions),
+            None => self.dependencies.push((package, versions)),
+        }
+        self
+    }
+
+    /// Retrieve the list of dependencies.
+    pub fn build(self) -> Vec<(P, VS)> {
+        self.dependencies
+    }
+}
+
+impl<P: Package, VS: VersionSet + FromStr> DependenciesBuilder<P, VS> {
+    /// Require a version of `package` in the set of versions parsed from `versions`,
+    /// merging requirements like [require](Self::require).
+    ///
+    /// ```
+    /// # use pubgrub::range::{Range, RangeParseError};
+    /// # use pubgrub::solver::Dependencies;
+    /// # use pubgrub::version::{SemanticVersion, VersionParseError};
+    /// #
+    /// # fn main() -> Result<(), RangeParseError<VersionParseError>> {
+    /// let dependencies = Dependencies::<&str, Range<SemanticVersion>>::builder()
+    ///     .require_str("menu", ">=1.0.0")?
+    ///     .require("icons", Range::any())
+    ///     .build();
+    /// assert_eq!(
+    ///     dependencies,
+    ///     vec![("menu", Range::higher_than((1, 0, 0))), ("icons", Range::any())]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn require_str(self, package: P, versions: &str) -> Result<Self, VS::Err> {
+        Ok(self.require(package, versions.parse()?))
+    }
 }
 
 /// Subtype of [Dependencies] which holds information about
 /// all possible versions a given package can accept.
-/// There is a difference in semantics between an empty [Map<P, Range<V>>](crate::type_aliases::Map)
+/// There is a difference in semantics between an empty [Map<P, VS>](crate::type_aliases::Map)
 /// inside [DependencyConstraints] and [Dependencies::Unknown]:
 /// the former means the package has no dependencies and it is a known fact,
 /// while the latter means they could not be fetched by [DependencyProvider].
-pub type DependencyConstraints<P, V> = Map<P, Range<V>>;
+pub type DependencyConstraints<P, VS> = Map<P, VS>;
 
 /// Trait that allows the algorithm to retrieve available packages and their dependencies.
 /// An implementor needs to be supplied to the [resolve] function.
-pub trait DependencyProvider<P: Package, V: Version> {
+pub trait DependencyProvider<P: Package, VS: VersionSet> {
     /// [Decision making](https://github.com/dart-lang/pub/blob/master/doc/solver.md#decision-making)
     /// is the process of choosing the next package
     /// and version that will be appended to the partial solution.
@@ -244,20 +2110,116 @@ pub trait DependencyProvider<P: Package, V: Version> {
     /// A helper function [choose_package_with_fewest_versions] is provided to ease
     /// implementations of this method if you can produce an iterator
     /// of the available versions in preference order for any package.
+    /// If packages have too many versions to enumerate them all,
+    /// [choose_package_with_fewest_counted_versions] only lists them lazily
+    /// from a cheaper count of the matching versions.
     ///
     /// Note: the type `T` ensures that this returns an item from the `packages` argument.
-    fn choose_package_version<T: Borrow<P>, U: Borrow<Range<V>>>(
+    #[allow(clippy::type_complexity)]
+    fn choose_package_version<T: Borrow<P>, U: Borrow<VS>>(
         &self,
         potential_packages: impl Iterator<Item = (T, U)>,
-    ) -> Result<(T, Option<V>), Box<dyn Error>>;
+    ) -> Result<(T, Option<VS::V>), Box<dyn Error>>;
+
+    /// Priority of a package for [decision making](DependencyProvider::choose_package_version).
+    /// Every time a decision must be made, only the potential packages
+    /// with the highest priority are handed to
+    /// [choose_package_version](DependencyProvider::choose_package_version),
+    /// which then breaks ties between them.
+    ///
+    /// This is useful if you know that some packages should be decided first,
+    /// for example because they constrain most of the others.
+    /// Priorities should be a consistent total order on packages,
+    /// otherwise the solver may thrash between decisions.
+    /// If not provided, all packages share the same priority
+    /// and the decision is entirely left to
+    /// [choose_package_version](DependencyProvider::choose_package_version).
+    fn prioritize(&self, package: &P, range: &VS) -> u32 {
+        let _ = (package, range);
+        0
+    }
+
+    /// Score of a version of a package, higher scores being preferred,
+    /// for example based on stability or security ratings.
+    /// It is used when picking versions with
+    /// [choose_package_with_fewest_versions_by_score],
+    /// which selects the highest scoring version in range,
+    /// the first one in preference order in case of a tie.
+    /// If not provided, all versions have the same score,
+    /// and versions are picked in preference order.
+    fn score_version(&self, package: &P, version: &VS::V) -> i64 {
+        let _ = (package, version);
+        0
+    }
 
     /// Retrieves the package dependencies.
     /// Return [Dependencies::Unknown] if its dependencies are unknown.
+    /// During a resolution, dependencies are retrieved at most once
+    /// for a given package and version, even when backtracking leads
+    /// to selecting that version again.
     fn get_dependencies(
         &self,
         package: &P,
-        version: &V,
-    ) -> Result<Dependencies<P, V>, Box<dyn Error>>;
+        version: &VS::V,
+    ) -> Result<Dependencies<P, VS>, Box<dyn Error>>;
+
+    /// Retrieves the development dependencies of a package,
+    /// needed to build it but not by the packages depending on it.
+    /// They are only added to the dependencies of the root package of the resolution,
+    /// and ignored for all other packages.
+    /// Return [Dependencies::Unknown] if they are unknown.
+    /// If not provided, packages have no development dependencies.
+    fn get_dev_dependencies(
+        &self,
+        package: &P,
+        version: &VS::V,
+    ) -> Result<Dependencies<P, VS>, Box<dyn Error>> {
+        let _ = (package, version);
+        Ok(Dependencies::Known(DependencyConstraints::default()))
+    }
+
+    /// Check if a package exists at all, independently of its available versions.
+    /// When no version of a package required by the root package can be picked,
+    /// the resolution fails with [PubGrubError::PackageNotFound]
+    /// if that package does not exist, for example because of a typo in its name,
+    /// instead of looking for another solution.
+    /// Missing packages only required by some versions of other packages
+    /// make those versions unavailable, like any other conflict.
+    /// If not provided, all packages are considered to exist.
+    fn package_exists(&self, package: &P) -> bool {
+        let _ = package;
+        true
+    }
+
+    /// Last chance to reject a version picked by
+    /// [choose_package_version](DependencyProvider::choose_package_version),
+    /// right before the solver commits to it.
+    /// A rejected version is treated as unavailable
+    /// and the solver keeps looking for another solution.
+    /// If not provided, all picked versions are accepted.
+    fn accept_version(&self, package: &P, version: &VS::V) -> bool {
+        let _ = (package, version);
+        true
+    }
+
+    /// Check if a package is virtual, only added to model requirements
+    /// that cannot be expressed with plain dependencies,
+    /// like the ones of [OfflineDependencyProvider::require_any_of].
+    /// Virtual packages are removed from the solution,
+    /// but still appear in failure reports.
+    /// If not provided, no package is virtual.
+    fn is_virtual(&self, package: &P) -> bool {
+        let _ = package;
+        false
+    }
+
+    /// Incompatibilities known before solving, such as packages that can never coexist.
+    /// They are added to the solver state before the first decision,
+    /// and treated exactly like the ones obtained from dependencies.
+    /// If not provided, no incompatibility is known in advance.
+    fn known_incompatibilities(&self) -> Vec<Incompatibility<P, VS>> {
+        Vec::new()
+    }
 
     /// This is called fairly regularly during the resolution,
     /// if it returns an Err then resolution will be terminated.
@@ -276,62 +2238,333 @@ pub trait DependencyProvider<P: Package, V: Version> {
 /// The helper finds the package from the `packages` argument with the fewest versions from
 /// `list_available_versions` contained in the constraints. Then takes that package and finds the
 /// first version contained in the constraints.
-pub fn choose_package_with_fewest_versions<P: Package, V: Version, T, U, I, F>(
+/// Among packages with the same number of versions,
+/// the first one from `potential_packages` is chosen.
+pub fn choose_package_with_fewest_versions<P: Package, VS: VersionSet, T, U, I, F>(
     list_available_versions: F,
     potential_packages: impl Iterator<Item = (T, U)>,
-) -> (T, Option<V>)
+) -> (T, Option<VS::V>)
 where
     T: Borrow<P>,
-    U: Borrow<Range<V>>,
-    I: Iterator<Item = V>,
+    U: Borrow<VS>,
+    I: Iterator<Item = VS::V>,
     F: Fn(&P) -> I,
 {
-    let count_valid = |(p, range): &(T, U)| {
-        list_available_versions(p.borrow())
-            .filter(|v| range.borrow().contains(v.borrow()))
-            .count()
-    };
-    let (pkg, range) = potential_packages
-        .min_by_key(count_valid)
+    let (pkg, range) = fewest_versions_package(&list_available_versions, potential_packages);
+    let version =
+        list_available_versions(pkg.borrow()).find(|v| range.borrow().contains(v.borrow()));
+    (pkg, version)
+}
+
+/// Same as [choose_package_with_fewest_versions],
+/// except that packages with the same number of versions are ordered by [Ord],
+/// the smallest one being chosen,
+/// so the decision does not depend on the order of `potential_packages`.
+pub fn choose_package_with_fewest_versions_by_package_order<
+    P: Package + Ord,
+    VS: VersionSet,
+    T,
+    U,
+    I,
+    F,
+>(
+    list_available_versions: F,
+    potential_packages: impl Iterator<Item = (T, U)>,
+) -> (T, Option<VS::V>)
+where
+    T: Borrow<P>,
+    U: Borrow<VS>,
+    I: Iterator<Item = VS::V>,
+    F: Fn(&P) -> I,
+{
+    let mut potential_packages: Vec<(T, U)> = potential_packages.collect();
+    potential_packages.sort_by(|(p1, _), (p2, _)| p1.borrow().cmp(p2.borrow()));
+    choose_package_with_fewest_versions(list_available_versions, potential_packages.into_iter())
+}
+
+/// Same as [choose_package_with_fewest_versions],
+/// except that the version picked is the one in range with the highest `score`,
+/// the first one from `list_available_versions` in case of a tie.
+pub fn choose_package_with_fewest_versions_by_score<P: Package, VS: VersionSet, T, U, I, F, S>(
+    list_available_versions: F,
+    score: S,
+    potential_packages: impl Iterator<Item = (T, U)>,
+) -> (T, Option<VS::V>)
+where
+    T: Borrow<P>,
+    U: Borrow<VS>,
+    I: Iterator<Item = VS::V>,
+    F: Fn(&P) -> I,
+    S: Fn(&P, &VS::V) -> i64,
+{
+    let (pkg, range) = fewest_versions_package(&list_available_versions, potential_packages);
+    let mut best: Option<(i64, VS::V)> = None;
+    for v in list_available_versions(pkg.borrow()).filter(|v| range.borrow().contains(v)) {
+        let v_score = score(pkg.borrow(), &v);
+        match &best {
+            Some((best_score, _)) if v_score <= *best_score => {}
+            _ => best = Some((v_score, v)),
+        }
+    }
+    (pkg, best.map(|(_, v)| v))
+}
+
+/// Same as [choose_package_with_fewest_versions],
+/// except that the number of versions of a package contained in its constraints
+/// is given by `count_matching_versions` instead of being counted
+/// from `list_available_versions`, which may be an estimate.
+/// Only the versions of the chosen package are then listed,
+/// up to the first one contained in its constraints,
+/// so packages with many versions never have to be fully enumerated.
+pub fn choose_package_with_fewest_counted_versions<P: Package, VS: VersionSet, T, U, I, F, C>(
+    list_available_versions: F,
+    count_matching_versions: C,
+    potential_packages: impl Iterator<Item = (T, U)>,
+) -> (T, Option<VS::V>)
+where
+    T: Borrow<P>,
+    U: Borrow<VS>,
+    I: Iterator<Item = VS::V>,
+    F: Fn(&P) -> I,
+    C: Fn(&P, &VS) -> usize,
+{
+    let (pkg, range, _) = rank_packages_by_count(&count_matching_versions, potential_packages)
+        .into_iter()
+        .next()
         .expect("potential_packages gave us an empty iterator");
     let version =
         list_available_versions(pkg.borrow()).find(|v| range.borrow().contains(v.borrow()));
     (pkg, version)
 }
 
+/// Package with the fewest versions contained in its constraints,
+/// the first one in case of a tie.
+fn fewest_versions_package<P, VS: VersionSet, T, U, I, F>(
+    list_available_versions: &F,
+    potential_packages: impl Iterator<Item = (T, U)>,
+) -> (T, U)
+where
+    T: Borrow<P>,
+    U: Borrow<VS>,
+    I: Iterator<Item = VS::V>,
+    F: Fn(&P) -> I,
+{
+    let (p, range, _) =
+        rank_packages_by_fewest_versions(list_available_versions, potential_packages)
+            .into_iter()
+            .next()
+            .expect("potential_packages gave us an empty iterator");
+    (p, range)
+}
+
+/// Ranking of packages used by [choose_package_with_fewest_versions]:
+/// every package with the number of versions from `list_available_versions`
+/// contained in its constraints, sorted by increasing count,
+/// packages with the same count keeping their order in `potential_packages`.
+/// The first one is the package that helper would choose.
+pub(crate) fn rank_packages_by_fewest_versions<P, VS: VersionSet, T, U, I, F>(
+    list_available_versions: &F,
+    potential_packages: impl Iterator<Item = (T, U)>,
+) -> Vec<(T, U, usize)>
+where
+    T: Borrow<P>,
+    U: Borrow<VS>,
+    I: Iterator<Item = VS::V>,
+    F: Fn(&P) -> I,
+{
+    let count_matching_versions = |p: &P, range: &VS| {
+        list_available_versions(p)
+            .filter(|v| range.contains(v))
+            .count()
+    };
+    rank_packages_by_count(&count_matching_versions, potential_packages)
+}
+
+/// Every package with its count from `count_matching_versions`,
+/// sorted by increasing count, packages with the same count keeping their order.
+fn rank_packages_by_count<P, VS: VersionSet, T, U, C>(
+    count_matching_versions: &C,
+    potential_packages: impl Iterator<Item = (T, U)>,
+) -> Vec<(T, U, usize)>
+where
+    T: Borrow<P>,
+    U: Borrow<VS>,
+    C: Fn(&P, &VS) -> usize,
+{
+    let mut ranking: Vec<_> = potential_packages
+        .map(|(p, range)| {
+            let count = count_matching_versions(p.borrow(), range.borrow());
+            (p, range, count)
+        })
+        .collect();
+    ranking.sort_by_key(|(_, _, count)| *count);
+    ranking
+}
+
+/// Versions preferred by [OfflineDependencyProvider] among the ones in range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ResolutionMode {
+    /// Pick the highest version in range.
+    #[default]
+    Latest,
+    /// Pick the lowest version in range,
+    /// useful to check that the lower bounds of dependencies are still correct.
+    Minimal,
+}
+
 /// A basic implementation of [DependencyProvider].
+///
+/// It is serialized as a struct of its dependencies and other registered data.
+/// Providers serialized as the map of their dependencies only,
+/// before they could hold anything else, are read with `from_legacy`
+/// when the `serde` feature is enabled.
 #[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-#[cfg_attr(feature = "serde", serde(transparent))]
-pub struct OfflineDependencyProvider<P: Package, V: Version> {
-    dependencies: Map<P, BTreeMap<V, DependencyConstraints<P, V>>>,
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "VS::V: serde::Serialize, VS: serde::Serialize, P: serde::Serialize",
+        deserialize = "VS::V: serde::Deserialize<'de>, VS: serde::Deserialize<'de>, P: serde::Deserialize<'de>",
+    ))
+)]
+pub struct OfflineDependencyProvider<P: Package, VS: VersionSet> {
+    dependencies: Map<P, BTreeMap<VS::V, DependencyConstraints<P, VS>>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    dev_dependencies: Map<P, BTreeMap<VS::V, DependencyConstraints<P, VS>>>,
+    #[cfg_attr(feature = "serde", serde(default, with = "serde_incompatibilities"))]
+    incompatibilities: Vec<Incompatibility<P, VS>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    lockstep_groups: Vec<Vec<P>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    resolution_mode: ResolutionMode,
+    /// Requirements of at least one of several alternatives, by virtual package.
+    #[cfg_attr(feature = "serde", serde(default))]
+    any_of: Map<P, AnyOf<P, VS::V>>,
+}
+
+/// Requirement of at least one of several alternatives
+/// by a package version, through a virtual package.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct AnyOf<P, V: Ord> {
+    /// Package and version requiring one of the alternatives.
+    required_by: (P, V),
+    /// Alternatives by version of the virtual package.
+    alternatives: BTreeMap<V, P>,
+}
+
+#[cfg(feature = "serde")]
+impl<P: Package, VS: VersionSet> OfflineDependencyProvider<P, VS> {
+    /// Deserializes a provider serialized in the previous format,
+    /// which was the map of its dependencies only.
+    ///
+    /// ```
+    /// # use pubgrub::range::Range;
+    /// # use pubgrub::solver::OfflineDependencyProvider;
+    /// # use pubgrub::version::NumberVersion;
+    /// #
+    /// let mut deserializer = serde_json::Deserializer::from_str(r#"{"dependencies":{"1":{}}}"#);
+    /// let dependency_provider =
+    ///     OfflineDependencyProvider::<&str, Range<NumberVersion>>::from_legacy(&mut deserializer)
+    ///         .unwrap();
+    /// assert_eq!(dependency_provider.packages().collect::<Vec<_>>(), [&"dependencies"]);
+    /// ```
+    pub fn from_legacy<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+    where
+        P: serde::Deserialize<'de>,
+        VS: serde::Deserialize<'de>,
+        VS::V: serde::Deserialize<'de>,
+    {
+        Ok(Self {
+            dependencies: serde::Deserialize::deserialize(deserializer)?,
+            ..Self::new()
+        })
+    }
+}
+
+/// Incompatibilities of [OfflineDependencyProvider] are serialized as their external reason.
+#[cfg(feature = "serde")]
+mod serde_incompatibilities {
+    use super::*;
+    use serde::ser::{Error as _, SerializeSeq};
+
+    #[allow(clippy::ptr_arg)]
+    pub fn serialize<P, VS, S>(
+        incompatibilities: &Vec<Incompatibility<P, VS>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        P: Package + serde::Serialize,
+        VS: VersionSet + serde::Serialize,
+        VS::V: serde::Serialize,
+        S: serde::Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(incompatibilities.len()))?;
+        for incompat in incompatibilities {
+            let external = incompat.external().ok_or_else(|| {
+                S::Error::custom("derived incompatibilities cannot be serialized")
+            })?;
+            seq.serialize_element(&external)?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, P, VS, D>(
+        deserializer: D,
+    ) -> Result<Vec<Incompatibility<P, VS>>, D::Error>
+    where
+        P: Package + serde::Deserialize<'de>,
+        VS: VersionSet + serde::Deserialize<'de>,
+        VS::V: serde::Deserialize<'de>,
+        D: serde::Deserializer<'de>,
+    {
+        let externals: Vec<External<P, VS>> = serde::Deserialize::deserialize(deserializer)?;
+        Ok(externals
+            .into_iter()
+            .map(Incompatibility::from_external)
+            .collect())
+    }
 }
 
-impl<P: Package, V: Version> OfflineDependencyProvider<P, V> {
+impl<P: Package, VS: VersionSet> OfflineDependencyProvider<P, VS> {
     /// Creates an empty OfflineDependencyProvider with no dependencies.
     pub fn new() -> Self {
         Self {
             dependencies: Map::default(),
+            dev_dependencies: Map::default(),
+            incompatibilities: Vec::new(),
+            lockstep_groups: Vec::new(),
+            resolution_mode: ResolutionMode::Latest,
+            any_of: Map::default(),
         }
     }
 
+    /// Sets whether the highest or the lowest versions in range are picked,
+    /// the highest ones being picked by default.
+    pub fn set_resolution_mode(&mut self, resolution_mode: ResolutionMode) {
+        self.resolution_mode = resolution_mode;
+    }
+
     /// Registers the dependencies of a package and version pair.
     /// Dependencies must be added with a single call to
     /// [add_dependencies](OfflineDependencyProvider::add_dependencies).
     /// All subsequent calls to
     /// [add_dependencies](OfflineDependencyProvider::add_dependencies) for a given
     /// package version pair will replace the dependencies by the new ones.
+    /// A package appearing multiple times in the same call is required in the intersection
+    /// of all its given sets of versions, like with [Dependencies::builder].
     ///
     /// The API does not allow to add dependencies one at a time to uphold an assumption that
     /// [OfflineDependencyProvider.get_dependencies(p, v)](OfflineDependencyProvider::get_dependencies)
     /// provides all dependencies of a given package (p) and version (v) pair.
-    pub fn add_dependencies<I: IntoIterator<Item = (P, Range<V>)>>(
+    pub fn add_dependencies<I: IntoIterator<Item = (P, VS)>>(
         &mut self,
         package: P,
-        version: impl Into<V>,
+        version: impl Into<VS::V>,
         dependencies: I,
     ) {
-        let package_deps = dependencies.into_iter().collect();
+        let package_deps = intersect_dependencies(dependencies);
         let v = version.into();
         *self
             .dependencies
@@ -341,41 +2574,223 @@ impl<P: Package, V: Version> OfflineDependencyProvider<P, V> {
             .or_default() = package_deps;
     }
 
-    /// Lists packages that have been saved.
+    /// Registers the development dependencies of a package and version pair,
+    /// only required when that package is the root of the resolution.
+    /// Like [add_dependencies](OfflineDependencyProvider::add_dependencies),
+    /// subsequent calls replace the development dependencies by the new ones,
+    /// and the sets of versions of a package appearing multiple times are intersected.
+    pub fn add_dev_dependencies<I: IntoIterator<Item = (P, VS)>>(
+        &mut self,
+        package: P,
+        version: impl Into<VS::V>,
+        dev_dependencies: I,
+    ) {
+        *self
+            .dev_dependencies
+            .entry(package)
+            .or_default()
+            .entry(version.into())
+            .or_default() = intersect_dependencies(dev_dependencies);
+    }
+
+    /// Registers an incompatibility known before solving.
+    /// It will be respected by the solver exactly like the ones
+    /// obtained from dependencies, and appear in failure reports.
+    /// It is serialized with the provider as its [External] reason,
+    /// so serializing fails for incompatibilities derived during a resolution.
+    pub fn add_incompatibility(&mut self, incompatibility: Incompatibility<P, VS>) {
+        self.incompatibilities.push(incompatibility);
+    }
+
+    /// Forbids the selection of both `package1` with a version in `versions1`
+    /// and `package2` with a version in `versions2`.
+    /// This is useful for rules that cannot be expressed as dependencies,
+    /// like two packages that can never coexist.
+    pub fn forbid_combination(&mut self, package1: P, versions1: VS, package2: P, versions2: VS) {
+        self.add_incompatibility(Incompatibility::forbidden_combination(
+            package1, versions1, package2, versions2,
+        ));
+    }
+
+    /// Forbids the selection of any version of `forbidden`
+    /// when `package` is selected at `version`, typically the root package,
+    /// even if other packages depend on it.
+    /// Failures caused by this rule are reported with
+    /// [External::Forbidden](crate::report::External::Forbidden).
+    pub fn forbid_package(&mut self, package: P, version: impl Into<VS::V>, forbidden: P) {
+        self.add_incompatibility(Incompatibility::forbidden_package(
+            package,
+            version.into(),
+            forbidden,
+        ));
+    }
+
+    /// Makes `package` at `version` depend on `dependency` in `dependency_range`,
+    /// but only when `condition` is selected in `condition_range`,
+    /// such as "if a is at >=2.0, then also depend on b".
+    /// The condition does not pull `condition` into the solution by itself.
+    /// Failures caused by this dependency are reported with
+    /// [External::ConditionalDependency](crate::report::External::ConditionalDependency).
+    pub fn add_conditional_dependency(
+        &mut self,
+        package: P,
+        version: impl Into<VS::V>,
+        condition: (P, VS),
+        dependency: (P, VS),
+    ) {
+        self.add_incompatibility(Incompatibility::conditional_dependency(
+            package,
+            version.into(),
+            condition,
+            dependency,
+        ));
+    }
+
+    /// Requires all the `members` of a group to be selected at the same version,
+    /// like a family of packages released together.
+    /// Members are not pulled into the solution by the group itself.
+    /// The group is turned into incompatibilities between each version of a member
+    /// and the other versions of every other member when solving,
+    /// and failures caused by it are reported with
+    /// [External::Lockstep](crate::report::External::Lockstep).
+    pub fn add_version_lockstep_group(&mut self, members: &[P]) {
+        self.lockstep_groups.push(members.to_vec());
+    }
+
+    /// Lists packages that have been saved,
+    /// including the virtual packages of [require_any_of](Self::require_any_of).
     pub fn packages(&self) -> impl Iterator<Item = &P> {
-        self.dependencies.keys()
+        self.dependencies.keys().chain(self.any_of.keys())
     }
 
     /// Lists versions of saved packages in sorted order.
     /// Returns [None] if no information is available regarding that package.
-    pub fn versions(&self, package: &P) -> Option<impl Iterator<Item = &V>> {
-        self.dependencies.get(package).map(|k| k.keys())
+    pub fn versions(&self, package: &P) -> Option<impl DoubleEndedIterator<Item = &VS::V>> {
+        let versions = self.dependencies.get(package).map(|k| k.keys());
+        let alternatives = self.any_of.get(package).map(|a| a.alternatives.keys());
+        if versions.is_none() && alternatives.is_none() {
+            return None;
+        }
+        Some(
+            versions
+                .into_iter()
+                .flatten()
+                .chain(alternatives.into_iter().flatten()),
+        )
     }
 
     /// Lists dependencies of a given package and version.
     /// Returns [None] if no information is available regarding that package and version pair.
-    fn dependencies(&self, package: &P, version: &V) -> Option<DependencyConstraints<P, V>> {
-        self.dependencies.get(package)?.get(version).cloned()
+    pub fn dependencies(
+        &self,
+        package: &P,
+        version: &VS::V,
+    ) -> Option<&DependencyConstraints<P, VS>> {
+        self.dependencies.get(package)?.get(version)
+    }
+
+    /// Check that all dependencies refer to saved packages.
+    /// Otherwise, returns the dangling dependencies,
+    /// as the package, version and the unknown dependency.
+    #[allow(clippy::type_complexity)]
+    pub fn validate(&self) -> Result<(), Vec<(&P, &VS::V, &P)>> {
+        let mut dangling = Vec::new();
+        for (p, versions) in &self.dependencies {
+            for (v, deps) in versions {
+                for dep in deps.keys() {
+                    if !self.dependencies.contains_key(dep) {
+                        dangling.push((p, v, dep));
+                    }
+                }
+            }
+        }
+        if dangling.is_empty() {
+            Ok(())
+        } else {
+            Err(dangling)
+        }
+    }
+}
+
+impl<P: Package, VS: VersionSet> OfflineDependencyProvider<P, VS>
+where
+    VS::V: Version,
+{
+    /// Requires at least one of the `alternatives`, in any version,
+    /// when `package` is selected at `version`, typically the root package.
+    /// This cannot be expressed with plain dependencies, which all have to be satisfied,
+    /// so the requirement goes through `virtual_package`, a package added for that purpose:
+    /// `package` at `version` depends on `virtual_package`,
+    /// and each version of `virtual_package`, starting from the [lowest](Version::lowest),
+    /// depends on one of the alternatives, in order.
+    /// The solver then picks one of those versions like for any other package.
+    /// `virtual_package` is [virtual](DependencyProvider::is_virtual),
+    /// so it is removed from the solution, but appears in failure reports,
+    /// where it should be recognizable, like `AnyOf#1`.
+    ///
+    /// The requirement is kept apart from the dependencies of `package` at `version`,
+    /// which can be registered before or after it.
+    ///
+    /// ```
+    /// # use pubgrub::range::Range;
+    /// # use pubgrub::solver::{resolve, OfflineDependencyProvider};
+    /// # use pubgrub::version::NumberVersion;
+    /// #
+    /// let mut dependency_provider = OfflineDependencyProvider::<&str, Range<NumberVersion>>::new();
+    /// dependency_provider.require_any_of("root", 1, "AnyOf#1", &["postgres", "sqlite"]);
+    /// dependency_provider.add_dependencies("root", 1, vec![]);
+    /// dependency_provider.add_dependencies("sqlite", 3, vec![]);
+    ///
+    /// let solution = resolve(&dependency_provider, "root", 1).unwrap();
+    /// assert_eq!(solution["sqlite"], 3.into());
+    /// assert!(!solution.contains_key("AnyOf#1"));
+    /// ```
+    pub fn require_any_of(
+        &mut self,
+        package: P,
+        version: impl Into<VS::V>,
+        virtual_package: P,
+        alternatives: &[P],
+    ) {
+        let mut alternative_version = VS::V::lowest();
+        let mut versions = BTreeMap::new();
+        for alternative in alternatives {
+            versions.insert(alternative_version.clone(), alternative.clone());
+            alternative_version = alternative_version.bump();
+        }
+        self.any_of.insert(
+            virtual_package,
+            AnyOf {
+                required_by: (package, version.into()),
+                alternatives: versions,
+            },
+        );
     }
 }
 
 /// An implementation of [DependencyProvider] that
 /// contains all dependency information available in memory.
 /// Packages are picked with the fewest versions contained in the constraints first.
-/// Versions are picked with the newest versions first.
-impl<P: Package, V: Version> DependencyProvider<P, V> for OfflineDependencyProvider<P, V> {
-    fn choose_package_version<T: Borrow<P>, U: Borrow<Range<V>>>(
+/// Versions are picked with the newest versions first,
+/// or the oldest ones with [ResolutionMode::Minimal].
+/// Its [score](DependencyProvider::score_version) being the default one,
+/// versions are not scored: the first one in range is picked.
+/// Packages with the same number of versions are picked in [Ord] order,
+/// so decisions do not depend on the order in which packages were added.
+impl<P: Package + Ord, VS: VersionSet> DependencyProvider<P, VS>
+    for OfflineDependencyProvider<P, VS>
+{
+    fn choose_package_version<T: Borrow<P>, U: Borrow<VS>>(
         &self,
         potential_packages: impl Iterator<Item = (T, U)>,
-    ) -> Result<(T, Option<V>), Box<dyn Error>> {
-        Ok(choose_package_with_fewest_versions(
-            |p| {
-                self.dependencies
-                    .get(p)
-                    .into_iter()
-                    .flat_map(|k| k.keys())
-                    .rev()
-                    .cloned()
+    ) -> Result<(T, Option<VS::V>), Box<dyn Error>> {
+        Ok(choose_package_with_fewest_versions_by_package_order(
+            |p| -> Box<dyn Iterator<Item = VS::V>> {
+                let versions = self.versions(p).into_iter().flatten().cloned();
+                match self.resolution_mode {
+                    ResolutionMode::Latest => Box::new(versions.rev()),
+                    ResolutionMode::Minimal => Box::new(versions),
+                }
             },
             potential_packages,
         ))
@@ -384,11 +2799,70 @@ impl<P: Package, V: Version> DependencyProvider<P, V> for OfflineDependencyProvi
     fn get_dependencies(
         &self,
         package: &P,
-        version: &V,
-    ) -> Result<Dependencies<P, V>, Box<dyn Error>> {
-        Ok(match self.dependencies(package, version) {
-            None => Dependencies::Unknown,
-            Some(dependencies) => Dependencies::Known(dependencies),
-        })
+        version: &VS::V,
+    ) -> Result<Dependencies<P, VS>, Box<dyn Error>> {
+        if let Some(any_of) = self.any_of.get(package) {
+            return Ok(match any_of.alternatives.get(version) {
+                None => Dependencies::Unknown,
+                Some(alternative) => Dependencies::Known(
+                    core::iter::once((alternative.clone(), VS::full())).collect(),
+                ),
+            });
+        }
+        let Some(dependencies) = self.dependencies(package, version) else {
+            return Ok(Dependencies::Unknown);
+        };
+        let mut dependencies = dependencies.clone();
+        for (virtual_package, any_of) in &self.any_of {
+            if any_of.required_by.0 == *package && any_of.required_by.1 == *version {
+                dependencies.insert(virtual_package.clone(), VS::full());
+            }
+        }
+        Ok(Dependencies::Known(dependencies))
+    }
+
+    fn get_dev_dependencies(
+        &self,
+        package: &P,
+        version: &VS::V,
+    ) -> Result<Dependencies<P, VS>, Box<dyn Error>> {
+        let dev_dependencies = self
+            .dev_dependencies
+            .get(package)
+            .and_then(|versions| versions.get(version));
+        Ok(Dependencies::Known(
+            dev_dependencies.cloned().unwrap_or_default(),
+        ))
+    }
+
+    fn package_exists(&self, package: &P) -> bool {
+        self.dependencies.contains_key(package) || self.any_of.contains_key(package)
+    }
+
+    fn is_virtual(&self, package: &P) -> bool {
+        self.any_of.contains_key(package)
+    }
+
+    fn known_incompatibilities(&self) -> Vec<Incompatibility<P, VS>> {
+        let mut incompatibilities = self.incompatibilities.clone();
+        for members in &self.lockstep_groups {
+            for (i, package) in members.iter().enumerate() {
+                let Some(versions) = self.dependencies.get(package) else {
+                    continue;
+                };
+                // Forbidding the other versions of the later members is enough,
+                // since lockstep incompatibilities are symmetric.
+                for other in members[i + 1..].iter().filter(|other| *other != package) {
+                    for version in versions.keys() {
+                        incompatibilities.push(Incompatibility::lockstep(
+                            package.clone(),
+                            version.clone(),
+                            other.clone(),
+                        ));
+                    }
+                }
+            }
+        }
+        incompatibilities
     }
 }
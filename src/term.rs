@@ -3,38 +3,98 @@
 //! A term is the fundamental unit of operation of the PubGrub algorithm.
 //! It is a positive or negative expression regarding a set of versions.
 
-use crate::range::Range;
-use crate::version::Version;
-use std::fmt;
+use crate::version_set::VersionSet;
+use alloc::format;
+use alloc::string::String;
+use core::fmt::{self, Display};
 
 ///  A positive or negative expression regarding a set of versions.
 #[derive(Debug, Clone, Eq, PartialEq)]
-pub enum Term<V: Version> {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Term<VS: VersionSet> {
     /// For example, "1.0.0 <= v < 2.0.0" is a positive expression
     /// that is evaluated true if a version is selected
     /// and comprised between version 1.0.0 and version 2.0.0.
-    Positive(Range<V>),
+    Positive(VS),
     /// The term "not v < 3.0.0" is a negative expression
     /// that is evaluated true if a version is selected >= 3.0.0
     /// or if no version is selected at all.
-    Negative(Range<V>),
+    Negative(VS),
+}
+
+/// Classification of a [Term] in terms of the selection it allows,
+/// independently of its positive or negative encoding.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Constraint<VS: VersionSet> {
+    /// A version must be selected, and it must be in that set.
+    Required(VS),
+    /// If a version is selected, it must not be in that set.
+    Forbidden(VS),
+    /// Any version may be selected, or none at all.
+    Any,
+    /// No assignment can ever satisfy this constraint.
+    Empty,
 }
 
 /// Base methods.
-impl<V: Version> Term<V> {
+impl<VS: VersionSet> Term<VS> {
+    /// A positive term, satisfied if a version in `set` is selected.
+    pub fn positive(set: VS) -> Self {
+        Self::Positive(set)
+    }
+
+    /// A negative term, satisfied if no version in `set` is selected,
+    /// including if no version is selected at all.
+    pub fn negative(set: VS) -> Self {
+        Self::Negative(set)
+    }
+
+    /// Set of versions of a positive term, or [None] for a negative term.
+    pub fn as_positive(&self) -> Option<&VS> {
+        match self {
+            Self::Positive(set) => Some(set),
+            Self::Negative(_) => None,
+        }
+    }
+
+    /// Set of versions of a negative term, or [None] for a positive term.
+    pub fn as_negative(&self) -> Option<&VS> {
+        match self {
+            Self::Positive(_) => None,
+            Self::Negative(set) => Some(set),
+        }
+    }
+
+    /// Set of versions of this term, and whether the term is positive.
+    ///
+    /// ```
+    /// # use pubgrub::range::Range;
+    /// # use pubgrub::term::Term;
+    /// # use pubgrub::version::NumberVersion;
+    /// #
+    /// let range = Range::<NumberVersion>::between(1, 3);
+    /// assert_eq!(Term::negative(range.clone()).range(), (&range, false));
+    /// ```
+    pub fn range(&self) -> (&VS, bool) {
+        match self {
+            Self::Positive(set) => (set, true),
+            Self::Negative(set) => (set, false),
+        }
+    }
+
     /// A term that is always true.
     pub(crate) fn any() -> Self {
-        Self::Negative(Range::none())
+        Self::Negative(VS::empty())
     }
 
     /// A term that is never true.
     pub(crate) fn empty() -> Self {
-        Self::Positive(Range::none())
+        Self::Positive(VS::empty())
     }
 
     /// A positive term containing exactly that version.
-    pub(crate) fn exact(version: V) -> Self {
-        Self::Positive(Range::exact(version))
+    pub(crate) fn exact(version: VS::V) -> Self {
+        Self::Positive(VS::singleton(version))
     }
 
     /// Simply check if a term is positive.
@@ -56,16 +116,82 @@ impl<V: Version> Term<V> {
     }
 
     /// Evaluate a term regarding a given choice of version.
-    pub(crate) fn contains(&self, v: &V) -> bool {
+    pub(crate) fn contains(&self, v: &VS::V) -> bool {
         match self {
             Self::Positive(range) => range.contains(v),
             Self::Negative(range) => !(range.contains(v)),
         }
     }
 
+    /// Classify this term by the selection it allows.
+    ///
+    /// ```
+    /// # use pubgrub::range::Range;
+    /// # use pubgrub::term::{Constraint, Term};
+    /// # use pubgrub::version::NumberVersion;
+    /// #
+    /// let range = Range::<NumberVersion>::between(1, 3);
+    /// assert_eq!(
+    ///     Term::Positive(range.clone()).constraint(),
+    ///     Constraint::Required(range.clone())
+    /// );
+    /// assert_eq!(
+    ///     Term::Negative(range.clone()).constraint(),
+    ///     Constraint::Forbidden(range)
+    /// );
+    /// assert_eq!(Term::Negative(Range::<NumberVersion>::none()).constraint(), Constraint::Any);
+    /// assert_eq!(Term::Positive(Range::<NumberVersion>::none()).constraint(), Constraint::Empty);
+    /// ```
+    pub fn constraint(&self) -> Constraint<VS> {
+        match self {
+            Self::Positive(set) if set.is_empty() => Constraint::Empty,
+            Self::Positive(set) => Constraint::Required(set.clone()),
+            Self::Negative(set) if set.is_empty() => Constraint::Any,
+            Self::Negative(set) => Constraint::Forbidden(set.clone()),
+        }
+    }
+
+    /// Check if this term forces the selection of a version,
+    /// which is the case of non-empty positive terms.
+    pub fn is_selection_mandatory(&self) -> bool {
+        matches!(self, Self::Positive(set) if !set.is_empty())
+    }
+
+    /// Describe this term as a requirement on the given package,
+    /// in a phrasing more suitable for end users than the [Display] implementation.
+    ///
+    /// ```
+    /// # use pubgrub::range::Range;
+    /// # use pubgrub::term::Term;
+    /// # use pubgrub::version::NumberVersion;
+    /// #
+    /// let range = Range::<NumberVersion>::between(1, 3);
+    /// assert_eq!(Term::Positive(range.clone()).describe(&"foo"), "foo must be 1 <= v < 3");
+    /// assert_eq!(Term::Negative(range).describe(&"foo"), "foo must not be 1 <= v < 3");
+    /// ```
+    pub fn describe(&self, package: &impl Display) -> String {
+        match self.constraint() {
+            Constraint::Required(set) => format!("{} must be {}", package, set),
+            Constraint::Forbidden(set) if set == VS::full() => {
+                format!("{} must be absent", package)
+            }
+            Constraint::Forbidden(set) => format!("{} must not be {}", package, set),
+            Constraint::Any => format!("{} may be absent", package),
+            Constraint::Empty => format!("{} can never be satisfied", package),
+        }
+    }
+
+    /// Check if this term is satisfied when no version of the package is selected,
+    /// which is the case of negative terms.
+    /// This distinguishes an optional package from one that is required at some version,
+    /// even when both allow any version to be selected.
+    pub fn allows_absence(&self) -> bool {
+        !self.is_positive()
+    }
+
     /// Unwrap the range contains in a positive term.
     /// Will panic if used on a negative range.
-    pub(crate) fn unwrap_positive(&self) -> &Range<V> {
+    pub(crate) fn unwrap_positive(&self) -> &VS {
         match self {
             Self::Positive(range) => range,
             _ => panic!("Negative term cannot unwrap positive range"),
@@ -74,32 +200,44 @@ impl<V: Version> Term<V> {
 }
 
 /// Set operations with terms.
-impl<V: Version> Term<V> {
+impl<VS: VersionSet> Term<VS> {
     /// Compute the intersection of two terms.
     /// If at least one term is positive, the intersection is also positive.
-    pub(crate) fn intersection(&self, other: &Term<V>) -> Term<V> {
+    pub(crate) fn intersection(&self, other: &Self) -> Self {
         match (self, other) {
             (Self::Positive(r1), Self::Positive(r2)) => Self::Positive(r1.intersection(r2)),
             (Self::Positive(r1), Self::Negative(r2)) => {
-                Self::Positive(r1.intersection(&r2.negate()))
+                Self::Positive(r1.intersection(&r2.complement()))
             }
             (Self::Negative(r1), Self::Positive(r2)) => {
-                Self::Positive(r1.negate().intersection(r2))
+                Self::Positive(r1.complement().intersection(r2))
             }
             (Self::Negative(r1), Self::Negative(r2)) => Self::Negative(r1.union(r2)),
         }
     }
 
+    /// Check if the intersection of two terms is the empty term,
+    /// without computing that intersection.
+    /// The intersection of two negative terms is negative, so it is never the empty term.
+    pub fn would_be_empty(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Positive(r1), Self::Positive(r2)) => r1.is_disjoint(r2),
+            (Self::Positive(r1), Self::Negative(r2)) => r1.subset_of(r2),
+            (Self::Negative(r1), Self::Positive(r2)) => r2.subset_of(r1),
+            (Self::Negative(_), Self::Negative(_)) => false,
+        }
+    }
+
     /// Compute the union of two terms.
     /// If at least one term is negative, the union is also negative.
-    pub(crate) fn union(&self, other: &Term<V>) -> Term<V> {
+    pub(crate) fn union(&self, other: &Self) -> Self {
         (self.negate().intersection(&other.negate())).negate()
     }
 
     /// Indicate if this term is a subset of another term.
     /// Just like for sets, we say that t1 is a subset of t2
     /// if and only if t1 ∩ t2 = t1.
-    pub(crate) fn subset_of(&self, other: &Term<V>) -> bool {
+    pub(crate) fn subset_of(&self, other: &Self) -> bool {
         self == &self.intersection(other)
     }
 }
@@ -120,7 +258,7 @@ pub(crate) enum Relation {
 }
 
 /// Relation between terms.
-impl<'a, V: 'a + Version> Term<V> {
+impl<VS: VersionSet> Term<VS> {
     /// Check if a set of terms satisfies this term.
     ///
     /// We say that a set of terms S "satisfies" a term t
@@ -129,7 +267,7 @@ impl<'a, V: 'a + Version> Term<V> {
     /// It turns out that this can also be expressed with set operations:
     ///    S satisfies t if and only if  ⋂ S ⊆ t
     #[cfg(test)]
-    fn satisfied_by(&self, terms_intersection: &Term<V>) -> bool {
+    fn satisfied_by(&self, terms_intersection: &Self) -> bool {
         terms_intersection.subset_of(self)
     }
 
@@ -142,33 +280,38 @@ impl<'a, V: 'a + Version> Term<V> {
     ///    S contradicts t if and only if ⋂ S is disjoint with t
     ///    S contradicts t if and only if  (⋂ S) ⋂ t = ∅
     #[cfg(test)]
-    fn contradicted_by(&self, terms_intersection: &Term<V>) -> bool {
+    fn contradicted_by(&self, terms_intersection: &Self) -> bool {
         terms_intersection.intersection(self) == Self::empty()
     }
 
     /// Check if a set of terms satisfies or contradicts a given term.
     /// Otherwise the relation is inconclusive.
-    pub(crate) fn relation_with(&self, other_terms_intersection: &Term<V>) -> Relation {
-        let full_intersection = self.intersection(other_terms_intersection);
-        if &full_intersection == other_terms_intersection {
+    pub(crate) fn relation_with(&self, other_terms_intersection: &Self) -> Relation {
+        if self.would_be_empty(other_terms_intersection) {
+            // The intersection is empty, so it is only equal to
+            // the other terms intersection if that one is empty too.
+            if other_terms_intersection == &Self::empty() {
+                Relation::Satisfied
+            } else {
+                Relation::Contradicted
+            }
+        } else if &self.intersection(other_terms_intersection) == other_terms_intersection {
             Relation::Satisfied
-        } else if full_intersection == Self::empty() {
-            Relation::Contradicted
         } else {
             Relation::Inconclusive
         }
     }
 }
 
-impl<V: Version> AsRef<Term<V>> for Term<V> {
-    fn as_ref(&self) -> &Term<V> {
-        &self
+impl<VS: VersionSet> AsRef<Self> for Term<VS> {
+    fn as_ref(&self) -> &Self {
+        self
     }
 }
 
 // REPORT ######################################################################
 
-impl<V: Version + fmt::Display> fmt::Display for Term<V> {
+impl<VS: VersionSet + Display> Display for Term<VS> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Positive(range) => write!(f, "{}", range),
@@ -179,21 +322,136 @@ impl<V: Version + fmt::Display> fmt::Display for Term<V> {
 
 // TESTS #######################################################################
 
+#[cfg(feature = "arbitrary")]
+impl<'a, VS: VersionSet + arbitrary::Arbitrary<'a>> arbitrary::Arbitrary<'a> for Term<VS> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        if u.arbitrary()? {
+            Ok(Self::Positive(u.arbitrary()?))
+        } else {
+            Ok(Self::Negative(u.arbitrary()?))
+        }
+    }
+}
+
 #[cfg(test)]
-pub mod tests {
+pub(crate) mod tests {
     use super::*;
+    use crate::range::Range;
     use crate::version::NumberVersion;
     use proptest::prelude::*;
 
-    pub fn strategy() -> impl Strategy<Value = Term<NumberVersion>> {
+    pub fn strategy() -> impl Strategy<Value = Term<Range<NumberVersion>>> {
         prop_oneof![
             crate::range::tests::strategy().prop_map(Term::Positive),
             crate::range::tests::strategy().prop_map(Term::Negative),
         ]
     }
 
+    #[test]
+    fn accumulated_intersections_stay_compact() {
+        // Ranges are kept canonical by set operations, so equality with
+        // a single interval means no redundant segment was accumulated.
+        let negative = (0..10).fold(Term::any(), |acc: Term<Range<NumberVersion>>, i| {
+            acc.intersection(&Term::Negative(Range::between(i, i + 3)))
+        });
+        assert_eq!(negative, Term::Negative(Range::between(0, 12)));
+        let positive = (0..10).fold(negative.negate(), |acc, i| {
+            acc.intersection(&Term::Positive(Range::higher_than(i)))
+                .intersection(&Term::Negative(Range::exact(20 + i)))
+        });
+        assert_eq!(positive, Term::Positive(Range::between(9, 12)));
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary_terms_double_negation() {
+        use arbitrary::{Arbitrary, Unstructured};
+        // Pseudo-random bytes from a xorshift generator.
+        let mut state = 0x2545_f491_u32;
+        let data: Vec<u8> = (0..64 * 20)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        for chunk in data.chunks(64) {
+            let mut u = Unstructured::new(chunk);
+            let term = Term::<Range<NumberVersion>>::arbitrary(&mut u).unwrap();
+            assert_eq!(term.negate().negate(), term);
+        }
+    }
+
+    #[test]
+    fn range_conversions() {
+        let range = Range::<NumberVersion>::between(1, 3);
+        let positive = Term::positive(range.clone());
+        assert_eq!(positive, Term::Positive(range.clone()));
+        assert_eq!(positive.as_positive(), Some(&range));
+        assert_eq!(positive.as_negative(), None);
+        assert_eq!(positive.range(), (&range, true));
+
+        let negative = Term::negative(range.clone());
+        assert_eq!(negative, Term::Negative(range.clone()));
+        assert_eq!(negative.as_positive(), None);
+        assert_eq!(negative.as_negative(), Some(&range));
+        assert_eq!(negative.range(), (&range, false));
+
+        // Round trips through the range and polarity.
+        for term in [positive, negative] {
+            let (range, is_positive) = term.range();
+            let rebuilt = if is_positive {
+                Term::positive(range.clone())
+            } else {
+                Term::negative(range.clone())
+            };
+            assert_eq!(rebuilt, term);
+        }
+    }
+
+    #[test]
+    fn describe_terms() {
+        let range = Range::<NumberVersion>::between(1, 3);
+        assert_eq!(
+            Term::Positive(range.clone()).describe(&"foo"),
+            "foo must be 1 <= v < 3"
+        );
+        assert_eq!(
+            Term::Negative(range).describe(&"foo"),
+            "foo must not be 1 <= v < 3"
+        );
+        assert_eq!(
+            Term::<Range<NumberVersion>>::any().describe(&"foo"),
+            "foo may be absent"
+        );
+        assert_eq!(
+            Term::Negative(Range::<NumberVersion>::any()).describe(&"foo"),
+            "foo must be absent"
+        );
+        assert_eq!(
+            Term::<Range<NumberVersion>>::empty().describe(&"foo"),
+            "foo can never be satisfied"
+        );
+    }
+
+    #[test]
+    fn absence_is_only_allowed_by_negative_terms() {
+        let range = Range::<NumberVersion>::between(1, 3);
+        assert!(Term::Negative(range.clone()).allows_absence());
+        assert!(Term::<Range<NumberVersion>>::any().allows_absence());
+        assert!(!Term::Positive(range).allows_absence());
+        assert!(!Term::Positive(Range::<NumberVersion>::any()).allows_absence());
+        assert!(!Term::<Range<NumberVersion>>::empty().allows_absence());
+    }
+
     proptest! {
 
+        #[test]
+        fn would_be_empty_agrees_with_intersection(t1 in strategy(), t2 in strategy()) {
+            assert_eq!(t1.would_be_empty(&t2), t1.intersection(&t2) == Term::empty());
+        }
+
         // Testing relation --------------------------------
 
         #[test]
@@ -208,5 +466,68 @@ pub mod tests {
             }
         }
 
+        // Testing set laws --------------------------------
+
+        #[test]
+        fn intersection_is_commutative(t1 in strategy(), t2 in strategy()) {
+            assert_eq!(t1.intersection(&t2), t2.intersection(&t1));
+        }
+
+        #[test]
+        fn intersection_is_associative(t1 in strategy(), t2 in strategy(), t3 in strategy()) {
+            assert_eq!(
+                t1.intersection(&t2).intersection(&t3),
+                t1.intersection(&t2.intersection(&t3))
+            );
+        }
+
+        #[test]
+        fn union_is_commutative(t1 in strategy(), t2 in strategy()) {
+            assert_eq!(t1.union(&t2), t2.union(&t1));
+        }
+
+        #[test]
+        fn union_is_associative(t1 in strategy(), t2 in strategy(), t3 in strategy()) {
+            assert_eq!(t1.union(&t2).union(&t3), t1.union(&t2.union(&t3)));
+        }
+
+        #[test]
+        fn de_morgan_negate_union(t1 in strategy(), t2 in strategy()) {
+            assert_eq!(t1.union(&t2).negate(), t1.negate().intersection(&t2.negate()));
+        }
+
+        #[test]
+        fn de_morgan_negate_intersection(t1 in strategy(), t2 in strategy()) {
+            assert_eq!(t1.intersection(&t2).negate(), t1.negate().union(&t2.negate()));
+        }
+
+        #[test]
+        fn intersection_agrees_with_contains(t1 in strategy(), t2 in strategy(), version in any::<u32>()) {
+            let v = NumberVersion(version);
+            assert_eq!(t1.intersection(&t2).contains(&v), t1.contains(&v) && t2.contains(&v));
+        }
+
+        #[test]
+        fn subset_of_is_reflexive(term in strategy()) {
+            assert!(term.subset_of(&term));
+        }
+
+        #[test]
+        fn subset_of_is_antisymmetric(t1 in strategy(), t2 in strategy()) {
+            if t1.subset_of(&t2) && t2.subset_of(&t1) {
+                assert_eq!(t1, t2);
+            }
+        }
+
+        #[test]
+        fn subset_of_is_transitive(t1 in strategy(), t2 in strategy(), t3 in strategy()) {
+            // Build a chain t1 ⊆ t2 ⊆ t3 from arbitrary terms.
+            let t2 = t2.intersection(&t3);
+            let t1 = t1.intersection(&t2);
+            assert!(t1.subset_of(&t2));
+            assert!(t2.subset_of(&t3));
+            assert!(t1.subset_of(&t3));
+        }
+
     }
 }
@@ -3,8 +3,113 @@
 //! Publicly exported type aliases.
 
 /// Map implementation used by the library.
+///
+/// The hasher is not randomly seeded, so the iteration order only depends
+/// on the inserted keys and the order of insertion.
+/// As a consequence, solving the same problem always makes the same decisions,
+/// including when breaking ties between packages.
+#[cfg(feature = "std")]
 pub type Map<K, V> = rustc_hash::FxHashMap<K, V>;
 
+/// Map implementation used by the library.
+///
+/// The hasher is not randomly seeded, so the iteration order only depends
+/// on the inserted keys and the order of insertion.
+/// As a consequence, solving the same problem always makes the same decisions,
+/// including when breaking ties between packages.
+#[cfg(not(feature = "std"))]
+pub type Map<K, V> = hashbrown::HashMap<K, V, core::hash::BuildHasherDefault<rustc_hash::FxHasher>>;
+
+/// Set implementation used by the library.
+#[cfg(feature = "std")]
+pub(crate) type Set<K> = rustc_hash::FxHashSet<K>;
+
+/// Set implementation used by the library.
+#[cfg(not(feature = "std"))]
+pub(crate) type Set<K> =
+    hashbrown::HashSet<K, core::hash::BuildHasherDefault<rustc_hash::FxHasher>>;
+
 /// Concrete dependencies picked by the library during [resolve](crate::solver::resolve)
 /// from [DependencyConstraints](crate::solver::DependencyConstraints)
 pub type SelectedDependencies<P, V> = Map<P, V>;
+
+/// Packages of a solution depending on each selected package,
+/// with the versions they require of it,
+/// obtained with [resolve_with_tree](crate::solver::resolve_with_tree).
+pub type Dependents<P, VS> = Map<P, Map<P, VS>>;
+
+/// Convenience methods for [SelectedDependencies],
+/// independent of the underlying [Map] implementation.
+pub trait SelectedDependenciesExt<P, V> {
+    /// Iterate over the selected packages and versions, sorted by package,
+    /// for a reproducible output.
+    fn iter_sorted(&self) -> alloc::vec::IntoIter<(&P, &V)>;
+
+    /// Compare this solution with a `new` one,
+    /// typically obtained after solving again with updated dependencies.
+    fn diff(&self, new: &Self) -> SolutionDiff<P, V>
+    where
+        P: Clone,
+        V: Clone + PartialEq;
+}
+
+/// Changes between two solutions, obtained with [SelectedDependenciesExt::diff].
+/// Every list is sorted by package, for a reproducible output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolutionDiff<P, V> {
+    /// Packages only in the new solution, with their version.
+    pub added: alloc::vec::Vec<(P, V)>,
+    /// Packages only in the old solution, with their version.
+    pub removed: alloc::vec::Vec<(P, V)>,
+    /// Packages in both solutions with a different version,
+    /// with their old and new versions.
+    pub changed: alloc::vec::Vec<(P, V, V)>,
+}
+
+impl<P: Ord, V> SelectedDependenciesExt<P, V> for SelectedDependencies<P, V> {
+    fn iter_sorted(&self) -> alloc::vec::IntoIter<(&P, &V)> {
+        let mut selected: alloc::vec::Vec<_> = self.iter().collect();
+        selected.sort_by_key(|(p, _)| *p);
+        selected.into_iter()
+    }
+
+    fn diff(&self, new: &Self) -> SolutionDiff<P, V>
+    where
+        P: Clone,
+        V: Clone + PartialEq,
+    {
+        let mut diff = SolutionDiff {
+            added: alloc::vec::Vec::new(),
+            removed: alloc::vec::Vec::new(),
+            changed: alloc::vec::Vec::new(),
+        };
+        // Both solutions are sorted by package, so they can be merged in a single pass.
+        let mut old = self.iter_sorted().peekable();
+        let mut new = new.iter_sorted().peekable();
+        loop {
+            match (old.peek(), new.peek()) {
+                (None, None) => break,
+                (Some((old_p, _)), Some((new_p, _))) if old_p == new_p => {
+                    let (p, old_v) = old.next().unwrap();
+                    let (_, new_v) = new.next().unwrap();
+                    if old_v != new_v {
+                        diff.changed.push((p.clone(), old_v.clone(), new_v.clone()));
+                    }
+                }
+                (Some((old_p, _)), Some((new_p, _))) if old_p > new_p => {
+                    let (p, new_v) = new.next().unwrap();
+                    diff.added.push((p.clone(), new_v.clone()));
+                }
+                (Some(_), _) => {
+                    let (p, old_v) = old.next().unwrap();
+                    diff.removed.push((p.clone(), old_v.clone()));
+                }
+                (None, Some(_)) => {
+                    let (p, new_v) = new.next().unwrap();
+                    diff.added.push((p.clone(), new_v.clone()));
+                }
+            }
+        }
+        diff
+    }
+}
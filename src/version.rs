@@ -2,9 +2,14 @@
 
 //! Traits and implementations to create and compare versions.
 
-use std::fmt::{self, Debug, Display};
-use std::str::FromStr;
-use thiserror::Error;
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::cmp::Ordering;
+use core::fmt::{self, Debug, Display};
+use core::num::ParseIntError;
+use core::str::FromStr;
+
+use crate::error::Error;
 
 /// Versions have a minimal version (a "0" version)
 /// and are ordered such that every version has a next one.
@@ -13,6 +18,29 @@ pub trait Version: Clone + Ord + Debug + Display {
     fn lowest() -> Self;
     /// Returns the next version, the smallest strictly higher version.
     fn bump(&self) -> Self;
+    /// Returns the next version, or [None] if this is the highest version.
+    /// If not provided, versions are assumed to have no highest version.
+    fn checked_bump(&self) -> Option<Self> {
+        Some(self.bump())
+    }
+    /// Canonical string representation of this version,
+    /// stable enough for caches, lock files and deterministic ordering of keys.
+    /// Versions implementing [FromStr] must parse it back to an equal version.
+    /// If not provided, it is the [Display] representation.
+    fn version_key(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// Versions that may be prereleases, like `1.0.0-rc1`.
+/// Prereleases are opt-in: they should only be selected if explicitly requested,
+/// which is checked by [Range::contains_selectable](crate::range::Range::contains_selectable).
+pub trait Prerelease: Version {
+    /// Check if this version is a prerelease.
+    fn is_prerelease(&self) -> bool;
+    /// Release of this version, `1.0.0` for `1.0.0-rc1`.
+    /// A version which is not a prerelease is its own release.
+    fn release(&self) -> Self;
 }
 
 /// Type for semantic versions: major.minor.patch.
@@ -103,19 +131,30 @@ impl SemanticVersion {
     pub fn bump_major(self) -> Self {
         Self::new(self.major + 1, 0, 0)
     }
+
+    /// Smallest version higher than all the ones with the same major and minor numbers,
+    /// or [None] if there is none.
+    pub fn checked_bump_minor(self) -> Option<Self> {
+        let [major, minor] = bump_parts([self.major, self.minor])?;
+        Some(Self::new(major, minor, 0))
+    }
+
+    /// Smallest version higher than all the ones with the same major number,
+    /// or [None] if there is none.
+    pub fn checked_bump_major(self) -> Option<Self> {
+        Some(Self::new(self.major.checked_add(1)?, 0, 0))
+    }
 }
 
 /// Error creating [SemanticVersion] from [String].
-#[derive(Error, Debug, PartialEq)]
+#[derive(Debug, PartialEq)]
 pub enum VersionParseError {
     /// [SemanticVersion] must contain major, minor, patch versions.
-    #[error("version {full_version} must contain 3 numbers separated by dot")]
     NotThreeParts {
         /// [SemanticVersion] that was being parsed.
         full_version: String,
     },
     /// Wrapper around [ParseIntError](core::num::ParseIntError).
-    #[error("cannot parse '{version_part}' in '{full_version}' as u32: {parse_error}")]
     ParseIntError {
         /// [SemanticVersion] that was being parsed.
         full_version: String,
@@ -124,8 +163,41 @@ pub enum VersionParseError {
         /// A specific error resulted from parsing a part of the version as [u32].
         parse_error: String,
     },
+    /// The prerelease of a [PrereleaseVersion] must be made of non-empty identifiers
+    /// separated by dots, containing only ASCII alphanumerics and hyphens,
+    /// and numbers without leading zeros.
+    InvalidPrerelease {
+        /// [PrereleaseVersion] that was being parsed.
+        full_version: String,
+    },
 }
 
+impl Display for VersionParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotThreeParts { full_version } => write!(
+                f,
+                "version {} must contain 3 numbers separated by dot",
+                full_version
+            ),
+            Self::ParseIntError {
+                full_version,
+                version_part,
+                parse_error,
+            } => write!(
+                f,
+                "cannot parse '{}' in '{}' as u32: {}",
+                version_part, full_version, parse_error
+            ),
+            Self::InvalidPrerelease { full_version } => {
+                write!(f, "invalid prerelease in version {}", full_version)
+            }
+        }
+    }
+}
+
+impl Error for VersionParseError {}
+
 impl FromStr for SemanticVersion {
     type Err = VersionParseError;
 
@@ -214,13 +286,34 @@ impl Display for SemanticVersion {
     }
 }
 
+/// Next version of lexicographically ordered parts,
+/// resetting the trailing parts that are already at their maximum,
+/// or [None] if all of them are.
+fn bump_parts<const N: usize>(mut parts: [u32; N]) -> Option<[u32; N]> {
+    let last_bumpable = parts.iter().rposition(|part| *part < u32::MAX)?;
+    parts[last_bumpable] += 1;
+    for part in &mut parts[last_bumpable + 1..] {
+        *part = 0;
+    }
+    Some(parts)
+}
+
 // Implement Version for SemanticVersion.
 impl Version for SemanticVersion {
     fn lowest() -> Self {
         Self::zero()
     }
     fn bump(&self) -> Self {
-        self.bump_patch()
+        self.checked_bump()
+            .expect("there is no version higher than the highest one")
+    }
+    fn checked_bump(&self) -> Option<Self> {
+        // The version following the highest patch is the next minor, and so on.
+        let [major, minor, patch] = bump_parts([self.major, self.minor, self.patch])?;
+        Some(Self::new(major, minor, patch))
+    }
+    fn version_key(&self) -> String {
+        format!("{}.{}.{}", self.major, self.minor, self.patch)
     }
 }
 
@@ -257,4 +350,342 @@ impl Version for NumberVersion {
     fn bump(&self) -> Self {
         Self(self.0 + 1)
     }
+    fn checked_bump(&self) -> Option<Self> {
+        self.0.checked_add(1).map(Self)
+    }
+    fn version_key(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for NumberVersion {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self(u.arbitrary()?))
+    }
+}
+
+impl FromStr for NumberVersion {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
+
+/// Versions made of multiple numbers, like `1.2.3.4`,
+/// ordered lexicographically by their components.
+/// It implements [Version] for tuples of two to four `u32`.
+/// The next version of a tuple only increments its last component,
+/// such that `1.2` is followed by `1.3`, and `1.2.3` by `1.2.4`.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize,))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct TupleVersion<T>(pub T);
+
+macro_rules! tuple_version {
+    ($($component:ident),+; $last:ident) => {
+        impl From<($(tuple_version!(@u32 $component),)+ u32)>
+            for TupleVersion<($(tuple_version!(@u32 $component),)+ u32)>
+        {
+            fn from(tuple: ($(tuple_version!(@u32 $component),)+ u32)) -> Self {
+                Self(tuple)
+            }
+        }
+
+        impl Display for TupleVersion<($(tuple_version!(@u32 $component),)+ u32)> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let ($($component,)+ $last) = self.0;
+                $(write!(f, "{}.", $component)?;)+
+                write!(f, "{}", $last)
+            }
+        }
+
+        impl Version for TupleVersion<($(tuple_version!(@u32 $component),)+ u32)> {
+            fn lowest() -> Self {
+                Self(($(tuple_version!(@zero $component),)+ 0))
+            }
+            fn bump(&self) -> Self {
+                self.checked_bump()
+                    .expect("there is no version higher than the highest one")
+            }
+            fn checked_bump(&self) -> Option<Self> {
+                let ($($component,)+ $last) = self.0;
+                let [$($component,)+ $last] = bump_parts([$($component,)+ $last])?;
+                Some(Self(($($component,)+ $last)))
+            }
+        }
+    };
+    (@u32 $component:ident) => { u32 };
+    (@zero $component:ident) => { 0 };
+}
+
+tuple_version!(a; b);
+tuple_version!(a, b; c);
+tuple_version!(a, b, c; d);
+
+/// Semantic version which may be a prerelease, like `1.1.0-beta.2`.
+///
+/// Prereleases come before their release, and are ordered
+/// following the precedence rules of semantic versioning:
+/// identifiers separated by dots are compared one by one,
+/// numerically if they are numbers, which come first, and alphabetically otherwise,
+/// so `1.0.0-alpha < 1.0.0-alpha.1 < 1.0.0-beta < 1.0.0-beta.2 < 1.0.0-beta.11 < 1.0.0`.
+/// Build metadata is not supported.
+///
+/// ```
+/// # use pubgrub::version::{Prerelease, PrereleaseVersion, SemanticVersion};
+/// #
+/// let beta: PrereleaseVersion = "1.1.0-beta".parse().unwrap();
+/// assert!(beta.is_prerelease());
+/// assert!(beta < PrereleaseVersion::from((1, 1, 0)));
+/// assert_eq!(beta.release(), PrereleaseVersion::from((1, 1, 0)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PrereleaseVersion {
+    release: SemanticVersion,
+    prerelease: Option<String>,
+}
+
+impl PrereleaseVersion {
+    /// Prerelease of a version, such as `1.1.0-beta` for `(1, 1, 0)` and `"beta"`.
+    pub fn new(
+        release: impl Into<SemanticVersion>,
+        prerelease: &str,
+    ) -> Result<Self, VersionParseError> {
+        let release = release.into();
+        if !is_valid_prerelease(prerelease) {
+            return Err(VersionParseError::InvalidPrerelease {
+                full_version: format!("{}-{}", release, prerelease),
+            });
+        }
+        Ok(Self {
+            release,
+            prerelease: Some(prerelease.to_string()),
+        })
+    }
+
+    /// Prerelease part of the version, `beta` for `1.1.0-beta`.
+    pub fn prerelease(&self) -> Option<&str> {
+        self.prerelease.as_deref()
+    }
+
+    /// Release of this version as a [SemanticVersion], `1.1.0` for `1.1.0-beta`.
+    pub(crate) fn semantic_release(&self) -> SemanticVersion {
+        self.release
+    }
+}
+
+/// Identifiers are non-empty, and numbers do not have leading zeros,
+/// so that equal prereleases are written the same way.
+fn is_valid_prerelease(prerelease: &str) -> bool {
+    prerelease.split('.').all(|identifier| {
+        let is_number = identifier.bytes().all(|b| b.is_ascii_digit());
+        !identifier.is_empty()
+            && identifier
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+            && !(is_number && identifier.len() > 1 && identifier.starts_with('0'))
+    })
+}
+
+/// Precedence of two prereleases of the same release.
+fn cmp_prereleases(a: &str, b: &str) -> Ordering {
+    let is_number = |identifier: &str| identifier.bytes().all(|b| b.is_ascii_digit());
+    let mut a_identifiers = a.split('.');
+    let mut b_identifiers = b.split('.');
+    loop {
+        let ordering = match (a_identifiers.next(), b_identifiers.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => match (is_number(a), is_number(b)) {
+                // Compare numbers without parsing them, so they can't overflow.
+                (true, true) => a.len().cmp(&b.len()).then_with(|| a.cmp(b)),
+                (true, false) => Ordering::Less,
+                (false, true) => Ordering::Greater,
+                (false, false) => a.cmp(b),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+impl Ord for PrereleaseVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.release
+            .cmp(&other.release)
+            .then_with(|| match (&self.prerelease, &other.prerelease) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => cmp_prereleases(a, b),
+            })
+    }
+}
+
+impl PartialOrd for PrereleaseVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Convert a release into a version.
+impl From<SemanticVersion> for PrereleaseVersion {
+    fn from(release: SemanticVersion) -> Self {
+        Self {
+            release,
+            prerelease: None,
+        }
+    }
+}
+
+// Convert a tuple (major, minor, patch) into a release.
+impl From<(u32, u32, u32)> for PrereleaseVersion {
+    fn from(tuple: (u32, u32, u32)) -> Self {
+        SemanticVersion::from(tuple).into()
+    }
+}
+
+impl Display for PrereleaseVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.release)?;
+        if let Some(prerelease) = &self.prerelease {
+            write!(f, "-{}", prerelease)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PrereleaseVersion {
+    type Err = VersionParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('-') {
+            None => s.parse::<SemanticVersion>().map(Self::from),
+            Some((release, prerelease)) => {
+                let release = release.parse::<SemanticVersion>()?;
+                Self::new(release, prerelease).map_err(|_| VersionParseError::InvalidPrerelease {
+                    full_version: s.to_string(),
+                })
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for PrereleaseVersion {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&format!("{}", self))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PrereleaseVersion {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        FromStr::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Version for PrereleaseVersion {
+    fn lowest() -> Self {
+        // `0` is the prerelease with the lowest precedence.
+        Self {
+            release: SemanticVersion::zero(),
+            prerelease: Some("0".to_string()),
+        }
+    }
+    fn bump(&self) -> Self {
+        match &self.prerelease {
+            // No prerelease of the same release comes between `1.0.0-beta` and `1.0.0-beta.0`.
+            Some(prerelease) => Self {
+                release: self.release,
+                prerelease: Some(format!("{}.0", prerelease)),
+            },
+            // The next release starts with its lowest prerelease.
+            None => Self {
+                release: self.release.bump(),
+                prerelease: Some("0".to_string()),
+            },
+        }
+    }
+    fn checked_bump(&self) -> Option<Self> {
+        match &self.prerelease {
+            Some(_) => Some(self.bump()),
+            None => Some(Self {
+                release: self.release.checked_bump()?,
+                prerelease: Some("0".to_string()),
+            }),
+        }
+    }
+}
+
+impl Prerelease for PrereleaseVersion {
+    fn is_prerelease(&self) -> bool {
+        self.prerelease.is_some()
+    }
+    fn release(&self) -> Self {
+        self.release.into()
+    }
+}
+
+#[test]
+fn prerelease_precedence() {
+    let versions: Vec<PrereleaseVersion> = [
+        "1.0.0-0",
+        "1.0.0-0.0",
+        "1.0.0-2",
+        "1.0.0-11",
+        "1.0.0-alpha",
+        "1.0.0-alpha.1",
+        "1.0.0-alpha.beta",
+        "1.0.0-beta",
+        "1.0.0-beta.2",
+        "1.0.0-beta.11",
+        "1.0.0-rc.1",
+        "1.0.0",
+        "1.0.1-0",
+        "1.0.1",
+    ]
+    .iter()
+    .map(|s| s.parse().unwrap())
+    .collect();
+    for pair in versions.windows(2) {
+        assert!(pair[0] < pair[1], "{} < {}", pair[0], pair[1]);
+        assert!(
+            pair[0].bump() <= pair[1],
+            "{} <= {}",
+            pair[0].bump(),
+            pair[1]
+        );
+        assert!(pair[0] < pair[0].bump());
+    }
+    for version in &versions {
+        assert_eq!(
+            &version.to_string().parse::<PrereleaseVersion>().unwrap(),
+            version
+        );
+    }
+    assert_eq!(PrereleaseVersion::lowest().to_string(), "0.0.0-0");
+    assert!(matches!(
+        "1.0.0-beta..1".parse::<PrereleaseVersion>(),
+        Err(VersionParseError::InvalidPrerelease { .. })
+    ));
+    assert!(matches!(
+        "1.0.0-beta.01".parse::<PrereleaseVersion>(),
+        Err(VersionParseError::InvalidPrerelease { .. })
+    ));
+    assert!(matches!(
+        "1.0.0-".parse::<PrereleaseVersion>(),
+        Err(VersionParseError::InvalidPrerelease { .. })
+    ));
 }
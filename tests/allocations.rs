@@ -0,0 +1,182 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Incompatibilities are stored in an arena of the solver and referenced by ids,
+//! so derived incompatibilities never clone their causes.
+//! These tests pin the solutions of resolutions on several registries,
+//! and check that the number of allocations stays proportional to the work done.
+
+#![cfg(feature = "serde")]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::fmt::Write;
+
+use pubgrub::error::PubGrubError;
+use pubgrub::range::Range;
+use pubgrub::solver::{resolve, resolve_with_stats, OfflineDependencyProvider, SolverStats};
+use pubgrub::version::NumberVersion;
+
+type NumVS = Range<NumberVersion>;
+
+/// Allocator counting the allocations made by the current thread,
+/// since tests run concurrently.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const LARGE_CASE: &str = "test-examples/large_case_u16_NumberVersion.ron";
+const LARGE_CASE_SOLUTIONS: &str = "test-examples/large_case_u16_NumberVersion.solutions";
+
+fn large_case() -> OfflineDependencyProvider<u16, NumVS> {
+    let data = std::fs::read_to_string(LARGE_CASE).unwrap();
+    let mut deserializer = ron::de::Deserializer::from_str(&data).unwrap();
+    OfflineDependencyProvider::from_legacy(&mut deserializer).unwrap()
+}
+
+/// Every package and version of the large case, sorted.
+fn roots(dependency_provider: &OfflineDependencyProvider<u16, NumVS>) -> Vec<(u16, NumberVersion)> {
+    let mut roots: Vec<_> = dependency_provider
+        .packages()
+        .flat_map(|p| {
+            let versions = dependency_provider.versions(p).unwrap();
+            versions.map(move |v| (*p, *v))
+        })
+        .collect();
+    roots.sort_unstable();
+    roots
+}
+
+/// One line per root, like `12@3 -> 4@1 12@3`,
+/// with the packages of the solution sorted.
+fn outcome(
+    dependency_provider: &OfflineDependencyProvider<u16, NumVS>,
+    p: u16,
+    v: NumberVersion,
+) -> String {
+    let mut line = format!("{}@{} ->", p, v);
+    match resolve(dependency_provider, p, v) {
+        Ok(solution) => {
+            let mut solution: Vec<_> = solution.into_iter().collect();
+            solution.sort_unstable();
+            for (package, version) in solution {
+                write!(line, " {}@{}", package, version).unwrap();
+            }
+        }
+        Err(PubGrubError::NoSolution(_)) => line.push_str(" no solution"),
+        Err(PubGrubError::PackageNotFound { package }) => {
+            write!(line, " {} not found", package).unwrap()
+        }
+        Err(err) => panic!("{:?}", err),
+    }
+    line
+}
+
+/// Solutions of every root of the large case, pinned in a file next to it,
+/// so that changes of the solver show up as a diff of that file.
+/// Run with `UPDATE_SOLUTIONS=1` to write the new solutions after an intended change.
+#[test]
+fn same_solutions_on_large_case() {
+    let dependency_provider = large_case();
+    let outcomes: Vec<String> = roots(&dependency_provider)
+        .into_iter()
+        .map(|(p, v)| outcome(&dependency_provider, p, v))
+        .collect();
+    if std::env::var_os("UPDATE_SOLUTIONS").is_some() {
+        std::fs::write(LARGE_CASE_SOLUTIONS, outcomes.join("\n") + "\n").unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(LARGE_CASE_SOLUTIONS).unwrap();
+    let expected: Vec<&str> = expected.lines().collect();
+    assert_eq!(outcomes.len(), expected.len());
+    let changed: Vec<String> = expected
+        .iter()
+        .zip(&outcomes)
+        .filter(|(expected, outcome)| expected != outcome)
+        .map(|(expected, outcome)| format!("- {}\n+ {}", expected, outcome))
+        .collect();
+    assert!(
+        changed.is_empty(),
+        "{} changed solutions:\n{}",
+        changed.len(),
+        changed.join("\n")
+    );
+}
+
+#[test]
+fn same_solutions_on_small_registries() {
+    // The registry of the documentation.
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1,
+        vec![("menu", Range::any()), ("icons", Range::any())],
+    );
+    dependency_provider.add_dependencies("menu", 1, vec![("dropdown", Range::any())]);
+    dependency_provider.add_dependencies("dropdown", 1, vec![("icons", Range::any())]);
+    dependency_provider.add_dependencies("icons", 1, vec![]);
+    let solution = resolve(&dependency_provider, "root", 1).unwrap();
+    let mut solution: Vec<_> = solution.into_iter().collect();
+    solution.sort_unstable();
+    assert_eq!(
+        solution,
+        [("dropdown", 1), ("icons", 1), ("menu", 1), ("root", 1)]
+            .map(|(p, v)| (p, NumberVersion(v)))
+    );
+
+    // The newest version of "menu" needs an older version of "icons",
+    // so the solver backtracks to a version of "menu" compatible with the newest "icons".
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1,
+        vec![("menu", Range::any()), ("icons", Range::higher_than(2))],
+    );
+    dependency_provider.add_dependencies("menu", 1, vec![("icons", Range::any())]);
+    dependency_provider.add_dependencies("menu", 2, vec![("icons", Range::exact(1))]);
+    dependency_provider.add_dependencies("icons", 1, vec![]);
+    dependency_provider.add_dependencies("icons", 2, vec![]);
+    let solution = resolve(&dependency_provider, "root", 1).unwrap();
+    let mut solution: Vec<_> = solution.into_iter().collect();
+    solution.sort_unstable();
+    assert_eq!(
+        solution,
+        [("icons", 2), ("menu", 1), ("root", 1)].map(|(p, v)| (p, NumberVersion(v)))
+    );
+}
+
+#[test]
+fn allocations_are_proportional_to_work() {
+    let dependency_provider = large_case();
+    let mut stats = SolverStats::default();
+    let before = ALLOCATIONS.with(Cell::get);
+    for (p, v) in roots(&dependency_provider) {
+        let (_, root_stats) = resolve_with_stats(&dependency_provider, p, v);
+        stats.decisions += root_stats.decisions;
+        stats.derivations += root_stats.derivations;
+        stats.conflicts += root_stats.conflicts;
+    }
+    let allocations = ALLOCATIONS.with(Cell::get) - before;
+    let work = stats.decisions + stats.derivations + stats.conflicts;
+    assert!(
+        allocations < 10 * work,
+        "{} allocations for {:?}",
+        allocations,
+        stats
+    );
+}
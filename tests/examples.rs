@@ -3,12 +3,15 @@
 use pubgrub::range::Range;
 use pubgrub::solver::{resolve, OfflineDependencyProvider};
 use pubgrub::type_aliases::Map;
-use pubgrub::version::{NumberVersion, SemanticVersion};
+use pubgrub::version::{NumberVersion, SemanticVersion, TupleVersion, Version};
+
+type NumVS = Range<NumberVersion>;
+type SemVS = Range<SemanticVersion>;
 
 #[test]
 /// https://github.com/dart-lang/pub/blob/master/doc/solver.md#no-conflicts
 fn no_conflict() {
-    let mut dependency_provider = OfflineDependencyProvider::<&str, SemanticVersion>::new();
+    let mut dependency_provider = OfflineDependencyProvider::<&str, SemVS>::new();
     #[rustfmt::skip]
         dependency_provider.add_dependencies(
         "root", (1, 0, 0),
@@ -38,7 +41,7 @@ fn no_conflict() {
 #[test]
 /// https://github.com/dart-lang/pub/blob/master/doc/solver.md#avoiding-conflict-during-decision-making
 fn avoiding_conflict_during_decision_making() {
-    let mut dependency_provider = OfflineDependencyProvider::<&str, SemanticVersion>::new();
+    let mut dependency_provider = OfflineDependencyProvider::<&str, SemVS>::new();
     #[rustfmt::skip]
         dependency_provider.add_dependencies(
         "root", (1, 0, 0),
@@ -73,7 +76,7 @@ fn avoiding_conflict_during_decision_making() {
 #[test]
 /// https://github.com/dart-lang/pub/blob/master/doc/solver.md#performing-conflict-resolution
 fn conflict_resolution() {
-    let mut dependency_provider = OfflineDependencyProvider::<&str, SemanticVersion>::new();
+    let mut dependency_provider = OfflineDependencyProvider::<&str, SemVS>::new();
     #[rustfmt::skip]
         dependency_provider.add_dependencies(
         "root", (1, 0, 0),
@@ -106,7 +109,7 @@ fn conflict_resolution() {
 #[test]
 /// https://github.com/dart-lang/pub/blob/master/doc/solver.md#conflict-resolution-with-a-partial-satisfier
 fn conflict_with_partial_satisfier() {
-    let mut dependency_provider = OfflineDependencyProvider::<&str, SemanticVersion>::new();
+    let mut dependency_provider = OfflineDependencyProvider::<&str, SemVS>::new();
     #[rustfmt::skip]
     // root 1.0.0 depends on foo ^1.0.0 and target ^2.0.0
         dependency_provider.add_dependencies(
@@ -171,7 +174,7 @@ fn conflict_with_partial_satisfier() {
 ///
 /// Solution: a0, b0, c0, d0
 fn double_choices() {
-    let mut dependency_provider = OfflineDependencyProvider::<&str, NumberVersion>::new();
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
     dependency_provider.add_dependencies("a", 0, vec![("b", Range::any()), ("c", Range::any())]);
     dependency_provider.add_dependencies("b", 0, vec![("d", Range::exact(0))]);
     dependency_provider.add_dependencies("b", 1, vec![("d", Range::exact(1))]);
@@ -190,3 +193,65 @@ fn double_choices() {
     let computed_solution = resolve(&dependency_provider, "a", 0).unwrap();
     assert_eq!(expected_solution, computed_solution);
 }
+
+#[test]
+/// Same as the "avoiding conflict during decision making" example,
+/// with versions made of three numbers.
+fn tuple_versions() {
+    type TupleVS = Range<TupleVersion<(u32, u32, u32)>>;
+    let mut dependency_provider = OfflineDependencyProvider::<&str, TupleVS>::new();
+    #[rustfmt::skip]
+        dependency_provider.add_dependencies(
+        "root", (1, 0, 0),
+        vec![
+            ("foo", Range::between((1, 0, 0), (2, 0, 0))),
+            ("bar", Range::between((1, 0, 0), (2, 0, 0))),
+        ],
+    );
+    #[rustfmt::skip]
+        dependency_provider.add_dependencies(
+        "foo", (1, 1, 0),
+        vec![("bar", Range::between((2, 0, 0), (3, 0, 0)))],
+    );
+    dependency_provider.add_dependencies("foo", (1, 0, 0), vec![]);
+    dependency_provider.add_dependencies("bar", (1, 0, 0), vec![]);
+    dependency_provider.add_dependencies("bar", (1, 0, 7), vec![]);
+    dependency_provider.add_dependencies("bar", (2, 0, 0), vec![]);
+
+    // Run the algorithm.
+    let computed_solution = resolve(&dependency_provider, "root", (1, 0, 0)).unwrap();
+
+    // Solution.
+    let mut expected_solution = Map::default();
+    expected_solution.insert("root", (1, 0, 0).into());
+    expected_solution.insert("foo", (1, 0, 0).into());
+    expected_solution.insert("bar", (1, 0, 7).into());
+
+    // Comparing the true solution with the one computed by the algorithm.
+    assert_eq!(expected_solution, computed_solution);
+    assert_eq!(computed_solution["bar"].to_string(), "1.0.7");
+    assert_eq!(
+        TupleVersion((1, 2, 3, 4)).bump(),
+        TupleVersion((1, 2, 3, 5)),
+        "only the last component is incremented"
+    );
+}
+
+#[cfg(feature = "debug-checks")]
+#[test]
+/// The example of the crate documentation,
+/// checking the invariants of the partial solution at every step.
+fn doc_example_with_debug_checks() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1,
+        vec![("menu", Range::any()), ("icons", Range::any())],
+    );
+    dependency_provider.add_dependencies("menu", 1, vec![("dropdown", Range::any())]);
+    dependency_provider.add_dependencies("dropdown", 1, vec![("icons", Range::any())]);
+    dependency_provider.add_dependencies("icons", 1, vec![]);
+
+    let computed_solution = resolve(&dependency_provider, "root", 1).unwrap();
+    assert_eq!(computed_solution.len(), 4);
+}
@@ -0,0 +1,48 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Tests specific to builds without the `std` feature,
+//! run with `cargo test --no-default-features`.
+
+#![cfg(not(feature = "std"))]
+
+use pubgrub::package::Package;
+use pubgrub::range::Range;
+use pubgrub::solver::{resolve, OfflineDependencyProvider};
+use pubgrub::version::NumberVersion;
+
+type NumVS = Range<NumberVersion>;
+
+#[test]
+fn resolve_without_std() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1,
+        vec![
+            ("c", Range::any()),
+            ("a", Range::any()),
+            ("b", Range::any()),
+        ],
+    );
+    dependency_provider.add_dependencies("a", 1, vec![]);
+    dependency_provider.add_dependencies("b", 2, vec![]);
+    dependency_provider.add_dependencies("c", 3, vec![]);
+
+    let solution = resolve(&dependency_provider, "root", 1).unwrap();
+    assert_eq!(solution.len(), 4);
+    assert_eq!(solution.get("b"), Some(&NumberVersion(2)));
+}
+
+/// Packages need the same traits with and without std.
+#[test]
+fn package_does_not_need_ord() {
+    #[derive(Clone, PartialEq, Eq, Hash, Debug)]
+    struct Unordered(&'static str);
+    impl core::fmt::Display for Unordered {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.write_str(self.0)
+        }
+    }
+    fn is_package<P: Package>(_: &P) {}
+    is_package(&Unordered("a"));
+}
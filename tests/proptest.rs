@@ -3,14 +3,16 @@
 use std::{collections::BTreeSet as Set, error::Error};
 
 use pubgrub::error::PubGrubError;
+use pubgrub::incompatibility::Incompatibility;
 use pubgrub::package::Package;
 use pubgrub::range::Range;
 use pubgrub::report::{DefaultStringReporter, Reporter};
 use pubgrub::solver::{
-    choose_package_with_fewest_versions, resolve, Dependencies, DependencyProvider,
-    OfflineDependencyProvider,
+    choose_package_with_fewest_versions, resolve, resolve_with_restarts, Dependencies,
+    DependencyProvider, OfflineDependencyProvider, RestartPolicy,
 };
-use pubgrub::version::{NumberVersion, Version};
+use pubgrub::version::{NumberVersion, SemanticVersion, Version};
+use pubgrub::version_set::VersionSet;
 
 use proptest::collection::{btree_map, vec};
 use proptest::prelude::*;
@@ -21,23 +23,29 @@ use crate::sat_dependency_provider::SatResolve;
 
 mod sat_dependency_provider;
 
+type NumVS = Range<NumberVersion>;
+
 /// The same as [OfflineDependencyProvider] but takes versions from the opposite end:
 /// if [OfflineDependencyProvider] returns versions from newest to oldest, this returns them from oldest to newest.
 #[derive(Clone)]
-struct OldestVersionsDependencyProvider<P: Package, V: Version>(OfflineDependencyProvider<P, V>);
+struct OldestVersionsDependencyProvider<P: Package, VS: VersionSet>(
+    OfflineDependencyProvider<P, VS>,
+);
 
-impl<P: Package, V: Version> DependencyProvider<P, V> for OldestVersionsDependencyProvider<P, V> {
-    fn choose_package_version<T: std::borrow::Borrow<P>, U: std::borrow::Borrow<Range<V>>>(
+impl<P: Package + Ord, VS: VersionSet> DependencyProvider<P, VS>
+    for OldestVersionsDependencyProvider<P, VS>
+{
+    fn choose_package_version<T: std::borrow::Borrow<P>, U: std::borrow::Borrow<VS>>(
         &self,
         potential_packages: impl Iterator<Item = (T, U)>,
-    ) -> Result<(T, Option<V>), Box<dyn Error>> {
+    ) -> Result<(T, Option<VS::V>), Box<dyn Error>> {
         Ok(choose_package_with_fewest_versions(
             |p| self.0.versions(p).into_iter().flatten().cloned(),
             potential_packages,
         ))
     }
 
-    fn get_dependencies(&self, p: &P, v: &V) -> Result<Dependencies<P, V>, Box<dyn Error>> {
+    fn get_dependencies(&self, p: &P, v: &VS::V) -> Result<Dependencies<P, VS>, Box<dyn Error>> {
         self.0.get_dependencies(p, v)
     }
 }
@@ -62,17 +70,25 @@ impl<DP> TimeoutDependencyProvider<DP> {
     }
 }
 
-impl<P: Package, V: Version, DP: DependencyProvider<P, V>> DependencyProvider<P, V>
+impl<P: Package, VS: VersionSet, DP: DependencyProvider<P, VS>> DependencyProvider<P, VS>
     for TimeoutDependencyProvider<DP>
 {
-    fn choose_package_version<T: std::borrow::Borrow<P>, U: std::borrow::Borrow<Range<V>>>(
+    fn choose_package_version<T: std::borrow::Borrow<P>, U: std::borrow::Borrow<VS>>(
         &self,
         potential_packages: impl Iterator<Item = (T, U)>,
-    ) -> Result<(T, Option<V>), Box<dyn Error>> {
+    ) -> Result<(T, Option<VS::V>), Box<dyn Error>> {
         self.dp.choose_package_version(potential_packages)
     }
 
-    fn get_dependencies(&self, p: &P, v: &V) -> Result<Dependencies<P, V>, Box<dyn Error>> {
+    fn prioritize(&self, package: &P, range: &VS) -> u32 {
+        self.dp.prioritize(package, range)
+    }
+
+    fn known_incompatibilities(&self) -> Vec<Incompatibility<P, VS>> {
+        self.dp.known_incompatibilities()
+    }
+
+    fn get_dependencies(&self, p: &P, v: &VS::V) -> Result<Dependencies<P, VS>, Box<dyn Error>> {
         self.dp.get_dependencies(p, v)
     }
 
@@ -88,7 +104,7 @@ impl<P: Package, V: Version, DP: DependencyProvider<P, V>> DependencyProvider<P,
 #[test]
 #[should_panic]
 fn should_cancel_can_panic() {
-    let mut dependency_provider = OfflineDependencyProvider::<_, NumberVersion>::new();
+    let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
     dependency_provider.add_dependencies(0, 0, vec![(666, Range::any())]);
 
     // Run the algorithm.
@@ -113,15 +129,11 @@ fn string_names() -> impl Strategy<Value = String> {
 /// This generates a random registry index.
 /// Unlike vec((Name, Ver, vec((Name, VerRq), ..), ..)
 /// This strategy has a high probability of having valid dependencies
+#[allow(clippy::type_complexity)]
 pub fn registry_strategy<N: Package + Ord>(
     name: impl Strategy<Value = N>,
     bad_name: N,
-) -> impl Strategy<
-    Value = (
-        OfflineDependencyProvider<N, NumberVersion>,
-        Vec<(N, NumberVersion)>,
-    ),
-> {
+) -> impl Strategy<Value = (OfflineDependencyProvider<N, NumVS>, Vec<(N, NumberVersion)>)> {
     let max_crates = 40;
     let max_versions = 15;
     let shrinkage = 40;
@@ -193,6 +205,9 @@ pub fn registry_strategy<N: Package + Ord>(
                     let (c, d) = order_index(c, d, s.len());
 
                     if let (_, Some(deps)) = &mut list_of_pkgid[b] {
+                        // Only keep the last requirement on a dependency,
+                        // since repeated ones would be intersected.
+                        deps.retain(|(name, _)| name != &dep_name);
                         deps.push((
                             dep_name,
                             if c == 0 && d == s_last_index {
@@ -210,7 +225,7 @@ pub fn registry_strategy<N: Package + Ord>(
                     }
                 }
 
-                let mut dependency_provider = OfflineDependencyProvider::<N, NumberVersion>::new();
+                let mut dependency_provider = OfflineDependencyProvider::<N, NumVS>::new();
 
                 let complicated_len = std::cmp::min(complicated_len, list_of_pkgid.len());
                 let complicated: Vec<_> = if reverse_alphabetical {
@@ -333,8 +348,8 @@ proptest! {
                     (Ok(l), Ok(r)) => assert_eq!(l, r),
                     (Err(PubGrubError::NoSolution(derivation_l)), Err(PubGrubError::NoSolution(derivation_r))) => {
                         prop_assert_eq!(
-                            DefaultStringReporter::report(&derivation_l),
-                            DefaultStringReporter::report(&derivation_r)
+                            DefaultStringReporter::report(derivation_l),
+                            DefaultStringReporter::report(derivation_r)
                         )},
                     _ => panic!("not the same result")
                 }
@@ -360,6 +375,25 @@ proptest! {
         }
     }
 
+    #[test]
+    /// Restarts change what order the packages are decided
+    /// but not the existence of a solution.
+    fn prop_restarts_errors_the_same(
+        (dependency_provider, cases) in registry_strategy(0u16..665, 666),
+        seed in any::<u64>(),
+    )  {
+        let policy = RestartPolicy { conflicts: 1, seed };
+        for (name, ver) in cases {
+            let l = resolve(&TimeoutDependencyProvider::new(dependency_provider.clone(), 50_000), name, ver);
+            let (r, _) = resolve_with_restarts(&TimeoutDependencyProvider::new(dependency_provider.clone(), 50_000), name, ver, policy);
+            match (&l, &r) {
+                (Ok(_), Ok(_)) => (),
+                (Err(_), Err(_)) => (),
+                _ => panic!("not the same result")
+            }
+        }
+    }
+
     #[test]
     fn prop_removing_a_dep_cant_break(
         (dependency_provider, cases) in registry_strategy(0u16..665, 666),
@@ -423,7 +457,7 @@ proptest! {
                 dependency_provider
                     .versions(&p)
                     .unwrap()
-                    .map(move |v| (p, v.clone()))
+                    .map(move |v| (p, *v))
             })
             .collect();
         let to_remove: Set<(_, _)> = indexes_to_remove.iter().map(|x| x.get(&all_versions)).cloned().collect();
@@ -432,10 +466,10 @@ proptest! {
                 Ok(used) => {
                     // If resolution was successful, then unpublishing a version of a crate
                     // that was not selected should not change that.
-                    let mut smaller_dependency_provider = OfflineDependencyProvider::<_, NumberVersion>::new();
+                    let mut smaller_dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
                     for &(n, v) in &all_versions {
                         if used.get(&n) == Some(&v) // it was used
-                           || to_remove.get(&(n, v)).is_none() // or it is not one to be removed
+                           || !to_remove.contains(&(n, v)) // or it is not one to be removed
                         {
                             let deps = match dependency_provider.get_dependencies(&n, &v).unwrap() {
                                 Dependencies::Unknown => panic!(),
@@ -455,9 +489,9 @@ proptest! {
                 Err(_) => {
                     // If resolution was unsuccessful, then it should stay unsuccessful
                     // even if any version of a crate is unpublished.
-                    let mut smaller_dependency_provider = OfflineDependencyProvider::<_, NumberVersion>::new();
+                    let mut smaller_dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
                     for &(n, v) in &all_versions {
-                        if to_remove.get(&(n, v)).is_none() // it is not one to be removed
+                        if !to_remove.contains(&(n, v)) // it is not one to be removed
                         {
                             let deps = match dependency_provider.get_dependencies(&n, &v).unwrap() {
                                 Dependencies::Unknown => panic!(),
@@ -479,6 +513,16 @@ proptest! {
     }
 }
 
+proptest! {
+    #[test]
+    fn prop_version_key_round_trip(major: u32, minor: u32, patch: u32) {
+        let number = NumberVersion(major);
+        prop_assert_eq!(number.version_key().parse::<NumberVersion>(), Ok(number));
+        let semantic = SemanticVersion::new(major, minor, patch);
+        prop_assert_eq!(semantic.version_key().parse::<SemanticVersion>(), Ok(semantic));
+    }
+}
+
 #[cfg(feature = "serde")]
 #[test]
 fn large_case() {
@@ -488,30 +532,33 @@ fn large_case() {
         eprintln!("{}", name);
         let data = std::fs::read_to_string(&case).unwrap();
         if name.ends_with("u16_NumberVersion.ron") {
-            let dependency_provider: OfflineDependencyProvider<u16, NumberVersion> =
-                ron::de::from_str(&data).unwrap();
+            let mut deserializer = ron::de::Deserializer::from_str(&data).unwrap();
+            let dependency_provider =
+                OfflineDependencyProvider::<u16, NumVS>::from_legacy(&mut deserializer).unwrap();
             let mut sat = SatResolve::new(&dependency_provider);
             for p in dependency_provider.packages() {
                 for n in dependency_provider.versions(p).unwrap() {
-                    if let Ok(s) = resolve(&dependency_provider, p.clone(), n.clone()) {
+                    if let Ok(s) = resolve(&dependency_provider, *p, *n) {
                         assert!(sat.sat_is_valid_solution(&s));
                     } else {
-                        assert!(!sat.sat_resolve(p, &n));
+                        assert!(!sat.sat_resolve(p, n));
                     }
                 }
             }
         } else if name.ends_with("str_SemanticVersion.ron") {
-            let dependency_provider: OfflineDependencyProvider<
+            let mut deserializer = ron::de::Deserializer::from_str(&data).unwrap();
+            let dependency_provider = OfflineDependencyProvider::<
                 &str,
-                pubgrub::version::SemanticVersion,
-            > = ron::de::from_str(&data).unwrap();
+                Range<pubgrub::version::SemanticVersion>,
+            >::from_legacy(&mut deserializer)
+            .unwrap();
             let mut sat = SatResolve::new(&dependency_provider);
             for p in dependency_provider.packages() {
                 for n in dependency_provider.versions(p).unwrap() {
-                    if let Ok(s) = resolve(&dependency_provider, p.clone(), n.clone()) {
+                    if let Ok(s) = resolve(&dependency_provider, *p, *n) {
                         assert!(sat.sat_is_valid_solution(&s));
                     } else {
-                        assert!(!sat.sat_resolve(p, &n));
+                        assert!(!sat.sat_resolve(p, n));
                     }
                 }
             }
@@ -1,13 +1,171 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use std::borrow::Borrow;
+use std::cell::{Cell, RefCell};
+use std::error::Error;
+use std::hash::Hash;
+
+use pubgrub::assignment::Assignment;
 use pubgrub::error::PubGrubError;
+use pubgrub::incompatibility::Incompatibility;
+use pubgrub::interning::resolve_interned;
+use pubgrub::layered::LayeredDependencyProvider;
+use pubgrub::package::Package;
 use pubgrub::range::Range;
-use pubgrub::solver::{resolve, OfflineDependencyProvider};
-use pubgrub::version::NumberVersion;
+use pubgrub::report::{DefaultStringReporter, DerivationTree, External, Reporter};
+use pubgrub::solver::{
+    choose_package_with_fewest_counted_versions, choose_package_with_fewest_versions,
+    choose_package_with_fewest_versions_by_package_order,
+    choose_package_with_fewest_versions_by_score, collect_conflicts, is_satisfiable, minimal_core,
+    replay, resolve, resolve_closest_to, resolve_iter, resolve_multi, resolve_recording,
+    resolve_with_assignments, resolve_with_backtrack_strategy, resolve_with_decisions,
+    resolve_with_learned_incompatibilities, resolve_with_max_depth, resolve_with_pins,
+    resolve_with_relaxation, resolve_with_restarts, resolve_with_stats, resolve_with_terms,
+    resolve_with_tree, verify_lock, BacktrackStrategy, Dependencies, DependencyProvider,
+    OfflineDependencyProvider, PreviousSatisfierLevel, ResolutionMode, RestartPolicy, SolverEvent,
+    TraceStep,
+};
+use pubgrub::term::Term;
+use pubgrub::type_aliases::{Map, SelectedDependencies, SelectedDependenciesExt, SolutionDiff};
+use pubgrub::version::{NumberVersion, PrereleaseVersion};
+use pubgrub::version_set::VersionSet;
+
+type NumVS = Range<NumberVersion>;
+
+/// Offline provider with configurable hooks,
+/// recording its decisions and its calls to `get_dependencies`.
+/// Packages are picked with [choose_package_with_fewest_versions],
+/// breaking ties in the order in which the solver lists them,
+/// and versions with the highest score among the selectable ones, newest first.
+struct TestDependencyProvider<VS: VersionSet = NumVS> {
+    offline: OfflineDependencyProvider<&'static str, VS>,
+    /// Package given the highest priority, if any.
+    prioritized: Option<&'static str>,
+    /// Versions rejected right after they are picked.
+    rejected: Vec<(&'static str, VS::V)>,
+    score: fn(&VS::V) -> i64,
+    selectable: fn(&VS, &VS::V) -> bool,
+    #[allow(clippy::type_complexity)]
+    decisions: RefCell<Vec<(&'static str, Option<VS::V>)>>,
+    calls: RefCell<Map<(&'static str, VS::V), usize>>,
+}
+
+impl<VS: VersionSet> TestDependencyProvider<VS> {
+    fn new(offline: OfflineDependencyProvider<&'static str, VS>) -> Self {
+        Self {
+            offline,
+            prioritized: None,
+            rejected: Vec::new(),
+            score: |_| 0,
+            selectable: |range, version| range.contains(version),
+            decisions: RefCell::new(Vec::new()),
+            calls: RefCell::new(Map::default()),
+        }
+    }
+}
+
+impl<VS: VersionSet> DependencyProvider<&'static str, VS> for TestDependencyProvider<VS>
+where
+    VS::V: Hash,
+{
+    fn choose_package_version<T: Borrow<&'static str>, U: Borrow<VS>>(
+        &self,
+        potential_packages: impl Iterator<Item = (T, U)>,
+    ) -> Result<(T, Option<VS::V>), Box<dyn Error>> {
+        let mut potential_packages: Vec<(T, U)> = potential_packages.collect();
+        let (chosen, _) = choose_package_with_fewest_versions::<_, VS, _, _, _, _>(
+            |p| self.offline.versions(p).into_iter().flatten().cloned(),
+            potential_packages
+                .iter()
+                .map(|(p, range)| (p.borrow(), range.borrow())),
+        );
+        let chosen = *chosen;
+        let index = potential_packages
+            .iter()
+            .position(|(p, _)| *p.borrow() == chosen)
+            .unwrap();
+        let (package, range) = potential_packages.swap_remove(index);
+        let selectable_range = range.borrow().clone();
+        let decision = choose_package_with_fewest_versions_by_score(
+            |p| {
+                let versions: Vec<_> = self.offline.versions(p).into_iter().flatten().collect();
+                let selectable = versions
+                    .into_iter()
+                    .rev()
+                    .filter(|v| (self.selectable)(&selectable_range, v));
+                selectable.cloned().collect::<Vec<_>>().into_iter()
+            },
+            |p, v| self.score_version(p, v),
+            std::iter::once((package, range)),
+        );
+        self.decisions
+            .borrow_mut()
+            .push((chosen, decision.1.clone()));
+        Ok(decision)
+    }
+
+    fn prioritize(&self, package: &&'static str, _range: &VS) -> u32 {
+        (Some(*package) == self.prioritized) as u32
+    }
+
+    fn score_version(&self, _package: &&'static str, version: &VS::V) -> i64 {
+        (self.score)(version)
+    }
+
+    fn get_dependencies(
+        &self,
+        package: &&'static str,
+        version: &VS::V,
+    ) -> Result<Dependencies<&'static str, VS>, Box<dyn Error>> {
+        *self
+            .calls
+            .borrow_mut()
+            .entry((*package, version.clone()))
+            .or_default() += 1;
+        self.offline.get_dependencies(package, version)
+    }
+
+    fn get_dev_dependencies(
+        &self,
+        package: &&'static str,
+        version: &VS::V,
+    ) -> Result<Dependencies<&'static str, VS>, Box<dyn Error>> {
+        self.offline.get_dev_dependencies(package, version)
+    }
+
+    fn package_exists(&self, package: &&'static str) -> bool {
+        self.offline.package_exists(package)
+    }
+
+    fn is_virtual(&self, package: &&'static str) -> bool {
+        self.offline.is_virtual(package)
+    }
+
+    fn accept_version(&self, package: &&'static str, version: &VS::V) -> bool {
+        !self
+            .rejected
+            .iter()
+            .any(|(p, v)| p == package && v == version)
+    }
+
+    fn known_incompatibilities(&self) -> Vec<Incompatibility<&'static str, VS>> {
+        self.offline.known_incompatibilities()
+    }
+}
+
+/// Derivation tree of a resolution expected to have no solution.
+fn expect_no_solution<P: Package, VS: VersionSet>(
+    result: Result<SelectedDependencies<P, VS::V>, PubGrubError<P, VS>>,
+) -> DerivationTree<P, VS> {
+    match result {
+        Err(PubGrubError::NoSolution(derivation_tree)) => derivation_tree,
+        other => panic!("expected no solution, got {:?}", other),
+    }
+}
 
 #[test]
 fn same_result_on_repeated_runs() {
-    let mut dependency_provider = OfflineDependencyProvider::<_, NumberVersion>::new();
+    let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
 
     dependency_provider.add_dependencies("c", 0, vec![]);
     dependency_provider.add_dependencies("c", 2, vec![]);
@@ -27,9 +185,91 @@ fn same_result_on_repeated_runs() {
     }
 }
 
+#[test]
+fn same_decisions_with_fresh_providers() {
+    // Versions 2 of "a" and "b" are incompatible, so the solution
+    // depends on which of those two packages is decided first.
+    let build_provider = || {
+        let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
+        dependency_provider.add_dependencies(
+            "root",
+            0,
+            vec![("a", Range::any()), ("b", Range::any())],
+        );
+        dependency_provider.add_dependencies("a", 1, vec![]);
+        dependency_provider.add_dependencies("a", 2, vec![("b", Range::between(0, 2))]);
+        dependency_provider.add_dependencies("b", 1, vec![]);
+        dependency_provider.add_dependencies("b", 2, vec![("a", Range::between(0, 2))]);
+        dependency_provider
+    };
+
+    let solve = || {
+        let solution = resolve(&build_provider(), "root", 0).unwrap();
+        solution.into_iter().collect::<Vec<_>>()
+    };
+    let first = solve();
+    // Ties are broken by package order, so "a" is decided first,
+    // and the outcome is the same from one process to the next.
+    assert_eq!(
+        first.iter().cloned().collect::<Map<_, _>>(),
+        [("root", 0), ("a", 2), ("b", 1)]
+            .iter()
+            .map(|&(p, v)| (p, NumberVersion(v)))
+            .collect()
+    );
+    for _ in 0..100 {
+        assert_eq!(first, solve());
+    }
+}
+
+#[test]
+fn same_decisions_whatever_the_insertion_order() {
+    // Versions 2 of the packages of each pair are incompatible,
+    // so the solution depends on which package of a pair is decided first.
+    let pairs: Vec<(String, String)> = (0..20)
+        .map(|i| (format!("a{}", i), format!("b{}", i)))
+        .collect();
+    type Registration = (String, u32, Vec<(String, NumVS)>);
+    let mut packages: Vec<Registration> = vec![(
+        "root".to_string(),
+        0,
+        pairs
+            .iter()
+            .flat_map(|(a, b)| [(a.clone(), Range::any()), (b.clone(), Range::any())])
+            .collect(),
+    )];
+    for (a, b) in &pairs {
+        packages.push((a.clone(), 1, vec![]));
+        packages.push((a.clone(), 2, vec![(b.clone(), Range::between(0, 2))]));
+        packages.push((b.clone(), 1, vec![]));
+        packages.push((b.clone(), 2, vec![(a.clone(), Range::between(0, 2))]));
+    }
+
+    let solve = |packages: &[Registration]| {
+        let mut dependency_provider = OfflineDependencyProvider::<String, NumVS>::new();
+        for (package, version, dependencies) in packages {
+            dependency_provider.add_dependencies(
+                package.clone(),
+                *version,
+                dependencies.iter().cloned(),
+            );
+        }
+        resolve(&dependency_provider, "root".to_string(), 0).unwrap()
+    };
+    let first = solve(&packages);
+    for (a, b) in &pairs {
+        assert_eq!(first[a], NumberVersion(2));
+        assert_eq!(first[b], NumberVersion(1));
+    }
+    packages.reverse();
+    assert_eq!(first, solve(&packages));
+    packages.sort_by(|(p1, v1, _), (p2, v2, _)| (v2, p1).cmp(&(v1, p2)));
+    assert_eq!(first, solve(&packages));
+}
+
 #[test]
 fn should_always_find_a_satisfier() {
-    let mut dependency_provider = OfflineDependencyProvider::<_, NumberVersion>::new();
+    let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
     dependency_provider.add_dependencies("a", 0, vec![("b", Range::none())]);
     assert!(matches!(
         resolve(&dependency_provider, "a", 0),
@@ -45,10 +285,2156 @@ fn should_always_find_a_satisfier() {
 
 #[test]
 fn cannot_depend_on_self() {
-    let mut dependency_provider = OfflineDependencyProvider::<_, NumberVersion>::new();
+    let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
     dependency_provider.add_dependencies("a", 0, vec![("a", Range::any())]);
     assert!(matches!(
         resolve(&dependency_provider, "a", 0),
         Err(PubGrubError::SelfDependency { .. })
     ));
 }
+
+#[test]
+fn priority_changes_decision_order() {
+    // "a" has fewer versions so it is decided first by default,
+    // but every version of "b" requires "a" 1.
+    let mut offline = OfflineDependencyProvider::<_, NumVS>::new();
+    offline.add_dependencies("root", 0, vec![("a", Range::any()), ("b", Range::any())]);
+    offline.add_dependencies("a", 1, vec![]);
+    offline.add_dependencies("a", 2, vec![]);
+    for v in 1..=3 {
+        offline.add_dependencies("b", v, vec![("a", Range::exact(1))]);
+    }
+
+    let resolve_with = |prioritized| {
+        let provider = TestDependencyProvider {
+            prioritized,
+            ..TestDependencyProvider::new(offline.clone())
+        };
+        let solution = resolve(&provider, "root", 0).unwrap();
+        assert_eq!(solution.get("a"), Some(&NumberVersion(1)));
+        let decisions = provider.decisions.into_inner();
+        decisions.into_iter().map(|(p, _)| p).collect::<Vec<_>>()
+    };
+
+    let default_order = resolve_with(None);
+    let prioritized_order = resolve_with(Some("b"));
+    assert_eq!(default_order[1], "a");
+    assert_eq!(prioritized_order[1], "b");
+    assert!(prioritized_order.len() < default_order.len());
+}
+
+#[test]
+fn root_is_decided_before_packages_of_known_incompatibilities() {
+    // The known dependency of the root derives "a" before the root is decided.
+    let mut offline = OfflineDependencyProvider::<_, NumVS>::new();
+    offline.add_dependencies("root", 0, vec![]);
+    offline.add_incompatibility(Incompatibility::from_dependency(
+        "root",
+        Range::exact(0),
+        "a",
+        Range::any(),
+    ));
+    offline.add_dependencies("a", 1, vec![]);
+    // "b" does not exist, so "a" 2 leads to a conflict once decided.
+    offline.add_dependencies("a", 2, vec![("b", Range::any())]);
+
+    let provider = TestDependencyProvider {
+        prioritized: Some("a"),
+        ..TestDependencyProvider::new(offline)
+    };
+    let solution = resolve(&provider, "root", 0).unwrap();
+    assert_eq!(solution.get("a"), Some(&NumberVersion(1)));
+    assert_eq!(provider.decisions.into_inner()[0].0, "root");
+}
+
+#[test]
+fn known_incompatibility_is_respected() {
+    let mut offline = OfflineDependencyProvider::<_, NumVS>::new();
+    offline.add_dependencies("root", 0, vec![("a", Range::any()), ("b", Range::any())]);
+    offline.add_dependencies("a", 1, vec![]);
+    offline.add_dependencies("a", 2, vec![]);
+    offline.add_dependencies("b", 1, vec![]);
+    offline.add_dependencies("b", 2, vec![]);
+
+    // "a" 2 conflicts with "b" 2, in other words "a" 2 depends on "b" not 2.
+    let conflict =
+        Incompatibility::from_dependency("a", Range::exact(2), "b", Range::exact(2).negate());
+    assert_eq!(conflict.get(&"a"), Some(&Term::Positive(Range::exact(2))));
+    assert_eq!(
+        conflict.get(&"b"),
+        Some(&Term::Negative(Range::exact(2).negate()))
+    );
+    assert_eq!(conflict.iter().count(), 2);
+    assert!(matches!(
+        conflict.external(),
+        Some(External::FromDependencyOf(..))
+    ));
+
+    let without_conflict = resolve(&offline, "root", 0).unwrap();
+    assert_eq!(without_conflict.get("a"), Some(&NumberVersion(2)));
+    assert_eq!(without_conflict.get("b"), Some(&NumberVersion(2)));
+
+    offline.add_incompatibility(conflict);
+    let with_conflict = resolve(&offline, "root", 0).unwrap();
+    assert_ne!(
+        (with_conflict.get("a"), with_conflict.get("b")),
+        (Some(&NumberVersion(2)), Some(&NumberVersion(2)))
+    );
+}
+
+#[test]
+fn forbidden_combination_rejects_solution() {
+    let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
+    dependency_provider.add_dependencies("root", 0, vec![("a", Range::any()), ("b", Range::any())]);
+    dependency_provider.add_dependencies("a", 1, vec![]);
+    dependency_provider.add_dependencies("b", 1, vec![]);
+    assert!(resolve(&dependency_provider, "root", 0).is_ok());
+
+    dependency_provider.forbid_combination("a", Range::any(), "b", Range::exact(1));
+    let derivation_tree = expect_no_solution(resolve(&dependency_provider, "root", 0));
+    let report = DefaultStringReporter::report(&derivation_tree);
+    assert!(report.contains("a is incompatible with b 1"), "{}", report);
+}
+
+#[test]
+fn forbidden_combination_of_a_package_with_itself() {
+    let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
+    dependency_provider.add_dependencies("root", 0, vec![("a", Range::any())]);
+    dependency_provider.add_dependencies("a", 1, vec![]);
+    dependency_provider.add_dependencies("a", 2, vec![]);
+
+    // Both terms are on "a", so this only excludes "a" 2.
+    dependency_provider.forbid_combination("a", Range::any(), "a", Range::exact(2));
+    let solution = resolve(&dependency_provider, "root", 0).unwrap();
+    assert_eq!(solution.get("a"), Some(&NumberVersion(1)));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialized_provider_keeps_forbidden_combinations() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 0, vec![("a", Range::any()), ("b", Range::any())]);
+    dependency_provider.add_dependencies("a", 1, vec![]);
+    dependency_provider.add_dependencies("b", 1, vec![]);
+    dependency_provider.forbid_combination("a", Range::any(), "b", Range::exact(1));
+
+    let json = serde_json::to_string(&dependency_provider).unwrap();
+    let deserialized: OfflineDependencyProvider<&str, NumVS> = serde_json::from_str(&json).unwrap();
+    expect_no_solution(resolve(&deserialized, "root", 0));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialized_provider_keeps_dev_dependencies_lockstep_groups_mode_and_any_of() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1,
+        vec![("a", Range::higher_than(2)), ("b", Range::any())],
+    );
+    dependency_provider.add_dev_dependencies("root", 1, vec![("linter", Range::any())]);
+    for package in ["a", "b", "linter"] {
+        dependency_provider.add_dependencies(package, 1, vec![]);
+        dependency_provider.add_dependencies(package, 2, vec![]);
+    }
+    dependency_provider.add_version_lockstep_group(&["a", "b"]);
+    dependency_provider.set_resolution_mode(ResolutionMode::Minimal);
+    dependency_provider.require_any_of("root", 1, "AnyOf#1", &["a", "b"]);
+
+    let json = serde_json::to_string(&dependency_provider).unwrap();
+    let deserialized: OfflineDependencyProvider<&str, NumVS> = serde_json::from_str(&json).unwrap();
+    assert!(deserialized.is_virtual(&"AnyOf#1"));
+    let solution = resolve(&deserialized, "root", 1).unwrap();
+    assert_eq!(solution, resolve(&dependency_provider, "root", 1).unwrap());
+    assert_eq!(solution.get("linter"), Some(&NumberVersion(1)));
+    assert_eq!(solution.get("b"), Some(&NumberVersion(2)));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn deserialize_provider_serialized_as_its_dependencies_only() {
+    // The large case was serialized before providers held anything but their dependencies.
+    let data = std::fs::read_to_string("test-examples/large_case_u16_NumberVersion.ron").unwrap();
+    let mut deserializer = ron::de::Deserializer::from_str(&data).unwrap();
+    let dependency_provider =
+        OfflineDependencyProvider::<u16, NumVS>::from_legacy(&mut deserializer).unwrap();
+    let dependencies: Map<u16, std::collections::BTreeMap<NumberVersion, Map<u16, NumVS>>> =
+        ron::de::from_str(&data).unwrap();
+    assert_eq!(dependency_provider.packages().count(), dependencies.len());
+    for (package, versions) in &dependencies {
+        for (version, package_dependencies) in versions {
+            assert_eq!(
+                dependency_provider.dependencies(package, version),
+                Some(package_dependencies)
+            );
+        }
+    }
+
+    // Packages named like the fields of the current format are read as packages.
+    let json =
+        r#"{"dependencies":{"1":{"resolution_mode":[[0,null]]}},"resolution_mode":{"1":{}}}"#;
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let dependency_provider =
+        OfflineDependencyProvider::<String, NumVS>::from_legacy(&mut deserializer).unwrap();
+    let solution = resolve(&dependency_provider, "dependencies".to_string(), 1).unwrap();
+    assert_eq!(solution["resolution_mode"], NumberVersion(1));
+
+    // The current format is not read as the previous one.
+    assert!(serde_json::from_str::<OfflineDependencyProvider<String, NumVS>>(json).is_err());
+    let mut dependency_provider = OfflineDependencyProvider::<u16, NumVS>::new();
+    dependency_provider.add_dependencies(0, 1, vec![(1, Range::any())]);
+    dependency_provider.add_dependencies(1, 1, vec![]);
+    dependency_provider.set_resolution_mode(ResolutionMode::Minimal);
+    let ron = ron::ser::to_string(&dependency_provider).unwrap();
+    let deserialized: OfflineDependencyProvider<u16, NumVS> = ron::de::from_str(&ron).unwrap();
+    assert_eq!(
+        resolve(&deserialized, 0, 1).unwrap(),
+        resolve(&dependency_provider, 0, 1).unwrap()
+    );
+}
+
+#[test]
+fn multiple_roots_coexist() {
+    let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
+    dependency_provider.add_dependencies("a", 1, vec![("shared", Range::higher_than(1))]);
+    dependency_provider.add_dependencies("b", 1, vec![("shared", Range::strictly_lower_than(3))]);
+    dependency_provider.add_dependencies("shared", 1, vec![]);
+    dependency_provider.add_dependencies("shared", 2, vec![]);
+    dependency_provider.add_dependencies("shared", 3, vec![]);
+
+    let roots = vec![("a", NumberVersion(1)), ("b", NumberVersion(1))];
+    let solution = resolve_multi(&dependency_provider, "workspace", 0, roots).unwrap();
+    assert_eq!(solution.len(), 3);
+    assert_eq!(solution.get("a"), Some(&NumberVersion(1)));
+    assert_eq!(solution.get("b"), Some(&NumberVersion(1)));
+    assert_eq!(solution.get("shared"), Some(&NumberVersion(2)));
+}
+
+#[test]
+fn multiple_roots_conflict() {
+    let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
+    dependency_provider.add_dependencies("a", 1, vec![("c", Range::any())]);
+    dependency_provider.add_dependencies("b", 1, vec![("shared", Range::higher_than(2))]);
+    dependency_provider.add_dependencies("c", 1, vec![("shared", Range::strictly_lower_than(2))]);
+    dependency_provider.add_dependencies("shared", 1, vec![]);
+    dependency_provider.add_dependencies("shared", 2, vec![]);
+
+    let roots = vec![("a", NumberVersion(1)), ("b", NumberVersion(1))];
+    let derivation_tree =
+        expect_no_solution(resolve_multi(&dependency_provider, "workspace", 0, roots));
+    let report = DefaultStringReporter::report(&derivation_tree);
+    assert!(report.contains("workspace 0 depends on"), "{}", report);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn derivation_tree_to_json() {
+    let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
+    // Diamond: both sides depend on "shared", with incompatible requirements.
+    dependency_provider.add_dependencies(
+        "root",
+        1,
+        vec![("left", Range::any()), ("right", Range::any())],
+    );
+    dependency_provider.add_dependencies("left", 1, vec![("shared", Range::exact(1))]);
+    dependency_provider.add_dependencies("left", 2, vec![("shared", Range::exact(1))]);
+    dependency_provider.add_dependencies("right", 1, vec![("shared", Range::exact(2))]);
+    dependency_provider.add_dependencies("shared", 1, vec![]);
+    dependency_provider.add_dependencies("shared", 2, vec![]);
+
+    let derivation_tree = expect_no_solution(resolve(&dependency_provider, "root", 1));
+
+    fn collect_externals(tree: &DerivationTree<&str, NumVS>, externals: &mut Vec<String>) {
+        match tree {
+            DerivationTree::External(external) => {
+                let external = serde_json::to_value(external).unwrap().to_string();
+                if !externals.contains(&external) {
+                    externals.push(external);
+                }
+            }
+            DerivationTree::Derived(derived) => {
+                collect_externals(&derived.cause1, externals);
+                collect_externals(&derived.cause2, externals);
+            }
+        }
+    }
+    let mut expected = Vec::new();
+    collect_externals(&derivation_tree, &mut expected);
+
+    let json = serde_json::to_value(&derivation_tree).unwrap();
+    let incompatibilities = json["incompatibilities"].as_array().unwrap();
+    let externals: Vec<_> = incompatibilities
+        .iter()
+        .filter(|incompat| incompat["kind"] == "external")
+        .map(|incompat| incompat["external"].to_string())
+        .collect();
+    assert_eq!(externals.len(), expected.len());
+    for external in &expected {
+        let count = externals.iter().filter(|e| *e == external).count();
+        assert_eq!(count, 1, "{} in {}", external, json);
+    }
+
+    // Causes are referenced by ids of previously listed incompatibilities.
+    for (id, incompat) in incompatibilities.iter().enumerate() {
+        assert_eq!(incompat["id"], id);
+        if incompat["kind"] == "derived" {
+            assert!(incompat["cause1"].as_u64().unwrap() < id as u64);
+            assert!(incompat["cause2"].as_u64().unwrap() < id as u64);
+        }
+    }
+    assert_eq!(json["root"], incompatibilities.len() - 1);
+}
+
+#[test]
+fn dependencies_builder_matches_doc_example() {
+    let built = Dependencies::<&str, NumVS>::builder()
+        .require("menu", Range::any())
+        .require("icons", Range::any())
+        .build();
+    assert_eq!(built, vec![("menu", Range::any()), ("icons", Range::any())]);
+
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1, built);
+    dependency_provider.add_dependencies(
+        "menu",
+        1,
+        Dependencies::builder()
+            .require("dropdown", Range::any())
+            .build(),
+    );
+    dependency_provider.add_dependencies(
+        "dropdown",
+        1,
+        Dependencies::builder()
+            .require("icons", Range::any())
+            .build(),
+    );
+    dependency_provider.add_dependencies("icons", 1, vec![]);
+    let solution = resolve(&dependency_provider, "root", 1).unwrap();
+    assert_eq!(solution.len(), 4);
+}
+
+#[test]
+fn dependencies_builder_parses_constraints() {
+    let built = Dependencies::<&str, NumVS>::builder()
+        .require_str("menu", ">=1")
+        .unwrap()
+        .require("icons", Range::any())
+        .require_str("menu", "<3 || =5")
+        .unwrap()
+        .build();
+    assert_eq!(
+        built,
+        vec![
+            ("menu", Range::between(1, 3).union(&Range::exact(5))),
+            ("icons", Range::any())
+        ]
+    );
+    assert!(Dependencies::<&str, NumVS>::builder()
+        .require_str("menu", "~1")
+        .is_err());
+}
+
+#[test]
+fn registering_dependencies_twice() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    // A package required multiple times in the same call is required in the intersection.
+    dependency_provider.add_dependencies(
+        "root",
+        1,
+        vec![
+            ("menu", Range::higher_than(1)),
+            ("menu", Range::strictly_lower_than(3)),
+        ],
+    );
+    // A second call replaces the dependencies of the first one.
+    dependency_provider.add_dependencies("menu", 2, vec![("icons", Range::any())]);
+    dependency_provider.add_dependencies("menu", 2, vec![]);
+    dependency_provider.add_dependencies("menu", 3, vec![]);
+
+    match dependency_provider.get_dependencies(&"root", &NumberVersion(1)) {
+        Ok(Dependencies::Known(dependencies)) => {
+            assert_eq!(dependencies.len(), 1);
+            assert_eq!(dependencies["menu"], Range::between(1, 3));
+        }
+        _ => panic!("root dependencies must be known"),
+    }
+    let solution = resolve(&dependency_provider, "root", 1).unwrap();
+    assert_eq!(solution.get("menu"), Some(&NumberVersion(2)));
+    assert!(!solution.contains_key("icons"));
+}
+
+#[test]
+fn pins_select_locked_versions() {
+    let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
+    dependency_provider.add_dependencies("root", 0, vec![("a", Range::any())]);
+    dependency_provider.add_dependencies("a", 1, vec![]);
+    dependency_provider.add_dependencies("a", 2, vec![]);
+
+    let mut pins = Map::default();
+    pins.insert("a", NumberVersion(1));
+    // Pins of packages that are not needed are not selected.
+    pins.insert("unused", NumberVersion(1));
+    let solution = resolve_with_pins(&dependency_provider, "root", 0, &pins).unwrap();
+    assert_eq!(solution.len(), 2);
+    assert_eq!(solution.get("a"), Some(&NumberVersion(1)));
+}
+
+#[test]
+fn pin_conflicting_with_dependencies() {
+    let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
+    dependency_provider.add_dependencies("root", 0, vec![("a", Range::strictly_lower_than(2))]);
+    dependency_provider.add_dependencies("a", 1, vec![]);
+    dependency_provider.add_dependencies("a", 2, vec![]);
+
+    let mut pins = Map::default();
+    pins.insert("a", NumberVersion(2));
+    let derivation_tree =
+        expect_no_solution(resolve_with_pins(&dependency_provider, "root", 0, &pins));
+    let report = DefaultStringReporter::report(&derivation_tree);
+    assert!(report.contains("a is pinned to 2"), "{}", report);
+}
+
+fn pre(version: &str) -> PrereleaseVersion {
+    version.parse().unwrap()
+}
+
+#[test]
+fn prereleases_are_opt_in() {
+    let solve = |requirement: &str| {
+        let mut offline = OfflineDependencyProvider::new();
+        offline.add_dependencies(
+            "root",
+            pre("1.0.0"),
+            Dependencies::builder()
+                .require_str("a", requirement)
+                .unwrap()
+                .build(),
+        );
+        offline.add_dependencies("a", pre("1.0.0"), vec![]);
+        offline.add_dependencies("a", pre("1.1.0-beta"), vec![]);
+        // Prereleases are only selectable if requested.
+        let provider = TestDependencyProvider {
+            selectable: |range: &Range<PrereleaseVersion>, version: &PrereleaseVersion| {
+                range.contains_selectable(version)
+            },
+            ..TestDependencyProvider::new(offline)
+        };
+        resolve(&provider, "root", pre("1.0.0")).unwrap()["a"].clone()
+    };
+    assert_eq!(solve(">=1.0.0"), pre("1.0.0"));
+    assert_eq!(solve(">=1.1.0-beta"), pre("1.1.0-beta"));
+}
+
+#[test]
+fn minimal_core_names_conflicting_packages() {
+    let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1,
+        vec![
+            ("a", Range::any()),
+            ("b", Range::any()),
+            ("irrelevant", Range::any()),
+        ],
+    );
+    // "irrelevant" needs backtracking, but always has a valid version.
+    dependency_provider.add_dependencies("irrelevant", 1, vec![("other", Range::any())]);
+    dependency_provider.add_dependencies("irrelevant", 2, vec![("missing", Range::any())]);
+    dependency_provider.add_dependencies("other", 1, vec![]);
+    // The real conflict: "a" and "b" need different versions of "shared".
+    dependency_provider.add_dependencies("a", 1, vec![("shared", Range::exact(1))]);
+    dependency_provider.add_dependencies("b", 1, vec![("shared", Range::exact(2))]);
+    dependency_provider.add_dependencies("shared", 1, vec![]);
+    dependency_provider.add_dependencies("shared", 2, vec![]);
+
+    let derivation_tree = expect_no_solution(resolve(&dependency_provider, "root", 1));
+    let core = minimal_core(&derivation_tree);
+    let mut packages: Vec<_> = core
+        .iter()
+        .flat_map(|external| match external {
+            External::NotRoot(p, _) => vec![*p],
+            External::NoVersions(p, _) | External::UnavailableDependencies(p, _) => vec![*p],
+            External::FromDependencyOf(p1, _, p2, _)
+            | External::ForbiddenCombination(p1, _, p2, _) => vec![*p1, *p2],
+            External::Pinned(p, _) => vec![*p],
+            External::Forbidden(p1, _, p2) => vec![*p1, *p2],
+            External::ConditionalDependency(p, _, condition, _, dep, _) => {
+                vec![*p, *condition, *dep]
+            }
+            External::Lockstep(p1, _, p2) => vec![*p1, *p2],
+        })
+        .collect();
+    packages.sort_unstable();
+    packages.dedup();
+    assert_eq!(packages, vec!["a", "b", "root", "shared"]);
+}
+
+/// Distinct external incompatibilities of a derivation tree.
+fn externals(
+    derivation_tree: &DerivationTree<&'static str, NumVS>,
+) -> Vec<External<&'static str, NumVS>> {
+    match derivation_tree {
+        DerivationTree::External(external) => vec![external.clone()],
+        DerivationTree::Derived(derived) => {
+            let mut externals = externals(&derived.cause1);
+            for external in self::externals(&derived.cause2) {
+                if !externals.contains(&external) {
+                    externals.push(external);
+                }
+            }
+            externals
+        }
+    }
+}
+
+#[test]
+fn minimal_core_drops_redundant_externals() {
+    let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1, vec![("a", Range::any())]);
+    dependency_provider.add_dependencies("a", 1, vec![("b", Range::exact(2))]);
+    dependency_provider.add_dependencies("a", 2, vec![("b", Range::exact(0))]);
+    dependency_provider.add_dependencies("b", 1, vec![]);
+
+    let derivation_tree = expect_no_solution(resolve(&dependency_provider, "root", 1));
+    let externals = externals(&derivation_tree);
+    let core = minimal_core(&derivation_tree);
+    // Both ways "b" runs out of versions are part of the derivation,
+    // but the wider one is enough to explain the failure.
+    let missing_b = Range::exact(0).union(&Range::exact(2));
+    assert!(externals.contains(&External::NoVersions("b", missing_b.clone())));
+    assert!(externals.contains(&External::NoVersions("b", Range::exact(0))));
+    assert!(core.contains(&External::NoVersions("b", missing_b)));
+    assert!(!core.contains(&External::NoVersions("b", Range::exact(0))));
+    assert_eq!(core.len(), externals.len() - 1);
+    assert!(core.iter().all(|external| externals.contains(external)));
+}
+
+#[test]
+fn suggestions_relax_root_constraint() {
+    let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
+    // root depends on a <2 and b, but b needs a >=2.
+    dependency_provider.add_dependencies(
+        "root",
+        1,
+        vec![("a", Range::between(1, 2)), ("b", Range::any())],
+    );
+    dependency_provider.add_dependencies("b", 1, vec![("a", Range::between(2, 3))]);
+    dependency_provider.add_dependencies("a", 1, vec![]);
+    dependency_provider.add_dependencies("a", 2, vec![]);
+
+    let derivation_tree = expect_no_solution(resolve(&dependency_provider, "root", 1));
+    let suggestions = derivation_tree.suggestions();
+    assert_eq!(suggestions, vec![("a", Range::between(1, 3))]);
+    assert!(suggestions[0].1.contains(&2.into()));
+}
+
+#[test]
+fn highest_score_version_is_picked() {
+    let mut offline = OfflineDependencyProvider::<_, NumVS>::new();
+    offline.add_dependencies("root", 1, vec![("a", Range::strictly_lower_than(6))]);
+    for v in 1..=6 {
+        offline.add_dependencies("a", v, vec![]);
+    }
+    // Versions in range in preference order are 5, 4, 3, 2, 1,
+    // so the first matching version is 5, but 4 and 2 score higher.
+    let provider = TestDependencyProvider {
+        score: |version: &NumberVersion| 1 - (version.0 % 2) as i64,
+        ..TestDependencyProvider::new(offline)
+    };
+    let solution = resolve(&provider, "root", 1).unwrap();
+    assert_eq!(solution.get("a"), Some(&NumberVersion(4)));
+}
+
+#[test]
+fn highest_score_version_is_picked_by_wrapped_providers() {
+    let mut offline = OfflineDependencyProvider::<_, NumVS>::new();
+    offline.add_dependencies("root", 1, vec![("a", Range::strictly_lower_than(6))]);
+    for v in 1..=6 {
+        offline.add_dependencies("a", v, vec![]);
+    }
+    let provider = TestDependencyProvider {
+        score: |version: &NumberVersion| 1 - (version.0 % 2) as i64,
+        ..TestDependencyProvider::new(offline)
+    };
+    let solution = resolve_multi(&provider, "workspace", 0, vec![("root", 1.into())]).unwrap();
+    assert_eq!(solution.get("a"), Some(&NumberVersion(4)));
+    let solution = resolve_closest_to(&provider, "root", 1, &SelectedDependencies::default());
+    assert_eq!(solution.unwrap().get("a"), Some(&NumberVersion(4)));
+}
+
+#[test]
+fn replay_recorded_trace() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1,
+        vec![("menu", Range::any()), ("icons", Range::any())],
+    );
+    dependency_provider.add_dependencies("menu", 1, vec![("dropdown", Range::any())]);
+    dependency_provider.add_dependencies("dropdown", 1, vec![("icons", Range::any())]);
+    dependency_provider.add_dependencies("icons", 1, vec![]);
+
+    let (solution, trace) = resolve_recording(&dependency_provider, "root", 1);
+    let solution = solution.unwrap();
+    assert_eq!(trace.root, ("root", NumberVersion(1)));
+    assert_eq!(trace.steps.len(), 4);
+    assert_eq!(replay(&dependency_provider, &trace).unwrap(), solution);
+
+    // Replaying with different dependencies diverges when "dropdown" is not needed anymore.
+    dependency_provider.add_dependencies("menu", 1, vec![]);
+    match replay(&dependency_provider, &trace) {
+        Err(PubGrubError::ReplayDivergence { step }) => {
+            assert!(step < trace.steps.len());
+        }
+        other => panic!("expected a divergence, got {:?}", other),
+    }
+}
+
+#[test]
+fn replay_recorded_backtracking() {
+    // Conflict resolution with a partial satisfier, from the PubGrub documentation.
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1,
+        vec![("foo", Range::any()), ("target", Range::exact(2))],
+    );
+    dependency_provider.add_dependencies("foo", 1, vec![]);
+    dependency_provider.add_dependencies(
+        "foo",
+        2,
+        vec![("left", Range::any()), ("right", Range::any())],
+    );
+    dependency_provider.add_dependencies("left", 1, vec![("shared", Range::higher_than(1))]);
+    dependency_provider.add_dependencies(
+        "right",
+        1,
+        vec![("shared", Range::strictly_lower_than(2))],
+    );
+    dependency_provider.add_dependencies("shared", 1, vec![("target", Range::exact(1))]);
+    dependency_provider.add_dependencies("shared", 2, vec![]);
+    dependency_provider.add_dependencies("target", 1, vec![]);
+    dependency_provider.add_dependencies("target", 2, vec![]);
+
+    let (solution, trace) = resolve_recording(&dependency_provider, "root", 1);
+    assert!(trace
+        .steps
+        .iter()
+        .any(|step| matches!(step, TraceStep::Backtrack(_))));
+    assert_eq!(
+        replay(&dependency_provider, &trace).unwrap(),
+        solution.unwrap()
+    );
+}
+
+#[test]
+fn iterate_over_resolution_events() {
+    let dependency_provider = partial_satisfier_provider();
+    let mut events: Vec<_> = resolve_iter(&dependency_provider, "root", 1).collect();
+    let (solution, trace) = resolve_recording(&dependency_provider, "root", 1);
+    match events.pop() {
+        Some(SolverEvent::Done(Ok(iter_solution))) => assert_eq!(iter_solution, solution.unwrap()),
+        other => panic!("expected a solution, got {:?}", other),
+    }
+    let steps: Vec<_> = events
+        .into_iter()
+        .map(|event| match event {
+            SolverEvent::Step(step) => step,
+            SolverEvent::Done(_) => panic!("the result must be the last event"),
+        })
+        .collect();
+    assert_eq!(steps, trace.steps);
+
+    // Errors are also the last event.
+    let events: Vec<_> = resolve_iter(&dependency_provider, "root", 2).collect();
+    assert!(matches!(
+        events.last(),
+        Some(SolverEvent::Done(Err(
+            PubGrubError::RootVersionNotAvailable { .. }
+        )))
+    ));
+}
+
+#[test]
+fn dependencies_are_retrieved_once() {
+    let mut offline = OfflineDependencyProvider::<&str, NumVS>::new();
+    offline.add_dependencies(
+        "root",
+        1,
+        vec![("foo", Range::any()), ("target", Range::exact(2))],
+    );
+    offline.add_dependencies("foo", 1, vec![]);
+    offline.add_dependencies(
+        "foo",
+        2,
+        vec![("left", Range::any()), ("right", Range::any())],
+    );
+    offline.add_dependencies("left", 1, vec![("shared", Range::higher_than(1))]);
+    offline.add_dependencies("right", 1, vec![("shared", Range::strictly_lower_than(2))]);
+    offline.add_dependencies("shared", 1, vec![("target", Range::exact(1))]);
+    offline.add_dependencies("shared", 2, vec![]);
+    offline.add_dependencies("target", 1, vec![]);
+    offline.add_dependencies("target", 2, vec![]);
+
+    let provider = TestDependencyProvider::new(offline);
+    resolve(&provider, "root", 1).unwrap();
+    // Backtracking leads to deciding "target" 2 twice.
+    let decisions = provider.decisions.borrow();
+    let target_decisions = decisions
+        .iter()
+        .filter(|d| **d == ("target", Some(NumberVersion(2))))
+        .count();
+    assert_eq!(target_decisions, 2);
+    assert!(provider.calls.borrow().values().all(|calls| *calls == 1));
+}
+
+#[test]
+fn unknown_package_is_not_found() {
+    let mut offline = OfflineDependencyProvider::<&str, NumVS>::new();
+    offline.add_dependencies(
+        "root",
+        1,
+        vec![("menu", Range::any()), ("icnos", Range::any())],
+    );
+    offline.add_dependencies("menu", 1, vec![]);
+    offline.add_dependencies("icons", 1, vec![]);
+    match resolve(&offline, "root", 1) {
+        Err(PubGrubError::PackageNotFound { package }) => assert_eq!(package, "icnos"),
+        other => panic!("expected a missing package, got {:?}", other),
+    }
+}
+
+#[test]
+fn unknown_package_of_a_backtracked_version_is_avoided() {
+    let mut offline = OfflineDependencyProvider::<&str, NumVS>::new();
+    offline.add_dependencies("root", 1, vec![("a", Range::any())]);
+    offline.add_dependencies("a", 1, vec![]);
+    offline.add_dependencies("a", 2, vec![("ghost", Range::any())]);
+    let solution = resolve(&offline, "root", 1).unwrap();
+    assert_eq!(solution.get("a"), Some(&NumberVersion(1)));
+}
+
+#[test]
+fn known_package_without_version_in_range() {
+    let mut offline = OfflineDependencyProvider::<&str, NumVS>::new();
+    offline.add_dependencies("root", 1, vec![("icons", Range::higher_than(2))]);
+    offline.add_dependencies("icons", 1, vec![]);
+    let derivation_tree = expect_no_solution(resolve(&offline, "root", 1));
+    let report = DefaultStringReporter::report(&derivation_tree);
+    assert!(
+        report.contains("there is no version of icons"),
+        "{}",
+        report
+    );
+}
+
+#[test]
+fn rejected_version_is_skipped() {
+    let mut offline = OfflineDependencyProvider::<&str, NumVS>::new();
+    offline.add_dependencies("root", 1, vec![("menu", Range::any())]);
+    offline.add_dependencies("menu", 1, vec![]);
+    offline.add_dependencies("menu", 2, vec![]);
+    let provider = TestDependencyProvider {
+        rejected: vec![("menu", NumberVersion(2))],
+        ..TestDependencyProvider::new(offline)
+    };
+    let solution = resolve(&provider, "root", 1).unwrap();
+    assert_eq!(solution.get("menu"), Some(&NumberVersion(1)));
+
+    let provider = TestDependencyProvider {
+        rejected: vec![("menu", NumberVersion(1)), ("menu", NumberVersion(2))],
+        ..provider
+    };
+    expect_no_solution(resolve(&provider, "root", 1));
+}
+
+#[test]
+fn is_satisfiable_agrees_with_resolve() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("solvable", 1, vec![("a", Range::any())]);
+    dependency_provider.add_dependencies("a", 1, vec![("b", Range::higher_than(2))]);
+    dependency_provider.add_dependencies("a", 2, vec![]);
+    dependency_provider.add_dependencies("b", 1, vec![]);
+    dependency_provider.add_dependencies("unsolvable", 1, vec![("b", Range::higher_than(2))]);
+    dependency_provider.add_dependencies("missing", 1, vec![("c", Range::any())]);
+    dependency_provider.add_dependencies(
+        "conflicting",
+        1,
+        vec![("a", Range::exact(1)), ("b", Range::exact(1))],
+    );
+
+    for root in ["solvable", "unsolvable", "conflicting", "b"] {
+        let satisfiable = is_satisfiable(&dependency_provider, root, 1).unwrap();
+        assert_eq!(
+            satisfiable,
+            resolve(&dependency_provider, root, 1).is_ok(),
+            "{}",
+            root
+        );
+    }
+    // Unknown packages are caller mistakes rather than unsatisfiable problems.
+    assert!(matches!(
+        is_satisfiable(&dependency_provider, "missing", 1),
+        Err(PubGrubError::PackageNotFound { package: "c" })
+    ));
+    assert!(matches!(
+        is_satisfiable(&dependency_provider, "unknown", 1),
+        Err(PubGrubError::RootVersionNotAvailable { .. })
+    ));
+    assert!(is_satisfiable(&dependency_provider, "solvable", 1).unwrap());
+    assert!(!is_satisfiable(&dependency_provider, "unsolvable", 1).unwrap());
+}
+
+#[test]
+fn validate_flags_dangling_dependencies() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1, vec![("menu", Range::any())]);
+    dependency_provider.add_dependencies("menu", 1, vec![("icons", Range::any())]);
+    dependency_provider.add_dependencies("menu", 2, vec![]);
+    assert_eq!(
+        dependency_provider.validate(),
+        Err(vec![(&"menu", &NumberVersion(1), &"icons")])
+    );
+
+    let mut packages: Vec<_> = dependency_provider.packages().collect();
+    packages.sort();
+    assert_eq!(packages, vec![&"menu", &"root"]);
+    let versions: Vec<_> = dependency_provider.versions(&"menu").unwrap().collect();
+    assert_eq!(versions, vec![&NumberVersion(1), &NumberVersion(2)]);
+    let menu_dependencies = dependency_provider.dependencies(&"menu", &NumberVersion(1));
+    assert_eq!(menu_dependencies.unwrap().get("icons"), Some(&Range::any()));
+    assert!(dependency_provider
+        .dependencies(&"menu", &NumberVersion(3))
+        .is_none());
+
+    dependency_provider.add_dependencies("icons", 1, vec![]);
+    assert_eq!(dependency_provider.validate(), Ok(()));
+}
+
+#[test]
+fn iter_sorted_selected_dependencies() {
+    let mut solution: SelectedDependencies<&str, NumberVersion> = Map::default();
+    for (package, version) in [("menu", 1), ("dropdown", 2), ("root", 1), ("icons", 3)] {
+        solution.insert(package, NumberVersion(version));
+    }
+    let sorted: Vec<_> = solution.iter_sorted().map(|(p, v)| (*p, v.0)).collect();
+    assert_eq!(
+        sorted,
+        vec![("dropdown", 2), ("icons", 3), ("menu", 1), ("root", 1)]
+    );
+    assert_eq!(solution.get("menu"), Some(&NumberVersion(1)));
+    let packages: Vec<_> = solution.into_keys().collect();
+    assert_eq!(packages.len(), 4);
+}
+
+#[test]
+fn diff_selected_dependencies() {
+    let solution = |selected: &[(&'static str, u32)]| {
+        let mut solution: SelectedDependencies<&str, NumberVersion> = Map::default();
+        for &(package, version) in selected {
+            solution.insert(package, NumberVersion(version));
+        }
+        solution
+    };
+    let old = solution(&[("root", 1), ("menu", 1), ("icons", 2), ("dropdown", 1)]);
+    let new = solution(&[("root", 1), ("menu", 2), ("icons", 2), ("tooltip", 3)]);
+    assert_eq!(
+        old.diff(&new),
+        SolutionDiff {
+            added: vec![("tooltip", NumberVersion(3))],
+            removed: vec![("dropdown", NumberVersion(1))],
+            changed: vec![("menu", NumberVersion(1), NumberVersion(2))],
+        }
+    );
+    assert_eq!(
+        new.diff(&new),
+        SolutionDiff {
+            added: vec![],
+            removed: vec![],
+            changed: vec![],
+        }
+    );
+}
+
+#[test]
+fn closest_solution_only_changes_forced_packages() {
+    let provider = |b_range: Range<NumberVersion>| {
+        let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+        dependency_provider.add_dependencies(
+            "root",
+            1,
+            vec![("a", Range::any()), ("b", b_range), ("c", Range::any())],
+        );
+        for v in 1..4 {
+            dependency_provider.add_dependencies("a", v, vec![]);
+            dependency_provider.add_dependencies("b", v, vec![]);
+            dependency_provider.add_dependencies("c", v, vec![]);
+        }
+        dependency_provider
+    };
+    let mut baseline: SelectedDependencies<&str, NumberVersion> = Map::default();
+    for (package, version) in [("root", 1), ("a", 1), ("b", 1), ("c", 1)] {
+        baseline.insert(package, NumberVersion(version));
+    }
+
+    // Without new constraints, the baseline is kept as is.
+    let solution = resolve_closest_to(&provider(Range::any()), "root", 1, &baseline).unwrap();
+    assert_eq!(solution, baseline);
+
+    // The new constraint on "b" only changes "b", although newer versions exist for all.
+    let dependency_provider = provider(Range::higher_than(2));
+    let solution = resolve_closest_to(&dependency_provider, "root", 1, &baseline).unwrap();
+    assert_eq!(
+        baseline.diff(&solution),
+        SolutionDiff {
+            added: vec![],
+            removed: vec![],
+            changed: vec![("b", NumberVersion(1), NumberVersion(3))],
+        }
+    );
+    let latest = resolve(&dependency_provider, "root", 1).unwrap();
+    assert_eq!(latest.get("a"), Some(&NumberVersion(3)));
+}
+
+#[test]
+fn collect_independent_conflicts() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1,
+        vec![
+            ("a", Range::any()),
+            ("b", Range::any()),
+            ("c", Range::any()),
+            ("d", Range::any()),
+            ("e", Range::any()),
+        ],
+    );
+    // Two unrelated conflicts, on "x" and on "y".
+    dependency_provider.add_dependencies("a", 1, vec![("x", Range::exact(1))]);
+    dependency_provider.add_dependencies("b", 1, vec![("x", Range::exact(2))]);
+    dependency_provider.add_dependencies("c", 1, vec![("y", Range::exact(1))]);
+    dependency_provider.add_dependencies("d", 1, vec![("y", Range::exact(2))]);
+    dependency_provider.add_dependencies("e", 1, vec![]);
+    for v in 1..3 {
+        dependency_provider.add_dependencies("x", v, vec![]);
+        dependency_provider.add_dependencies("y", v, vec![]);
+    }
+
+    let conflicts = collect_conflicts(&dependency_provider, "root", 1).unwrap();
+    let mut reports: Vec<_> = conflicts
+        .iter()
+        .map(DefaultStringReporter::report)
+        .collect();
+    reports.sort_by_key(|report| report.contains("y 2"));
+    assert_eq!(reports.len(), 2, "{:#?}", reports);
+    assert!(reports[0].contains("b 1 depends on x 2"), "{}", reports[0]);
+    assert!(!reports[0].contains("y 2"), "{}", reports[0]);
+    assert!(reports[1].contains("d 1 depends on y 2"), "{}", reports[1]);
+    assert!(!reports[1].contains("x 2"), "{}", reports[1]);
+
+    // Without conflicts, there is nothing to report.
+    dependency_provider.add_dependencies("root", 2, vec![("e", Range::any())]);
+    assert!(collect_conflicts(&dependency_provider, "root", 2)
+        .unwrap()
+        .is_empty());
+}
+
+const HUGE_VERSIONS: u32 = 100_000;
+
+/// Registry where "huge" has versions 0 to 99_999,
+/// which must never be listed beyond the first few newest ones.
+struct HugeRegistry {
+    listed: Cell<usize>,
+}
+
+impl DependencyProvider<&'static str, NumVS> for HugeRegistry {
+    fn choose_package_version<T: Borrow<&'static str>, U: Borrow<NumVS>>(
+        &self,
+        potential_packages: impl Iterator<Item = (T, U)>,
+    ) -> Result<(T, Option<NumberVersion>), Box<dyn Error>> {
+        Ok(choose_package_with_fewest_counted_versions(
+            |p: &&str| -> Box<dyn Iterator<Item = NumberVersion>> {
+                match *p {
+                    "huge" => Box::new((0..HUGE_VERSIONS).rev().map(|v| {
+                        self.listed.set(self.listed.get() + 1);
+                        assert!(self.listed.get() <= 100, "versions fully enumerated");
+                        NumberVersion(v)
+                    })),
+                    _ => Box::new(std::iter::once(NumberVersion(1))),
+                }
+            },
+            // A cheap estimate, never listing versions.
+            |p: &&str, range: &NumVS| match *p {
+                "huge" => HUGE_VERSIONS as usize,
+                _ => usize::from(range.contains(&NumberVersion(1))),
+            },
+            potential_packages,
+        ))
+    }
+
+    fn get_dependencies(
+        &self,
+        package: &&'static str,
+        _version: &NumberVersion,
+    ) -> Result<Dependencies<&'static str, NumVS>, Box<dyn Error>> {
+        let mut dependencies = Map::default();
+        if *package == "root" {
+            dependencies.insert("huge", Range::strictly_lower_than(HUGE_VERSIONS - 5));
+            dependencies.insert("small", Range::any());
+        }
+        Ok(Dependencies::Known(dependencies))
+    }
+}
+
+#[test]
+fn counted_versions_are_listed_lazily() {
+    let registry = HugeRegistry {
+        listed: Cell::new(0),
+    };
+    let solution = resolve(&registry, "root", 1).unwrap();
+    assert_eq!(
+        solution.get("huge"),
+        Some(&NumberVersion(HUGE_VERSIONS - 6))
+    );
+    assert_eq!(solution.get("small"), Some(&NumberVersion(1)));
+    assert!(registry.listed.get() <= 6);
+}
+
+#[test]
+fn feature_dependencies_only_apply_when_enabled() {
+    use pubgrub::feature::{resolve_with_features, FeaturePackage};
+    let base = |p: &'static str| FeaturePackage::Base(p);
+    let mut dependency_provider = OfflineDependencyProvider::<_, NumVS>::new();
+    dependency_provider.add_dependencies(
+        base("root"),
+        1,
+        vec![(base("a"), Range::any()), (base("c"), Range::between(1, 2))],
+    );
+    dependency_provider.add_feature_dependencies(
+        "root",
+        1,
+        "extra",
+        vec![(FeaturePackage::Feature("a", "extra".into()), Range::any())],
+    );
+    dependency_provider.add_dependencies(base("a"), 1, vec![]);
+    // The extra feature of "a" needs a version of "c" excluded by the root.
+    dependency_provider.add_feature_dependencies(
+        "a",
+        1,
+        "extra",
+        vec![(base("c"), Range::between(2, 3))],
+    );
+    dependency_provider.add_dependencies(base("c"), 1, vec![]);
+    dependency_provider.add_dependencies(base("c"), 2, vec![]);
+
+    let solution = resolve_with_features(&dependency_provider, "root", 1, vec![]).unwrap();
+    assert_eq!(solution.get(&base("c")), Some(&NumberVersion(1)));
+    assert!(!solution.contains_key(&FeaturePackage::Feature("a", "extra".into())));
+
+    expect_no_solution(resolve_with_features(
+        &dependency_provider,
+        "root",
+        1,
+        vec!["extra".into()],
+    ));
+}
+
+/// Backtracks a single decision level at a time.
+struct OneLevelBacktrack;
+
+impl BacktrackStrategy for OneLevelBacktrack {
+    fn backtrack_level(&self, _previous_satisfier_level: u32, satisfier_level: u32) -> u32 {
+        satisfier_level - 1
+    }
+}
+
+/// https://github.com/dart-lang/pub/blob/master/doc/solver.md#conflict-resolution-with-a-partial-satisfier
+fn partial_satisfier_provider() -> OfflineDependencyProvider<&'static str, NumVS> {
+    let mut dependency_provider = OfflineDependencyProvider::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1,
+        vec![("foo", Range::between(1, 3)), ("target", Range::exact(2))],
+    );
+    dependency_provider.add_dependencies(
+        "foo",
+        2,
+        vec![("left", Range::exact(1)), ("right", Range::exact(1))],
+    );
+    dependency_provider.add_dependencies("foo", 1, vec![]);
+    dependency_provider.add_dependencies("left", 1, vec![("shared", Range::higher_than(1))]);
+    dependency_provider.add_dependencies(
+        "right",
+        1,
+        vec![("shared", Range::strictly_lower_than(2))],
+    );
+    dependency_provider.add_dependencies("shared", 2, vec![]);
+    dependency_provider.add_dependencies("shared", 1, vec![("target", Range::exact(1))]);
+    dependency_provider.add_dependencies("target", 2, vec![]);
+    dependency_provider.add_dependencies("target", 1, vec![]);
+    dependency_provider
+}
+
+#[test]
+fn default_backtrack_strategy_is_unchanged() {
+    let dependency_provider = partial_satisfier_provider();
+    let solution = resolve(&dependency_provider, "root", 1).unwrap();
+    assert_eq!(
+        resolve_with_backtrack_strategy(&dependency_provider, "root", 1, &PreviousSatisfierLevel)
+            .unwrap(),
+        solution
+    );
+    assert_eq!(solution.get("foo"), Some(&NumberVersion(1)));
+    assert_eq!(solution.get("target"), Some(&NumberVersion(2)));
+}
+
+#[test]
+fn alternate_backtrack_strategy_still_solves() {
+    let dependency_provider = partial_satisfier_provider();
+    let solution =
+        resolve_with_backtrack_strategy(&dependency_provider, "root", 1, &OneLevelBacktrack)
+            .unwrap();
+    assert_eq!(solution, resolve(&dependency_provider, "root", 1).unwrap());
+    let mut failing = partial_satisfier_provider();
+    failing.add_dependencies(
+        "foo",
+        1,
+        vec![("left", Range::exact(1)), ("right", Range::exact(1))],
+    );
+    expect_no_solution(resolve_with_backtrack_strategy(
+        &failing,
+        "root",
+        1,
+        &OneLevelBacktrack,
+    ));
+}
+
+#[test]
+fn root_version_must_be_available() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1, vec![("a", Range::any())]);
+    dependency_provider.add_dependencies("a", 1, vec![]);
+
+    match resolve(&dependency_provider, "root", 2) {
+        Err(PubGrubError::RootVersionNotAvailable { package, version }) => {
+            assert_eq!(package, "root");
+            assert_eq!(version, NumberVersion(2));
+        }
+        other => panic!("expected an unavailable root version, got {:?}", other),
+    }
+    let solution = resolve(&dependency_provider, "root", 1).unwrap();
+    assert_eq!(solution.get("a"), Some(&NumberVersion(1)));
+}
+
+#[test]
+fn stats_are_consistent() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1,
+        vec![("menu", Range::any()), ("icons", Range::any())],
+    );
+    dependency_provider.add_dependencies("menu", 1, vec![("dropdown", Range::any())]);
+    dependency_provider.add_dependencies("dropdown", 1, vec![("icons", Range::any())]);
+    dependency_provider.add_dependencies("icons", 1, vec![]);
+
+    let (solution, stats) = resolve_with_stats(&dependency_provider, "root", 1);
+    let solution = solution.unwrap();
+    assert_eq!(solution, resolve(&dependency_provider, "root", 1).unwrap());
+    assert_eq!(stats.decisions, solution.len());
+    assert!(stats.derivations >= solution.len());
+    assert_eq!(stats.conflicts, 0);
+    assert_eq!(stats.backtracks, 0);
+    assert!(stats.provider_calls > stats.decisions);
+
+    // Backtracking leads to more decisions than packages in the solution.
+    let (solution, stats) = resolve_with_stats(&partial_satisfier_provider(), "root", 1);
+    let solution = solution.unwrap();
+    assert!(stats.decisions > solution.len());
+    assert!(stats.conflicts > 0);
+    assert_eq!(stats.backtracks, stats.conflicts);
+    assert!(stats.derivations >= stats.conflicts);
+}
+
+#[test]
+fn restarts_keep_learned_incompatibilities() {
+    // All "a" packages must share the version of "b",
+    // and only "b" 1 does not depend on a missing "c".
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    let a_packages = ["a0", "a1", "a2", "a3", "a4", "a5"];
+    dependency_provider.add_dependencies("root", 1, a_packages.iter().map(|a| (*a, Range::any())));
+    for v in 1..5 {
+        for a in a_packages {
+            dependency_provider.add_dependencies(a, v, vec![("b", Range::exact(v))]);
+        }
+    }
+    dependency_provider.add_dependencies("b", 1, vec![]);
+    for v in 2..5 {
+        dependency_provider.add_dependencies("b", v, vec![("c", Range::any())]);
+    }
+
+    let policy = RestartPolicy {
+        conflicts: 1,
+        seed: 42,
+    };
+    let (solution, stats) = resolve_with_restarts(&dependency_provider, "root", 1, policy);
+    let solution = solution.unwrap();
+    assert!(stats.restarts > 0);
+    assert_eq!(solution, resolve(&dependency_provider, "root", 1).unwrap());
+    assert!(a_packages
+        .iter()
+        .all(|a| solution.get(a) == Some(&NumberVersion(1))));
+
+    // The same seed gives the same resolution.
+    let (other_solution, other_stats) =
+        resolve_with_restarts(&dependency_provider, "root", 1, policy);
+    assert_eq!(other_solution.unwrap(), solution);
+    assert_eq!(other_stats, stats);
+}
+
+#[test]
+fn restart_seeds_change_decision_order() {
+    // Same registry as above, where all "a" packages tie on their number of versions.
+    let mut offline = OfflineDependencyProvider::<&str, NumVS>::new();
+    let a_packages = ["a0", "a1", "a2", "a3", "a4", "a5"];
+    offline.add_dependencies("root", 1, a_packages.iter().map(|a| (*a, Range::any())));
+    for v in 1..5 {
+        for a in a_packages {
+            offline.add_dependencies(a, v, vec![("b", Range::exact(v))]);
+        }
+    }
+    offline.add_dependencies("b", 1, vec![]);
+    for v in 2..5 {
+        offline.add_dependencies("b", v, vec![("c", Range::any())]);
+    }
+
+    let decisions_with_seed = |seed| {
+        let provider = TestDependencyProvider::new(offline.clone());
+        let policy = RestartPolicy { conflicts: 1, seed };
+        let (solution, stats) = resolve_with_restarts(&provider, "root", 1, policy);
+        solution.unwrap();
+        assert!(stats.restarts > 0);
+        provider.decisions.into_inner()
+    };
+    let first = decisions_with_seed(0);
+    assert!((1..10).any(|seed| decisions_with_seed(seed) != first));
+}
+
+#[test]
+fn assignments_journal() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1, vec![("menu", Range::any())]);
+    dependency_provider.add_dependencies("menu", 1, vec![]);
+    dependency_provider.add_dependencies("menu", 2, vec![("icons", Range::between(1, 3))]);
+    dependency_provider.add_dependencies("icons", 2, vec![]);
+
+    let (solution, assignments) = resolve_with_assignments(&dependency_provider, "root", 1);
+    let solution = solution.unwrap();
+    let decisions: Vec<_> = assignments
+        .iter()
+        .filter_map(|assignment| match assignment {
+            Assignment::Decision(decision) => Some((*decision.package(), *decision.version())),
+            Assignment::Derivation(_) => None,
+        })
+        .collect();
+    assert_eq!(decisions.len(), solution.len());
+    for (package, version) in &decisions {
+        assert_eq!(solution.get(package), Some(version));
+    }
+    // Every package is derived before being decided, at an increasing decision level.
+    for (level, (package, _)) in decisions.iter().enumerate() {
+        let first = assignments
+            .iter()
+            .position(|a| a.package() == package)
+            .unwrap();
+        assert!(
+            matches!(&assignments[first], Assignment::Derivation(d) if d.cause().get(package).is_some())
+        );
+        let decision = assignments
+            .iter()
+            .find(|a| a.package() == package && matches!(a, Assignment::Decision(_)))
+            .unwrap();
+        assert_eq!(decision.decision_level(), level as u32 + 1);
+        assert_eq!(
+            decision.as_term(),
+            Term::Positive(Range::exact(solution[package]))
+        );
+    }
+}
+
+#[test]
+fn terms_after_each_propagation() {
+    // Example of the crate documentation.
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1,
+        vec![("menu", Range::any()), ("icons", Range::any())],
+    );
+    dependency_provider.add_dependencies("menu", 1, vec![("dropdown", Range::any())]);
+    dependency_provider.add_dependencies("dropdown", 1, vec![("icons", Range::any())]);
+    dependency_provider.add_dependencies("icons", 1, vec![]);
+
+    let mut snapshots = Vec::new();
+    resolve_with_terms(&dependency_provider, "root", 1, |terms| {
+        let mut terms: Vec<_> = terms.iter().map(|(p, t)| (*p, t.to_string())).collect();
+        terms.sort_unstable();
+        snapshots.push(terms);
+    })
+    .unwrap();
+    let expected: Vec<Vec<(&str, &str)>> = vec![
+        vec![("root", "1")],
+        vec![("icons", "*"), ("menu", "*"), ("root", "1")],
+        vec![("icons", "1"), ("menu", "*"), ("root", "1")],
+        vec![
+            ("dropdown", "*"),
+            ("icons", "1"),
+            ("menu", "1"),
+            ("root", "1"),
+        ],
+        vec![
+            ("dropdown", "1"),
+            ("icons", "1"),
+            ("menu", "1"),
+            ("root", "1"),
+        ],
+    ];
+    let snapshots: Vec<Vec<_>> = snapshots
+        .iter()
+        .map(|terms| terms.iter().map(|(p, t)| (*p, t.as_str())).collect())
+        .collect();
+    assert_eq!(snapshots, expected);
+}
+
+#[test]
+fn satisfier_of_incompatibility_during_resolution() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1, vec![("menu", Range::any())]);
+    dependency_provider.add_dependencies("menu", 1, vec![]);
+
+    // Satisfied once "menu" 1 is decided, at the second decision level.
+    let root_menu =
+        Incompatibility::forbidden_combination("root", Range::exact(1), "menu", Range::exact(1));
+    let mut satisfiers = Vec::new();
+    resolve_with_terms(&dependency_provider, "root", 1, |terms| {
+        satisfiers.push(
+            terms
+                .satisfier_of(&root_menu)
+                .map(|s| (*s.package(), s.decision_level())),
+        );
+    })
+    .unwrap();
+    assert_eq!(satisfiers.first(), Some(&None));
+    assert_eq!(satisfiers.last(), Some(&Some(("menu", 2))));
+}
+
+#[test]
+fn dev_dependencies_only_apply_to_root() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1, vec![("a", Range::any())]);
+    dependency_provider.add_dev_dependencies("root", 1, vec![("linter", Range::any())]);
+    dependency_provider.add_dependencies("a", 1, vec![]);
+    // The dev dependency of "a" does not even exist.
+    dependency_provider.add_dev_dependencies("a", 1, vec![("test-framework", Range::any())]);
+    dependency_provider.add_dependencies("linter", 1, vec![]);
+
+    let solution = resolve(&dependency_provider, "root", 1).unwrap();
+    let mut packages: Vec<_> = solution.keys().copied().collect();
+    packages.sort_unstable();
+    assert_eq!(packages, vec!["a", "linter", "root"]);
+
+    // Dev dependencies of the root package are required.
+    dependency_provider.add_dev_dependencies("root", 1, vec![("linter", Range::exact(2))]);
+    expect_no_solution(resolve(&dependency_provider, "root", 1));
+}
+
+#[test]
+fn ties_on_version_count_are_broken_by_package_order() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    for package in ["b", "a", "c"] {
+        dependency_provider.add_dependencies(package, 1, vec![]);
+        dependency_provider.add_dependencies(package, 2, vec![]);
+    }
+    let any = Range::any();
+    for order in [["b", "a", "c"], ["c", "b", "a"], ["a", "c", "b"]] {
+        let (package, version) =
+            choose_package_with_fewest_versions_by_package_order::<_, NumVS, _, _, _, _>(
+                |p: &&str| {
+                    let versions: Vec<_> = dependency_provider.versions(p).unwrap().collect();
+                    versions.into_iter().rev().cloned()
+                },
+                order.iter().map(|p| (*p, &any)),
+            );
+        assert_eq!((package, version), ("a", Some(NumberVersion(2))));
+
+        // The offline provider breaks ties the same way.
+        let (package, _) = dependency_provider
+            .choose_package_version(order.iter().map(|p| (*p, &any)))
+            .unwrap();
+        assert_eq!(package, "a");
+    }
+}
+
+#[test]
+fn packages_ranked_by_fewest_versions() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1,
+        vec![
+            ("menu", Range::any()),
+            ("icons", Range::higher_than(2)),
+            ("dropdown", Range::any()),
+        ],
+    );
+    for package in ["menu", "icons", "dropdown"] {
+        for version in 1..=3 {
+            dependency_provider.add_dependencies(package, version, vec![]);
+        }
+    }
+
+    let mut rankings = Vec::new();
+    resolve_with_terms(&dependency_provider, "root", 1, |terms| {
+        rankings.push(terms.ranked_potential_packages(|p| {
+            dependency_provider
+                .versions(p)
+                .into_iter()
+                .flatten()
+                .cloned()
+        }))
+    })
+    .unwrap();
+    // Right after deciding the root package, all its dependencies are undecided.
+    let ranking = &rankings[1];
+    assert_eq!(ranking.len(), 3);
+    assert_eq!(
+        ranking[0],
+        ("icons", Term::Positive(Range::higher_than(2)), 2)
+    );
+    assert!(ranking.windows(2).all(|w| w[0].2 <= w[1].2));
+    // Nothing is left to rank once every package is decided.
+    assert!(rankings.last().unwrap().is_empty());
+}
+
+#[test]
+fn verify_lock_reports_first_violation() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1, vec![("menu", Range::any())]);
+    dependency_provider.add_dependencies("menu", 1, vec![("icons", Range::exact(1))]);
+    dependency_provider.add_dependencies("menu", 2, vec![("icons", Range::exact(2))]);
+    dependency_provider.add_dependencies("icons", 1, vec![]);
+    dependency_provider.add_dependencies("icons", 2, vec![]);
+
+    let mut lock: Map<_, NumberVersion> = Map::default();
+    lock.insert("menu", 2.into());
+    lock.insert("icons", 2.into());
+    assert!(verify_lock(&dependency_provider, "root", 1, &lock).is_ok());
+
+    // menu 2 now requires a version of icons that is not the locked one.
+    dependency_provider.add_dependencies("menu", 2, vec![("icons", Range::exact(3))]);
+    match verify_lock(&dependency_provider, "root", 1, &lock) {
+        Err(PubGrubError::LockViolation {
+            package,
+            version,
+            dependency,
+            locked,
+        }) => {
+            assert_eq!((package, version), ("menu", NumberVersion(2)));
+            assert_eq!((dependency, locked), ("icons", Some(NumberVersion(2))));
+        }
+        other => panic!("expected a lock violation, got {:?}", other),
+    }
+
+    lock.remove("icons");
+    let err = verify_lock(&dependency_provider, "root", 1, &lock).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "package 'menu' at version 2 depends on package 'icons', which is missing from the lock"
+    );
+}
+
+#[test]
+fn verify_lock_checks_root_dev_dependencies_and_unknown_dependencies() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1, vec![]);
+    dependency_provider.add_dev_dependencies("root", 1, vec![("linter", Range::higher_than(2))]);
+    dependency_provider.add_dependencies("linter", 1, vec![]);
+
+    let mut lock: Map<_, NumberVersion> = Map::default();
+    lock.insert("linter", 1.into());
+    match verify_lock(&dependency_provider, "root", 1, &lock) {
+        Err(PubGrubError::LockViolation {
+            package,
+            dependency,
+            locked,
+            ..
+        }) => {
+            assert_eq!(package, "root");
+            assert_eq!((dependency, locked), ("linter", Some(NumberVersion(1))));
+        }
+        other => panic!("expected a lock violation, got {:?}", other),
+    }
+
+    // The dependencies of linter 2 were never registered.
+    lock.insert("linter", 2.into());
+    assert!(matches!(
+        verify_lock(&dependency_provider, "root", 1, &lock),
+        Err(PubGrubError::UnknownDependencies { package: "linter", version })
+            if version == NumberVersion(2)
+    ));
+}
+
+#[test]
+fn verify_lock_accepts_solutions_with_virtual_packages() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1, vec![]);
+    dependency_provider.add_dependencies("postgres", 1, vec![("libpq", Range::exact(2))]);
+    dependency_provider.add_dependencies("libpq", 1, vec![]);
+    dependency_provider.add_dependencies("sqlite", 1, vec![]);
+    dependency_provider.require_any_of("root", 1, "AnyOf#1", &["postgres", "sqlite"]);
+
+    // The virtual package is absent from the solution, and so from the lock.
+    let lock = resolve(&dependency_provider, "root", 1).unwrap();
+    assert!(verify_lock(&dependency_provider, "root", 1, &lock).is_ok());
+
+    // Without any of the alternatives locked, the first one is reported.
+    let lock: Map<_, NumberVersion> = Map::default();
+    match verify_lock(&dependency_provider, "root", 1, &lock) {
+        Err(PubGrubError::LockViolation {
+            package,
+            dependency,
+            locked,
+            ..
+        }) => {
+            assert_eq!(package, "AnyOf#1");
+            assert_eq!((dependency, locked), ("sqlite", None));
+        }
+        other => panic!("expected a lock violation, got {:?}", other),
+    }
+}
+
+#[test]
+fn verify_lock_checks_known_incompatibilities() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1, vec![("a", Range::any()), ("b", Range::any())]);
+    dependency_provider.add_dependencies("a", 1, vec![]);
+    dependency_provider.add_dependencies("b", 1, vec![]);
+    dependency_provider.add_dependencies("b", 2, vec![]);
+
+    let mut lock: Map<_, NumberVersion> = Map::default();
+    lock.insert("a", 1.into());
+    lock.insert("b", 1.into());
+    assert!(verify_lock(&dependency_provider, "root", 1, &lock).is_ok());
+
+    dependency_provider.forbid_combination("a", Range::any(), "b", Range::exact(1));
+    let err = verify_lock(&dependency_provider, "root", 1, &lock).unwrap_err();
+    assert!(matches!(
+        err,
+        PubGrubError::LockIncompatibility(External::ForbiddenCombination(..))
+    ));
+
+    lock.insert("b", 2.into());
+    assert!(verify_lock(&dependency_provider, "root", 1, &lock).is_ok());
+}
+
+#[test]
+fn resolution_modes_pick_highest_or_lowest_versions() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1, vec![("menu", Range::between(1, 4))]);
+    for version in 0..5 {
+        dependency_provider.add_dependencies("menu", version, vec![]);
+    }
+
+    let latest = resolve(&dependency_provider, "root", 1).unwrap();
+    assert_eq!(latest.get("menu"), Some(&NumberVersion(3)));
+
+    dependency_provider.set_resolution_mode(ResolutionMode::Minimal);
+    let minimal = resolve(&dependency_provider, "root", 1).unwrap();
+    assert_eq!(minimal.get("menu"), Some(&NumberVersion(1)));
+
+    dependency_provider.set_resolution_mode(ResolutionMode::Latest);
+    assert_eq!(resolve(&dependency_provider, "root", 1).unwrap(), latest);
+}
+
+#[test]
+fn error_messages_name_packages() {
+    type Err = PubGrubError<&'static str, NumVS>;
+    let v = NumberVersion(1);
+    let messages: Vec<(Err, &str)> = vec![
+        (
+            PubGrubError::ErrorRetrievingDependencies {
+                package: "foo",
+                version: v,
+                source: "network is down".into(),
+            },
+            "error retrieving dependencies of package 'foo' at version 1: network is down",
+        ),
+        (
+            PubGrubError::DependencyOnTheEmptySet {
+                package: "foo",
+                version: v,
+                dependent: "bar",
+            },
+            "package 'foo' at version 1 depends on package 'bar' with an empty set of versions",
+        ),
+        (
+            PubGrubError::SelfDependency {
+                package: "foo",
+                version: v,
+            },
+            "package 'foo' at version 1 depends on itself",
+        ),
+        (
+            PubGrubError::PackageNotFound { package: "foo" },
+            "package 'foo' does not exist",
+        ),
+        (
+            PubGrubError::LockViolation {
+                package: "foo",
+                version: v,
+                dependency: "bar",
+                locked: Some(NumberVersion(2)),
+            },
+            "package 'foo' at version 1 depends on package 'bar', \
+             which is locked at the incompatible version 2",
+        ),
+        (
+            PubGrubError::RootVersionNotAvailable {
+                package: "foo",
+                version: v,
+            },
+            "root package 'foo' is not available at version 1",
+        ),
+        (
+            PubGrubError::ErrorChoosingPackageVersion("no registry".into()),
+            "error choosing a package version: no registry",
+        ),
+        (
+            PubGrubError::ErrorInShouldCancel("timeout".into()),
+            "resolution was cancelled: timeout",
+        ),
+    ];
+    for (err, message) in messages {
+        assert_eq!(err.to_string(), message);
+    }
+}
+
+#[test]
+fn error_source_is_the_provider_error() {
+    let err: PubGrubError<&str, NumVS> = PubGrubError::ErrorRetrievingDependencies {
+        package: "foo",
+        version: NumberVersion(1),
+        source: "network is down".into(),
+    };
+    assert_eq!(err.source().unwrap().to_string(), "network is down");
+    let err: PubGrubError<&str, NumVS> = PubGrubError::ErrorInShouldCancel("timeout".into());
+    assert_eq!(err.source().unwrap().to_string(), "timeout");
+    let err: PubGrubError<&str, NumVS> = PubGrubError::PackageNotFound { package: "foo" };
+    assert!(err.source().is_none());
+}
+
+#[test]
+fn max_depth_limits_explored_dependencies() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1,
+        vec![("menu", Range::any()), ("icons", Range::any())],
+    );
+    dependency_provider.add_dependencies("menu", 1, vec![("dropdown", Range::any())]);
+    dependency_provider.add_dependencies("dropdown", 1, vec![("icons", Range::any())]);
+    dependency_provider.add_dependencies("icons", 1, vec![]);
+
+    let solution = resolve_with_max_depth(&dependency_provider, "root", 1, 1).unwrap();
+    let mut packages: Vec<_> = solution.keys().copied().collect();
+    packages.sort_unstable();
+    assert_eq!(packages, vec!["icons", "menu", "root"]);
+
+    let solution = resolve_with_max_depth(&dependency_provider, "root", 1, 0).unwrap();
+    assert_eq!(solution.keys().copied().collect::<Vec<_>>(), vec!["root"]);
+
+    let solution = resolve_with_max_depth(&dependency_provider, "root", 1, 2).unwrap();
+    assert_eq!(solution, resolve(&dependency_provider, "root", 1).unwrap());
+}
+
+#[test]
+fn learned_incompatibilities_of_a_failure() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1,
+        vec![("menu", Range::any()), ("icons", Range::exact(1))],
+    );
+    dependency_provider.add_dependencies("menu", 1, vec![("icons", Range::exact(2))]);
+    dependency_provider.add_dependencies("icons", 1, vec![]);
+    dependency_provider.add_dependencies("icons", 2, vec![]);
+
+    let (result, incompatibilities) =
+        resolve_with_learned_incompatibilities(&dependency_provider, "root", 1);
+    expect_no_solution(result);
+    let root_dependencies: Vec<_> = incompatibilities
+        .iter()
+        .filter_map(|incompat| match incompat.external() {
+            Some(External::FromDependencyOf("root", _, dependency, _)) => Some(dependency),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(root_dependencies.len(), 2);
+    assert!(root_dependencies.contains(&"menu"));
+    assert!(root_dependencies.contains(&"icons"));
+    assert!(incompatibilities
+        .iter()
+        .any(|incompat| incompat.external().is_none()));
+}
+
+#[test]
+fn dependencies_of_successive_versions_are_merged() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1, vec![("menu", Range::any())]);
+    for v in 1..4 {
+        dependency_provider.add_dependencies("menu", v, vec![("icons", Range::higher_than(2))]);
+    }
+    dependency_provider.add_dependencies("menu", 0, vec![]);
+    dependency_provider.add_dependencies("icons", 1, vec![]);
+
+    let (result, incompatibilities) =
+        resolve_with_learned_incompatibilities(&dependency_provider, "root", 1);
+    assert_eq!(result.unwrap().get("menu"), Some(&NumberVersion(0)));
+    assert!(incompatibilities.iter().any(|incompat| matches!(
+        incompat.external(),
+        Some(External::FromDependencyOf("menu", range, "icons", _)) if range == Range::between(1, 4)
+    )));
+}
+
+#[test]
+fn tree_records_all_dependents() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1,
+        vec![("menu", Range::any()), ("icons", Range::any())],
+    );
+    dependency_provider.add_dependencies("menu", 1, vec![("dropdown", Range::any())]);
+    dependency_provider.add_dependencies("dropdown", 1, vec![("icons", Range::higher_than(1))]);
+    dependency_provider.add_dependencies("icons", 1, vec![]);
+
+    let (solution, dependents) = resolve_with_tree(&dependency_provider, "root", 1).unwrap();
+    assert_eq!(solution, resolve(&dependency_provider, "root", 1).unwrap());
+    let icons = &dependents["icons"];
+    assert_eq!(icons.len(), 2);
+    assert_eq!(icons["root"], Range::any());
+    assert_eq!(icons["dropdown"], Range::higher_than(1));
+    assert_eq!(
+        dependents["dropdown"].keys().collect::<Vec<_>>(),
+        vec![&"menu"]
+    );
+    assert!(!dependents.contains_key("root"));
+}
+
+#[test]
+fn relaxation_widens_only_the_blocking_constraint() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1,
+        vec![
+            ("menu", Range::any()),
+            ("icons", Range::exact(1)),
+            ("intl", Range::exact(1)),
+        ],
+    );
+    dependency_provider.add_dependencies("menu", 1, vec![("icons", Range::exact(2))]);
+    dependency_provider.add_dependencies("icons", 1, vec![]);
+    dependency_provider.add_dependencies("icons", 2, vec![]);
+    dependency_provider.add_dependencies("intl", 1, vec![]);
+    assert!(resolve(&dependency_provider, "root", 1).is_err());
+
+    let (solution, relaxed) = resolve_with_relaxation(&dependency_provider, "root", 1).unwrap();
+    assert_eq!(relaxed, vec![("icons", Range::exact(1))]);
+    assert_eq!(solution.get("icons"), Some(&NumberVersion(2)));
+    assert_eq!(solution.get("intl"), Some(&NumberVersion(1)));
+
+    // Nothing is relaxed when there is already a solution.
+    dependency_provider.add_dependencies("menu", 1, vec![]);
+    let (_, relaxed) = resolve_with_relaxation(&dependency_provider, "root", 1).unwrap();
+    assert!(relaxed.is_empty());
+}
+
+#[test]
+fn relaxation_may_need_several_constraints() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1,
+        vec![("menu", Range::exact(1)), ("icons", Range::exact(1))],
+    );
+    dependency_provider.add_dependencies("menu", 1, vec![("missing", Range::any())]);
+    dependency_provider.add_dependencies("menu", 2, vec![("icons", Range::exact(2))]);
+    dependency_provider.add_dependencies("icons", 1, vec![]);
+    dependency_provider.add_dependencies("icons", 2, vec![]);
+
+    let (solution, mut relaxed) = resolve_with_relaxation(&dependency_provider, "root", 1).unwrap();
+    relaxed.sort_by_key(|(p, _)| *p);
+    assert_eq!(
+        relaxed,
+        vec![("icons", Range::exact(1)), ("menu", Range::exact(1))]
+    );
+    assert_eq!(solution.get("menu"), Some(&NumberVersion(2)));
+}
+
+thread_local! {
+    static PACKAGE_CLONES: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Package with a long name, counting how many times it is cloned.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct LongNamePackage(String);
+
+impl Clone for LongNamePackage {
+    fn clone(&self) -> Self {
+        PACKAGE_CLONES.with(|clones| clones.set(clones.get() + 1));
+        Self(self.0.clone())
+    }
+}
+
+impl std::fmt::Display for LongNamePackage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[test]
+fn interned_packages_are_cloned_less() {
+    let name = |i: u32| LongNamePackage(format!("org.example.some.deeply.nested.package{}", i));
+    let mut dependency_provider = OfflineDependencyProvider::<LongNamePackage, NumVS>::new();
+    for i in 0..20 {
+        for v in 0..3 {
+            dependency_provider.add_dependencies(
+                name(i),
+                v,
+                vec![
+                    (name(i + 1), Range::any()),
+                    (name(i + 2), Range::lower_than(v)),
+                ],
+            );
+        }
+    }
+    dependency_provider.add_dependencies(name(20), 0, vec![]);
+    dependency_provider.add_dependencies(name(21), 0, vec![]);
+
+    let clones_before = PACKAGE_CLONES.with(Cell::get);
+    let solution = resolve(&dependency_provider, name(0), 2).unwrap();
+    let clones = PACKAGE_CLONES.with(Cell::get) - clones_before;
+
+    let clones_before = PACKAGE_CLONES.with(Cell::get);
+    let interned_solution = resolve_interned(&dependency_provider, name(0), 2).unwrap();
+    let interned_clones = PACKAGE_CLONES.with(Cell::get) - clones_before;
+
+    assert_eq!(interned_solution, solution);
+    assert!(
+        interned_clones < clones,
+        "{} clones with interning, {} without",
+        interned_clones,
+        clones
+    );
+}
+
+#[test]
+fn layered_provider_prefers_local_overrides() {
+    let mut local = OfflineDependencyProvider::<&str, NumVS>::new();
+    local.add_dependencies("icons", 1, vec![]);
+    let mut registry = OfflineDependencyProvider::<&str, NumVS>::new();
+    registry.add_dependencies("root", 1, vec![("menu", Range::any())]);
+    registry.add_dependencies("menu", 1, vec![("icons", Range::any())]);
+    registry.add_dependencies("icons", 1, vec![]);
+    registry.add_dependencies("icons", 2, vec![]);
+
+    let layered = LayeredDependencyProvider::new(vec![local.clone(), registry.clone()]);
+    let solution = resolve(&layered, "root", 1).unwrap();
+    assert_eq!(solution["icons"], NumberVersion(1));
+    assert_eq!(layered.source(&"icons", &NumberVersion(1)), Some(0));
+    assert_eq!(layered.source(&"menu", &NumberVersion(1)), Some(1));
+    assert_eq!(layered.source(&"icons", &NumberVersion(3)), None);
+
+    // Versions of lower layers are still used when higher ones have none in range.
+    registry.add_dependencies("menu", 1, vec![("icons", Range::higher_than(2))]);
+    let layered = LayeredDependencyProvider::new(vec![local.clone(), registry.clone()]);
+    let solution = resolve(&layered, "root", 1).unwrap();
+    assert_eq!(solution["icons"], NumberVersion(2));
+
+    // Layers must agree on the dependencies of a version they both provide.
+    local.add_dependencies("icons", 2, vec![("menu", Range::any())]);
+    let layered = LayeredDependencyProvider::new(vec![local, registry]);
+    match resolve(&layered, "root", 1) {
+        Err(PubGrubError::ErrorRetrievingDependencies {
+            package, source, ..
+        }) => {
+            assert_eq!(package, "icons");
+            assert_eq!(
+                source.to_string(),
+                "layers 0 and 1 declare different dependencies for icons 2"
+            );
+        }
+        other => panic!("expected conflicting layers, got {:?}", other),
+    }
+}
+
+#[test]
+fn layered_provider_checks_dev_dependencies_of_every_layer() {
+    let mut local = OfflineDependencyProvider::<&str, NumVS>::new();
+    local.add_dependencies("root", 1, vec![]);
+    local.add_dev_dependencies("root", 1, vec![("linter", Range::any())]);
+    let mut registry = OfflineDependencyProvider::<&str, NumVS>::new();
+    registry.add_dependencies("linter", 1, vec![]);
+
+    // Layers without that version do not take part.
+    let layered = LayeredDependencyProvider::new(vec![local.clone(), registry.clone()]);
+    let solution = resolve(&layered, "root", 1).unwrap();
+    assert_eq!(solution["linter"], NumberVersion(1));
+
+    // Layers must agree on the dev dependencies of a version they both provide.
+    registry.add_dependencies("root", 1, vec![]);
+    let layered = LayeredDependencyProvider::new(vec![local, registry]);
+    match resolve(&layered, "root", 1) {
+        Err(PubGrubError::ErrorRetrievingDependencies {
+            package, source, ..
+        }) => {
+            assert_eq!(package, "root");
+            assert_eq!(
+                source.to_string(),
+                "layers 0 and 1 declare different dev dependencies for root 1"
+            );
+        }
+        other => panic!("expected conflicting layers, got {:?}", other),
+    }
+}
+
+#[test]
+fn layered_provider_reports_missing_packages() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1, vec![("typo", Range::any())]);
+    let layered = LayeredDependencyProvider::new(vec![dependency_provider]);
+    assert!(matches!(
+        resolve(&layered, "root", 1),
+        Err(PubGrubError::PackageNotFound { package: "typo" })
+    ));
+}
+
+#[test]
+fn forbidden_package_is_reported() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1, vec![("menu", Range::any())]);
+    dependency_provider.add_dependencies("menu", 1, vec![("telemetry", Range::any())]);
+    dependency_provider.add_dependencies("telemetry", 1, vec![]);
+    assert!(resolve(&dependency_provider, "root", 1).is_ok());
+
+    dependency_provider.forbid_package("root", 1, "telemetry");
+    let derivation_tree = expect_no_solution(resolve(&dependency_provider, "root", 1));
+    let report = DefaultStringReporter::report(&derivation_tree);
+    assert!(
+        report.contains("root 1 forbids telemetry"),
+        "unexpected report: {}",
+        report
+    );
+
+    // The prohibition only applies to the version of the package forbidding it.
+    assert!(resolve(&dependency_provider, "menu", 1).is_ok());
+}
+
+#[test]
+fn require_any_of_alternatives() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1, vec![]);
+    // "postgres" depends on a package that does not exist in any version.
+    dependency_provider.add_dependencies("postgres", 1, vec![("libpq", Range::exact(2))]);
+    dependency_provider.add_dependencies("libpq", 1, vec![]);
+    dependency_provider.add_dependencies("sqlite", 1, vec![]);
+    dependency_provider.add_dependencies("sqlite", 2, vec![]);
+    dependency_provider.require_any_of("root", 1, "AnyOf#1", &["postgres", "sqlite"]);
+
+    let solution = resolve(&dependency_provider, "root", 1).unwrap();
+    assert_eq!(solution.get("sqlite"), Some(&2.into()));
+    // The virtual package is not part of the solution.
+    assert!(!solution.contains_key("AnyOf#1"));
+    assert!(!solution.contains_key("postgres"));
+    assert!(!solution.contains_key("libpq"));
+}
+
+#[test]
+fn require_any_of_before_adding_dependencies() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.require_any_of("root", 1, "AnyOf#1", &["postgres", "sqlite"]);
+    // Registering the dependencies afterwards keeps the requirement.
+    dependency_provider.add_dependencies("root", 1, vec![("menu", Range::any())]);
+    dependency_provider.add_dependencies("menu", 1, vec![]);
+    dependency_provider.add_dependencies("sqlite", 1, vec![]);
+
+    let solution = resolve(&dependency_provider, "root", 1).unwrap();
+    assert_eq!(solution.get("menu"), Some(&1.into()));
+    assert_eq!(solution.get("sqlite"), Some(&1.into()));
+    assert_eq!(solution.len(), 3);
+}
+
+#[test]
+fn require_any_of_unsatisfiable_alternatives() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1, vec![]);
+    dependency_provider.add_dependencies("postgres", 1, vec![("libpq", Range::exact(2))]);
+    dependency_provider.add_dependencies("libpq", 1, vec![]);
+    dependency_provider.add_dependencies("sqlite", 1, vec![("libsqlite", Range::exact(3))]);
+    dependency_provider.add_dependencies("libsqlite", 1, vec![]);
+    dependency_provider.require_any_of("root", 1, "AnyOf#1", &["postgres", "sqlite"]);
+
+    let derivation_tree = expect_no_solution(resolve(&dependency_provider, "root", 1));
+    let report = DefaultStringReporter::report(&derivation_tree);
+    assert!(
+        report.contains("AnyOf#1 0 depends on postgres")
+            && report.contains("AnyOf#1 1 depends on sqlite")
+            && report.contains("root 1 depends on AnyOf#1"),
+        "unexpected report: {}",
+        report
+    );
+}
+
+#[test]
+fn exhausted_versions_are_reported_as_no_versions() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1, vec![("icons", Range::higher_than(2))]);
+    dependency_provider.add_dependencies("icons", 1, vec![]);
+    let derivation_tree = expect_no_solution(resolve(&dependency_provider, "root", 1));
+    assert!(minimal_core(&derivation_tree)
+        .contains(&External::NoVersions("icons", Range::higher_than(2))));
+    let report = DefaultStringReporter::report(&derivation_tree);
+    assert!(
+        report.contains("there is no version of icons in 2 <= v"),
+        "unexpected report: {}",
+        report
+    );
+}
+
+/// Reporter listing the external causes of a failure as a markdown list.
+struct MarkdownReporter;
+
+impl Reporter<&'static str, NumVS> for MarkdownReporter {
+    type Output = String;
+
+    fn report(derivation_tree: &DerivationTree<&'static str, NumVS>) -> String {
+        match derivation_tree {
+            DerivationTree::External(external) => format!("- {}\n", external),
+            DerivationTree::Derived(derived) => {
+                Self::report(&derived.cause1) + &Self::report(&derived.cause2)
+            }
+        }
+    }
+}
+
+#[test]
+fn default_and_custom_reporters() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies("root", 1, vec![("menu", Range::any())]);
+    dependency_provider.add_dependencies("menu", 1, vec![("icons", Range::exact(2))]);
+    dependency_provider.add_dependencies("icons", 1, vec![]);
+    let derivation_tree = expect_no_solution(resolve(&dependency_provider, "root", 1));
+
+    assert_eq!(
+        DefaultStringReporter::report(&derivation_tree),
+        "Because there is no version of icons in 2 and menu 1 depends on icons 2, \
+         menu 1 is forbidden.\n\
+         And because there is no version of menu in 0 ∪ [2, ∞) and root 1 depends on menu, \
+         root 1 is forbidden."
+    );
+    assert_eq!(
+        MarkdownReporter::report(&derivation_tree),
+        "- there is no version of menu in 0 ∪ [2, ∞)\n\
+         - there is no version of icons in 2\n\
+         - menu 1 depends on icons 2\n\
+         - root 1 depends on menu\n"
+    );
+}
+
+#[test]
+fn conditional_dependency_on_selected_version() {
+    let conditional_provider = |a_versions: &[u32]| {
+        let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+        dependency_provider.add_dependencies("root", 1, vec![("a", Range::any())]);
+        for &v in a_versions {
+            dependency_provider.add_dependencies("a", v, vec![]);
+        }
+        dependency_provider.add_dependencies("b", 1, vec![]);
+        dependency_provider.add_conditional_dependency(
+            "root",
+            1,
+            ("a", Range::higher_than(2)),
+            ("b", Range::any()),
+        );
+        dependency_provider
+    };
+
+    // "b" is pulled in only because "a" resolved to a version >= 2.
+    let solution = resolve(&conditional_provider(&[1, 2]), "root", 1).unwrap();
+    assert_eq!(solution.get("a"), Some(&2.into()));
+    assert_eq!(solution.get("b"), Some(&1.into()));
+
+    // "b" is not needed when "a" resolved to a version < 2.
+    let solution = resolve(&conditional_provider(&[1]), "root", 1).unwrap();
+    assert_eq!(solution.get("a"), Some(&1.into()));
+    assert!(!solution.contains_key("b"));
+}
+
+#[test]
+fn lockstep_group_members_share_a_version() {
+    let lockstep_provider = |tokio_range: Range<NumberVersion>| {
+        let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+        dependency_provider.add_dependencies(
+            "root",
+            1,
+            vec![
+                ("tokio", tokio_range),
+                ("tokio-macros", Range::strictly_lower_than(3)),
+            ],
+        );
+        for v in 1..4 {
+            dependency_provider.add_dependencies("tokio", v, vec![]);
+            dependency_provider.add_dependencies("tokio-macros", v, vec![]);
+        }
+        dependency_provider.add_version_lockstep_group(&["tokio", "tokio-macros"]);
+        dependency_provider
+    };
+
+    // The constraint on "tokio-macros" also pulls "tokio" down to a common version.
+    let solution = resolve(&lockstep_provider(Range::any()), "root", 1).unwrap();
+    assert_eq!(solution.get("tokio"), Some(&2.into()));
+    assert_eq!(solution.get("tokio-macros"), Some(&2.into()));
+
+    // There is no common version when "tokio" has to be at least 3.
+    let derivation_tree = expect_no_solution(resolve(
+        &lockstep_provider(Range::higher_than(3)),
+        "root",
+        1,
+    ));
+    let report = DefaultStringReporter::report(&derivation_tree);
+    assert!(report.contains("tokio 3 is in lockstep with tokio-macros"));
+}
+
+#[test]
+fn decision_log_of_the_doc_example() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, NumVS>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        1,
+        vec![("menu", Range::any()), ("icons", Range::any())],
+    );
+    dependency_provider.add_dependencies("menu", 1, vec![("dropdown", Range::any())]);
+    dependency_provider.add_dependencies("dropdown", 1, vec![("icons", Range::any())]);
+    dependency_provider.add_dependencies("icons", 1, vec![]);
+
+    let (solution, decisions) = resolve_with_decisions(&dependency_provider, "root", 1);
+    assert_eq!(solution.unwrap().len(), 4);
+    let decisions: Vec<_> = decisions.into_iter().map(|(p, v)| (p, v.0)).collect();
+    assert_eq!(
+        decisions,
+        vec![("root", 1), ("icons", 1), ("menu", 1), ("dropdown", 1)]
+    );
+}
@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use std::fmt;
+
+use pubgrub::error::PubGrubError;
+use pubgrub::solver::{resolve, OfflineDependencyProvider};
+use pubgrub::type_aliases::Map;
+use pubgrub::version_set::VersionSet;
+
+/// A set of versions in `0..32`, each version being one bit of a `u32`.
+/// Versions outside of that interval can never be selected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BitSet(u32);
+
+impl BitSet {
+    fn from_versions(versions: &[u8]) -> Self {
+        versions
+            .iter()
+            .fold(Self::empty(), |set, &v| set.union(&Self::singleton(v)))
+    }
+}
+
+impl VersionSet for BitSet {
+    type V = u8;
+
+    fn empty() -> Self {
+        Self(0)
+    }
+
+    fn singleton(v: u8) -> Self {
+        Self(1u32.checked_shl(v as u32).unwrap_or(0))
+    }
+
+    fn complement(&self) -> Self {
+        Self(!self.0)
+    }
+
+    fn intersection(&self, other: &Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    fn contains(&self, v: &u8) -> bool {
+        self.intersection(&Self::singleton(*v)) != Self::empty()
+    }
+}
+
+impl fmt::Display for BitSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let versions: Vec<_> = (0..32u8)
+            .filter(|v| self.contains(v))
+            .map(|v| v.to_string())
+            .collect();
+        write!(f, "{{{}}}", versions.join(", "))
+    }
+}
+
+#[test]
+fn bit_set_solves_small_problem() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, BitSet>::new();
+    dependency_provider.add_dependencies(
+        "root",
+        0,
+        vec![("a", BitSet::from_versions(&[1, 3])), ("b", BitSet::full())],
+    );
+    dependency_provider.add_dependencies("a", 1, vec![("b", BitSet::from_versions(&[2]))]);
+    dependency_provider.add_dependencies("a", 2, vec![]);
+    dependency_provider.add_dependencies("a", 3, vec![("b", BitSet::from_versions(&[5]))]);
+    dependency_provider.add_dependencies("b", 2, vec![]);
+    dependency_provider.add_dependencies("b", 4, vec![]);
+
+    let mut expected_solution = Map::default();
+    expected_solution.insert("root", 0);
+    expected_solution.insert("a", 1);
+    expected_solution.insert("b", 2);
+
+    let computed_solution = resolve(&dependency_provider, "root", 0).unwrap();
+    assert_eq!(expected_solution, computed_solution);
+}
+
+#[test]
+fn bit_set_reports_no_solution() {
+    let mut dependency_provider = OfflineDependencyProvider::<&str, BitSet>::new();
+    dependency_provider.add_dependencies("root", 0, vec![("a", BitSet::from_versions(&[7]))]);
+    dependency_provider.add_dependencies("a", 6, vec![]);
+
+    assert!(matches!(
+        resolve(&dependency_provider, "root", 0),
+        Err(PubGrubError::NoSolution(_))
+    ));
+}